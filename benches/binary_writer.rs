@@ -0,0 +1,63 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use plist::{
+    stream::{BinaryWriter, Writer},
+    Dictionary, Value,
+};
+
+/// Builds a plist resembling a large Xcode project, with many dictionaries sharing keys.
+fn large_value(objects: usize) -> Value {
+    let objects: Dictionary = (0..objects)
+        .map(|i| {
+            let mut object = Dictionary::new();
+            object.insert("isa".to_owned(), "PBXFileReference".into());
+            object.insert("lastKnownFileType".to_owned(), "sourcecode.swift".into());
+            object.insert("path".to_owned(), format!("Source/File{i}.swift").into());
+            object.insert("sourceTree".to_owned(), "<group>".into());
+            object.insert("fileEncoding".to_owned(), 4.into());
+            object.insert(
+                "children".to_owned(),
+                Value::Array(vec![(i as u64).into(); 4]),
+            );
+            (format!("{i:024X}"), Value::Dictionary(object))
+        })
+        .collect();
+
+    let mut root = Dictionary::new();
+    root.insert("objectVersion".to_owned(), 56.into());
+    root.insert("objects".to_owned(), objects.into());
+    root.into()
+}
+
+fn binary_writer(c: &mut Criterion) {
+    let mut group = c.benchmark_group("binary_writer");
+    for objects in [1_000, 100_000] {
+        let value = large_value(objects);
+        let mut buf = Vec::new();
+        value.to_writer_binary(&mut buf).unwrap();
+        group.throughput(Throughput::Bytes(buf.len() as u64));
+
+        group.bench_function(format!("events/{objects}"), |b| {
+            b.iter_batched_ref(
+                || Vec::with_capacity(buf.len()),
+                |buf| {
+                    let mut writer = BinaryWriter::new(buf);
+                    for event in value.events() {
+                        writer.write(event).unwrap();
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(format!("value/{objects}"), |b| {
+            b.iter_batched_ref(
+                || Vec::with_capacity(buf.len()),
+                |buf| value.to_writer_binary(buf).unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, binary_writer);
+criterion_main!(benches);
@@ -0,0 +1,88 @@
+//! Benchmarks reading a synthetic iTunes library export.
+//!
+//! The `xml_reader` group reads a library of about 44 MB and the `xml_reader_large` group one of
+//! about 440 MB, which needs several GB of memory. Run a single group with
+//! `cargo bench --bench xml_reader -- xml_reader/` or `-- xml_reader_large/`. Set
+//! `PLIST_BENCH_TRACKS` to change the number of tracks in the large library.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use plist::{arena::Document, Value, ValueRef};
+use std::fmt::Write;
+
+/// Builds an XML plist resembling an iTunes library export of about `tracks` kilobytes.
+fn itunes_library(tracks: usize) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \
+         \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\">\n<dict>\n\
+         \t<key>Major Version</key><integer>1</integer>\n\
+         \t<key>Application Version</key><string>12.9.5.5</string>\n\
+         \t<key>Tracks</key>\n\t<dict>\n",
+    );
+    for id in 0..tracks {
+        write!(
+            xml,
+            "\t\t<key>{id}</key>\n\t\t<dict>\n\
+             \t\t\t<key>Track ID</key><integer>{id}</integer>\n\
+             \t\t\t<key>Name</key><string>Track {id}</string>\n\
+             \t\t\t<key>Artist</key><string>Artist {}</string>\n\
+             \t\t\t<key>Album</key><string>Album &amp; Friends {}</string>\n\
+             \t\t\t<key>Genre</key><string>Alternative</string>\n\
+             \t\t\t<key>Kind</key><string>MPEG audio file</string>\n\
+             \t\t\t<key>Size</key><integer>{}</integer>\n\
+             \t\t\t<key>Total Time</key><integer>{}</integer>\n\
+             \t\t\t<key>Date Added</key><date>2019-03-14T21:07:54Z</date>\n\
+             \t\t\t<key>Bit Rate</key><integer>320</integer>\n\
+             \t\t\t<key>Play Count</key><integer>{}</integer>\n\
+             \t\t\t<key>Compilation</key><true/>\n\
+             \t\t\t<key>Persistent ID</key><string>{id:016X}</string>\n\
+             \t\t\t<key>Location</key><string>file:///Users/me/Music/iTunes/Track%20{id}.mp3</string>\n\
+             \t\t\t<key>Artwork</key>\n\t\t\t<data>\n\
+             \t\t\tiVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA\n\
+             \t\t\t60e6kgAAAABJRU5ErkJggg==\n\t\t\t</data>\n\
+             \t\t</dict>\n",
+            id % 1000,
+            id % 100,
+            id * 4096,
+            id * 1000,
+            id % 50,
+        )
+        .unwrap();
+    }
+    xml.push_str("\t</dict>\n</dict>\n</plist>\n");
+    xml
+}
+
+fn xml_reader(c: &mut Criterion) {
+    bench_reads(c, "xml_reader", 50_000);
+}
+
+fn xml_reader_large(c: &mut Criterion) {
+    let tracks = std::env::var("PLIST_BENCH_TRACKS")
+        .ok()
+        .and_then(|tracks| tracks.parse().ok())
+        .unwrap_or(500_000);
+    bench_reads(c, "xml_reader_large", tracks);
+}
+
+fn bench_reads(c: &mut Criterion, name: &str, tracks: usize) {
+    let xml = itunes_library(tracks);
+
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(xml.len() as u64));
+    group.bench_function("value", |b| {
+        b.iter(|| Value::from_reader_xml(xml.as_bytes()).unwrap())
+    });
+    group.bench_function("value_ref", |b| {
+        b.iter(|| ValueRef::from_slice(xml.as_bytes()).unwrap())
+    });
+    group.bench_function("document", |b| {
+        b.iter(|| Document::from_slice(xml.as_bytes()).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, xml_reader, xml_reader_large);
+criterion_main!(benches);
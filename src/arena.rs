@@ -0,0 +1,783 @@
+//! An arena-allocated, read-only plist document.
+//!
+//! A [`Value`] allocates every array, dictionary, string and data blob separately. A [`Document`]
+//! instead stores all of its nodes in a single buffer, and all of its strings and data in two
+//! more, so reading a plist into a `Document` makes a handful of allocations however many values
+//! it contains. This suits reading large numbers of small plists. Each distinct dictionary key is
+//! stored only once however many dictionaries it appears in, which cuts the memory used by large
+//! plists made of many similar dictionaries.
+//!
+//! A `Document` is navigated with [`Node`], [`Array`] and [`Dictionary`] handles which borrow
+//! from it, and can be converted to a `Value` with [`Document::to_value`].
+//!
+//! ```
+//! use plist::arena::Document;
+//!
+//! let document = Document::from_file("tests/data/xml.plist").unwrap();
+//! let root = document.root().as_dictionary().unwrap();
+//!
+//! let author = root.get("Author").and_then(|author| author.as_string());
+//! assert_eq!(author, Some("William Shakespeare"));
+//!
+//! let lines = root.get("Lines").and_then(|lines| lines.as_array()).unwrap();
+//! assert_eq!(lines.len(), 2);
+//! assert_eq!(
+//!     lines.get(1).and_then(|line| line.as_string()),
+//!     Some("Full of sound and fury, signifying nothing.")
+//! );
+//! ```
+
+use std::{
+    collections::{
+        hash_map::{Entry, RandomState},
+        HashMap,
+    },
+    fmt::{self, Debug},
+    fs::File,
+    hash::BuildHasher,
+    io::{BufReader, Read, Seek},
+    path::Path,
+};
+
+use crate::{
+    error::{self, Error, EventKind},
+    stream::{Event, Reader, SliceReader},
+    tree_builder::{Tree, TreeBuilder},
+    Date, Integer, Uid, Value,
+};
+
+/// A read-only plist whose nodes, strings and data are stored in a few shared buffers.
+#[derive(Clone)]
+pub struct Document {
+    /// Nodes in the order in which they were completed, so the root node is last.
+    nodes: Vec<Slot>,
+    /// The indices into `nodes` of the elements of each array, and the alternating keys and
+    /// values of each dictionary.
+    children: Vec<usize>,
+    strings: String,
+    data: Vec<u8>,
+}
+
+#[derive(Clone, Copy)]
+struct Span {
+    start: usize,
+    len: usize,
+}
+
+impl Span {
+    fn range(self) -> std::ops::Range<usize> {
+        self.start..self.start + self.len
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Slot {
+    Array(Span),
+    Dictionary(Span),
+    Boolean(bool),
+    Data(Span),
+    Date(Date),
+    Real(f64),
+    Integer(Integer),
+    String(Span),
+    Uid(Uid),
+}
+
+impl Slot {
+    fn kind(&self) -> EventKind {
+        match self {
+            Slot::Array(_) => EventKind::StartArray,
+            Slot::Dictionary(_) => EventKind::StartDictionary,
+            Slot::Boolean(_) => EventKind::Boolean,
+            Slot::Data(_) => EventKind::Data,
+            Slot::Date(_) => EventKind::Date,
+            Slot::Real(_) => EventKind::Real,
+            Slot::Integer(_) => EventKind::Integer,
+            Slot::String(_) => EventKind::String,
+            Slot::Uid(_) => EventKind::Uid,
+        }
+    }
+}
+
+impl Document {
+    /// Reads a `Document` from a plist file of any encoding.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Document, Error> {
+        let file = File::open(path).map_err(error::from_io_without_position)?;
+        Document::from_reader(BufReader::new(file))
+    }
+
+    /// Reads a `Document` from a seekable byte stream containing a plist of any encoding.
+    pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Document, Error> {
+        Document::build(Reader::new(reader))
+    }
+
+    /// Reads a `Document` from a slice containing a plist of any encoding.
+    pub fn from_slice(bytes: &[u8]) -> Result<Document, Error> {
+        Document::build(SliceReader::new(bytes))
+    }
+
+    /// Builds a `Document` from an `Event` iterator.
+    #[cfg(feature = "enable_unstable_features_that_may_break_with_minor_version_bumps")]
+    pub fn from_events<'event, T>(events: T) -> Result<Document, Error>
+    where
+        T: IntoIterator<Item = Result<Event<'event>, Error>>,
+    {
+        Document::build(events)
+    }
+
+    fn build<'event, T>(events: T) -> Result<Document, Error>
+    where
+        T: IntoIterator<Item = Result<Event<'event>, Error>>,
+    {
+        let tree = DocumentTree {
+            document: Document {
+                nodes: Vec::new(),
+                children: Vec::new(),
+                strings: String::new(),
+                data: Vec::new(),
+            },
+            pending: Vec::new(),
+            keys: KeyInterner::default(),
+            entries: HashMap::new(),
+        };
+        // The root node is the last node to be completed so it need not be recorded.
+        let (tree, _) = TreeBuilder::build(tree, events)?;
+        Ok(tree.document)
+    }
+
+    /// Returns the root node of the document.
+    pub fn root(&self) -> Node<'_> {
+        Node {
+            document: self,
+            index: self.nodes.len() - 1,
+        }
+    }
+
+    /// Converts the document into a `Value`.
+    pub fn to_value(&self) -> Value {
+        self.root().to_value()
+    }
+
+    fn node(&self, index: usize) -> Node<'_> {
+        Node {
+            document: self,
+            index,
+        }
+    }
+}
+
+/// Stores each distinct dictionary key in a `Document` once.
+#[derive(Default)]
+struct KeyInterner {
+    hasher: RandomState,
+    /// The span of each distinct key, by its hash. A key whose hash is already used by a
+    /// different key is stored under the next unused hash.
+    spans: HashMap<u64, Span>,
+}
+
+impl KeyInterner {
+    /// Returns the span of an earlier key equal to the key at `span`, which must be at the end of
+    /// `strings`, and removes the key from `strings`. Returns `span` if there is no such key.
+    ///
+    /// Equal keys therefore always have the same span.
+    fn intern(&mut self, strings: &mut String, span: Span) -> Span {
+        let key = &strings[span.range()];
+        let mut hash = self.hasher.hash_one(key);
+        loop {
+            match self.spans.get(&hash) {
+                Some(&interned) if strings[interned.range()] == *key => {
+                    strings.truncate(span.start);
+                    return interned;
+                }
+                Some(_) => hash = hash.wrapping_add(1),
+                None => {
+                    self.spans.insert(hash, span);
+                    return span;
+                }
+            }
+        }
+    }
+}
+
+/// Builds a `Document` with a [`TreeBuilder`].
+///
+/// Nodes are the indices of slots in `document.nodes`, and open collections are the start in
+/// `pending` of their children.
+struct DocumentTree {
+    document: Document,
+    /// The indices of completed nodes which belong to a collection which is still open.
+    pending: Vec<usize>,
+    keys: KeyInterner,
+    /// The position in `pending` of the entry for each key of the dictionary being ended, by the
+    /// start and length of the key's interned span. An empty key's span starts where the next
+    /// key's does, so the start alone does not identify a key.
+    entries: HashMap<(usize, usize), usize>,
+}
+
+impl DocumentTree {
+    fn push_slot(&mut self, slot: Slot) -> usize {
+        self.document.nodes.push(slot);
+        self.document.nodes.len() - 1
+    }
+
+    fn end_collection(&mut self, start: usize) -> Span {
+        let span = Span {
+            start: self.document.children.len(),
+            len: self.pending.len() - start,
+        };
+        self.document.children.extend(self.pending.drain(start..));
+        span
+    }
+}
+
+impl<'event> Tree<'event> for DocumentTree {
+    type Node = usize;
+    type Array = usize;
+    type Dictionary = usize;
+    type Key = usize;
+
+    fn start_array(&mut self, _: Option<u64>) -> usize {
+        self.pending.len()
+    }
+
+    fn start_dictionary(&mut self, _: Option<u64>) -> usize {
+        self.pending.len()
+    }
+
+    fn push(&mut self, _: &mut usize, node: usize) {
+        self.pending.push(node);
+    }
+
+    fn insert(&mut self, _: &mut usize, key: usize, node: usize) {
+        self.pending.push(key);
+        self.pending.push(node);
+    }
+
+    fn end_array(&mut self, start: usize) -> usize {
+        let span = self.end_collection(start);
+        self.push_slot(Slot::Array(span))
+    }
+
+    fn end_dictionary(&mut self, start: usize) -> usize {
+        // As in `Value`, a repeated key keeps the position of its first entry and the value of
+        // its last.
+        self.entries.clear();
+        let mut end = start;
+        for i in (start..self.pending.len()).step_by(2) {
+            let (key, value) = (self.pending[i], self.pending[i + 1]);
+            let Slot::String(key_span) = self.document.nodes[key] else {
+                unreachable!("dictionary keys are strings")
+            };
+            match self.entries.entry((key_span.start, key_span.len)) {
+                Entry::Occupied(entry) => self.pending[entry.get() + 1] = value,
+                Entry::Vacant(entry) => {
+                    entry.insert(end);
+                    self.pending[end] = key;
+                    self.pending[end + 1] = value;
+                    end += 2;
+                }
+            }
+        }
+        self.pending.truncate(end);
+        let span = self.end_collection(start);
+        self.push_slot(Slot::Dictionary(span))
+    }
+
+    fn leaf(&mut self, event: Event<'event>) -> usize {
+        let document = &mut self.document;
+        let slot = match event {
+            Event::Boolean(value) => Slot::Boolean(value),
+            Event::Data(value) => {
+                let start = document.data.len();
+                document.data.extend_from_slice(&value);
+                Slot::Data(Span {
+                    start,
+                    len: value.len(),
+                })
+            }
+            Event::Date(value) => Slot::Date(value),
+            Event::Integer(value) => Slot::Integer(value),
+            Event::Real(value) => Slot::Real(value),
+            Event::String(value) => {
+                let start = document.strings.len();
+                document.strings.push_str(&value);
+                Slot::String(Span {
+                    start,
+                    len: value.len(),
+                })
+            }
+            Event::Uid(value) => Slot::Uid(value),
+            Event::StartArray(_) | Event::StartDictionary(_) | Event::EndCollection => {
+                unreachable!("collection events are not leaves")
+            }
+        };
+        self.push_slot(slot)
+    }
+
+    fn key(&mut self, node: usize) -> Result<usize, usize> {
+        // Keys are converted as soon as they are read, so a key's string is the last in
+        // `strings`.
+        let Slot::String(span) = self.document.nodes[node] else {
+            return Err(node);
+        };
+        let span = self.keys.intern(&mut self.document.strings, span);
+        self.document.nodes[node] = Slot::String(span);
+        Ok(node)
+    }
+
+    fn kind(&self, &node: &usize) -> EventKind {
+        self.document.nodes[node].kind()
+    }
+}
+
+impl Debug for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.root().fmt(f)
+    }
+}
+
+impl From<&Document> for Value {
+    fn from(document: &Document) -> Value {
+        document.to_value()
+    }
+}
+
+/// A value in a [`Document`].
+#[derive(Clone, Copy)]
+pub struct Node<'a> {
+    document: &'a Document,
+    index: usize,
+}
+
+impl<'a> Node<'a> {
+    fn slot(&self) -> Slot {
+        self.document.nodes[self.index]
+    }
+
+    /// If the node is an Array, returns a handle to it.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_array(&self) -> Option<Array<'a>> {
+        match self.slot() {
+            Slot::Array(span) => Some(Array {
+                document: self.document,
+                children: &self.document.children[span.range()],
+            }),
+            _ => None,
+        }
+    }
+
+    /// If the node is a Dictionary, returns a handle to it.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_dictionary(&self) -> Option<Dictionary<'a>> {
+        match self.slot() {
+            Slot::Dictionary(span) => Some(Dictionary {
+                document: self.document,
+                children: &self.document.children[span.range()],
+            }),
+            _ => None,
+        }
+    }
+
+    /// If the node is a Boolean, returns the associated `bool`.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_boolean(&self) -> Option<bool> {
+        match self.slot() {
+            Slot::Boolean(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// If the node is a Data, returns the associated bytes.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_data(&self) -> Option<&'a [u8]> {
+        match self.slot() {
+            Slot::Data(span) => Some(&self.document.data[span.range()]),
+            _ => None,
+        }
+    }
+
+    /// If the node is a Date, returns the associated `Date`.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_date(&self) -> Option<Date> {
+        match self.slot() {
+            Slot::Date(date) => Some(date),
+            _ => None,
+        }
+    }
+
+    /// If the node is a Real, returns the associated `f64`.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_real(&self) -> Option<f64> {
+        match self.slot() {
+            Slot::Real(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// If the node is a signed Integer, returns the associated `i64`.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_signed_integer(&self) -> Option<i64> {
+        match self.slot() {
+            Slot::Integer(v) => v.as_signed(),
+            _ => None,
+        }
+    }
+
+    /// If the node is an unsigned Integer, returns the associated `u64`.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_unsigned_integer(&self) -> Option<u64> {
+        match self.slot() {
+            Slot::Integer(v) => v.as_unsigned(),
+            _ => None,
+        }
+    }
+
+    /// If the node is a String, returns the associated `str`.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_string(&self) -> Option<&'a str> {
+        match self.slot() {
+            Slot::String(span) => Some(&self.document.strings[span.range()]),
+            _ => None,
+        }
+    }
+
+    /// If the node is a Uid, returns the associated `Uid`.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_uid(&self) -> Option<Uid> {
+        match self.slot() {
+            Slot::Uid(u) => Some(u),
+            _ => None,
+        }
+    }
+
+    /// Converts the node and its children into a `Value`.
+    pub fn to_value(&self) -> Value {
+        match self.slot() {
+            Slot::Array(_) => Value::Array(
+                self.as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|node| node.to_value())
+                    .collect(),
+            ),
+            Slot::Dictionary(_) => Value::Dictionary(
+                self.as_dictionary()
+                    .into_iter()
+                    .flatten()
+                    .map(|(key, node)| (key, node.to_value()))
+                    .collect(),
+            ),
+            Slot::Boolean(value) => Value::Boolean(value),
+            Slot::Data(span) => Value::Data(self.document.data[span.range()].to_vec()),
+            Slot::Date(value) => Value::Date(value),
+            Slot::Real(value) => Value::Real(value),
+            Slot::Integer(value) => Value::Integer(value),
+            Slot::String(span) => Value::String(self.document.strings[span.range()].to_owned()),
+            Slot::Uid(value) => Value::Uid(value),
+        }
+    }
+}
+
+impl Debug for Node<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.slot() {
+            Slot::Array(_) => f.debug_list().entries(self.as_array().unwrap()).finish(),
+            Slot::Dictionary(_) => f
+                .debug_map()
+                .entries(self.as_dictionary().unwrap())
+                .finish(),
+            Slot::Boolean(value) => value.fmt(f),
+            Slot::Data(span) => self.document.data[span.range()].fmt(f),
+            Slot::Date(value) => value.fmt(f),
+            Slot::Real(value) => value.fmt(f),
+            Slot::Integer(value) => value.fmt(f),
+            Slot::String(span) => self.document.strings[span.range()].fmt(f),
+            Slot::Uid(value) => value.fmt(f),
+        }
+    }
+}
+
+/// An array in a [`Document`].
+#[derive(Clone, Copy)]
+pub struct Array<'a> {
+    document: &'a Document,
+    children: &'a [usize],
+}
+
+impl<'a> Array<'a> {
+    /// Returns the element at `index`.
+    pub fn get(&self, index: usize) -> Option<Node<'a>> {
+        let index = *self.children.get(index)?;
+        Some(self.document.node(index))
+    }
+
+    /// Returns the number of elements in the array.
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Returns true if the array contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Gets an iterator over the elements of the array.
+    pub fn iter(&self) -> ArrayIter<'a> {
+        ArrayIter {
+            document: self.document,
+            iter: self.children.iter(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for Array<'a> {
+    type Item = Node<'a>;
+    type IntoIter = ArrayIter<'a>;
+
+    fn into_iter(self) -> ArrayIter<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the elements of an [`Array`].
+pub struct ArrayIter<'a> {
+    document: &'a Document,
+    iter: std::slice::Iter<'a, usize>,
+}
+
+impl<'a> Iterator for ArrayIter<'a> {
+    type Item = Node<'a>;
+
+    fn next(&mut self) -> Option<Node<'a>> {
+        let index = *self.iter.next()?;
+        Some(self.document.node(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl ExactSizeIterator for ArrayIter<'_> {}
+
+/// A dictionary in a [`Document`].
+///
+/// Entries are kept in the order in which they were read. As in a [`crate::Dictionary`], a key
+/// which is repeated keeps the position of its first entry and the value of its last. Looking up
+/// a key compares it with each key in turn.
+#[derive(Clone, Copy)]
+pub struct Dictionary<'a> {
+    document: &'a Document,
+    children: &'a [usize],
+}
+
+impl<'a> Dictionary<'a> {
+    /// Returns the value corresponding to the key.
+    pub fn get(&self, key: &str) -> Option<Node<'a>> {
+        self.iter().find(|(k, _)| *k == key).map(|(_, value)| value)
+    }
+
+    /// Returns true if the dictionary contains a value for the specified key.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Returns the number of entries in the dictionary.
+    pub fn len(&self) -> usize {
+        self.children.len() / 2
+    }
+
+    /// Returns true if the dictionary contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// Gets an iterator over the entries of the dictionary.
+    pub fn iter(&self) -> DictionaryIter<'a> {
+        DictionaryIter {
+            document: self.document,
+            iter: self.children.chunks_exact(2),
+        }
+    }
+}
+
+impl<'a> IntoIterator for Dictionary<'a> {
+    type Item = (&'a str, Node<'a>);
+    type IntoIter = DictionaryIter<'a>;
+
+    fn into_iter(self) -> DictionaryIter<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of a [`Dictionary`].
+pub struct DictionaryIter<'a> {
+    document: &'a Document,
+    iter: std::slice::ChunksExact<'a, usize>,
+}
+
+impl<'a> Iterator for DictionaryIter<'a> {
+    type Item = (&'a str, Node<'a>);
+
+    fn next(&mut self) -> Option<(&'a str, Node<'a>)> {
+        let entry = self.iter.next()?;
+        let key = self.document.node(entry[0]).as_string()?;
+        Some((key, self.document.node(entry[1])))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl ExactSizeIterator for DictionaryIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_value() {
+        for path in [
+            "./tests/data/xml.plist",
+            "./tests/data/binary.plist",
+            "./tests/data/ascii-sample.plist",
+            "./tests/data/binary_NSKeyedArchiver.plist",
+        ] {
+            let document = Document::from_file(path).unwrap();
+            assert_eq!(
+                document.to_value(),
+                Value::from_file(path).unwrap(),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn navigation() {
+        let document = Document::from_slice(
+            b"<plist><dict>
+                <key>a</key><array><integer>1</integer><data>AQI=</data><dict/></array>
+                <key>b</key><real>1.5</real>
+            </dict></plist>",
+        )
+        .unwrap();
+        let root = document.root().as_dictionary().unwrap();
+        assert_eq!(root.len(), 2);
+        assert_eq!(
+            root.iter().map(|(key, _)| key).collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+        assert_eq!(root.get("b").and_then(|b| b.as_real()), Some(1.5));
+        assert!(!root.contains_key("c"));
+
+        let a = root.get("a").unwrap().as_array().unwrap();
+        assert_eq!(a.get(0).and_then(|n| n.as_unsigned_integer()), Some(1));
+        assert_eq!(a.get(1).and_then(|n| n.as_data()), Some(&[1, 2][..]));
+        assert!(a.get(2).unwrap().as_dictionary().unwrap().is_empty());
+        assert!(a.get(3).is_none());
+        assert_eq!(
+            format!("{document:?}"),
+            "{\"a\": [1, [1, 2], {}], \"b\": 1.5}"
+        );
+    }
+
+    #[test]
+    fn keys_are_interned() {
+        let value: Value = (0..100)
+            .map(|i| {
+                let mut dict = crate::Dictionary::new();
+                dict.insert("Name".to_owned(), format!("Item {i}").into());
+                dict.insert("Na\u{ef}ve".to_owned(), "Name".into());
+                dict
+            })
+            .collect();
+        let mut buf = Vec::new();
+        value.to_writer_xml(&mut buf).unwrap();
+
+        let document = Document::from_slice(&buf).unwrap();
+        assert_eq!(document.to_value(), value);
+        // Each key is stored once. Values are not interned.
+        let values_len: usize = (0..100).map(|i| format!("Item {i}").len() + 4).sum();
+        assert_eq!(
+            document.strings.len(),
+            values_len + "Name".len() + "Na\u{ef}ve".len()
+        );
+    }
+
+    #[test]
+    fn repeated_keys_match_value() {
+        let xml = b"<plist><dict>
+            <key>a</key><integer>1</integer>
+            <key>b</key><integer>2</integer>
+            <key>a</key><integer>3</integer>
+        </dict></plist>";
+        let document = Document::from_slice(xml).unwrap();
+        let value = Value::from_reader(std::io::Cursor::new(xml)).unwrap();
+        assert_eq!(document.to_value(), value);
+
+        let root = document.root().as_dictionary().unwrap();
+        assert_eq!(root.len(), 2);
+        assert_eq!(root.get("a").and_then(|a| a.as_signed_integer()), Some(3));
+        assert_eq!(
+            root.iter().map(|(key, _)| key).collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+    }
+
+    #[test]
+    fn empty_key_next_to_other_keys() {
+        let xml = b"<plist><dict>
+            <key></key><integer>1</integer>
+            <key>abc</key><integer>2</integer>
+            <key></key><integer>3</integer>
+        </dict></plist>";
+        let document = Document::from_slice(xml).unwrap();
+        let value = Value::from_reader(std::io::Cursor::new(xml)).unwrap();
+        assert_eq!(document.to_value(), value);
+
+        let root = document.root().as_dictionary().unwrap();
+        assert_eq!(root.get("").and_then(|a| a.as_signed_integer()), Some(3));
+        assert_eq!(root.get("abc").and_then(|a| a.as_signed_integer()), Some(2));
+    }
+
+    #[test]
+    fn invalid_event_streams() {
+        let build = |events: Vec<Event<'static>>| Document::build(events.into_iter().map(Ok));
+
+        let err = build(vec![Event::StartDictionary(None), Event::Integer(1.into())]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "UnexpectedEventType { expected: DictionaryKeyOrEndCollection, found: Integer }"
+        );
+        let err = build(vec![
+            Event::StartDictionary(None),
+            Event::String("a".into()),
+            Event::EndCollection,
+        ])
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "UnexpectedEventType { expected: ValueOrStartCollection, found: EndCollection }"
+        );
+        let err = build(vec![Event::Boolean(true), Event::Boolean(false)]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ExpectedEndOfEventStream { found: Boolean }"
+        );
+        let err = build(vec![Event::StartArray(None)]).unwrap_err();
+        assert_eq!(err.to_string(), "UnexpectedEndOfEventStream");
+        let err = build(vec![]).unwrap_err();
+        assert_eq!(err.to_string(), "UnexpectedEndOfEventStream");
+    }
+}
@@ -0,0 +1,122 @@
+//! A git merge driver which performs a three-way merge of plist files.
+//!
+//! Install with `cargo install plist --features merge_driver`, then configure git to use it:
+//!
+//! ```text
+//! # .git/config or ~/.gitconfig
+//! [merge "plist"]
+//!     name = plist three-way merge
+//!     driver = plist-merge-driver %O %A %B
+//!
+//! # .gitattributes
+//! *.plist merge=plist
+//! *.pbxproj merge=plist
+//! ```
+//!
+//! The merged plist is written over the `%A` file in the same binary or XML encoding.
+//!
+//! ASCII plists, which include Xcode project files, cannot be written back in their original
+//! encoding without losing their comments and layout. These are instead merged line by line with
+//! `git merge-file`, as they would be without this driver.
+//!
+//! If binary or XML plists cannot be merged the conflicts are printed and `%A` is left unmodified
+//! for git to report as conflicting. Conflicts in ASCII plists are marked in `%A` as usual.
+
+use std::{
+    fs,
+    process::{Command, ExitCode},
+};
+
+use plist::{ArrayMergeStrategy, ThreeWayMergeOptions, Value};
+
+const USAGE: &str = "usage: plist-merge-driver [--arrays conflict|by-index|union] BASE OURS THEIRS";
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(message) => {
+            eprintln!("plist-merge-driver: {message}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Returns `true` if the files were merged without conflicts.
+fn run() -> Result<bool, String> {
+    let mut options = ThreeWayMergeOptions::new();
+    let mut paths = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--arrays" => {
+                let strategy = match args.next().as_deref() {
+                    Some("conflict") => ArrayMergeStrategy::Conflict,
+                    Some("by-index") => ArrayMergeStrategy::ByIndex,
+                    Some("union") => ArrayMergeStrategy::Union,
+                    _ => return Err(USAGE.to_owned()),
+                };
+                options = options.arrays(strategy);
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(true);
+            }
+            _ => paths.push(arg),
+        }
+    }
+    let [base_path, ours_path, theirs_path] = paths.as_slice() else {
+        return Err(USAGE.to_owned());
+    };
+
+    let ours_bytes = fs::read(ours_path).map_err(|err| format!("{ours_path}: {err}"))?;
+    let is_binary = ours_bytes.starts_with(b"bplist");
+    if !is_binary && !is_xml(&ours_bytes) {
+        return merge_text(base_path, ours_path, theirs_path);
+    }
+
+    let read = |path: &str| Value::from_file(path).map_err(|err| format!("{path}: {err}"));
+    let base = read(base_path)?;
+    let ours = Value::from_reader(std::io::Cursor::new(&ours_bytes))
+        .map_err(|err| format!("{ours_path}: {err}"))?;
+    let theirs = read(theirs_path)?;
+
+    let merged = match plist::three_way_merge_with_options(&base, &ours, &theirs, &options) {
+        Ok(merged) => merged,
+        Err(conflicts) => {
+            eprint!("{conflicts}");
+            return Ok(false);
+        }
+    };
+
+    let written = if is_binary {
+        merged.to_file_binary(ours_path)
+    } else {
+        merged.to_file_xml(ours_path)
+    };
+    written.map_err(|err| format!("{ours_path}: {err}"))?;
+    Ok(true)
+}
+
+/// Returns `true` if `bytes` starts with an XML declaration, document type or element.
+fn is_xml(bytes: &[u8]) -> bool {
+    let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+    let start = bytes.iter().position(|b| !b.is_ascii_whitespace());
+    start.is_some_and(|start| bytes[start..].starts_with(b"<"))
+}
+
+/// Merges the files line by line with `git merge-file`, writing the result over `ours_path`.
+///
+/// Returns `true` if the files were merged without conflicts.
+fn merge_text(base_path: &str, ours_path: &str, theirs_path: &str) -> Result<bool, String> {
+    let status = Command::new("git")
+        .args(["merge-file", ours_path, base_path, theirs_path])
+        .status()
+        .map_err(|err| format!("failed to run git merge-file: {err}"))?;
+    match status.code() {
+        Some(0) => Ok(true),
+        // `git merge-file` exits with the number of conflicts, or a negative value on error.
+        Some(1..=127) => Ok(false),
+        _ => Err(format!("git merge-file failed: {status}")),
+    }
+}
@@ -0,0 +1,348 @@
+//! `TryFrom` conversions from a [`Value`] into Rust types.
+//!
+//! A conversion fails with an [`Error`] naming the expected and found kinds of value if the value
+//! is of the wrong kind, or with an error of category [`ErrorCategory::InvalidValue`] if an
+//! integer or real is out of range. Errors in the elements of a collection include their key
+//! path.
+//!
+//! [`ErrorCategory::InvalidValue`]: crate::ErrorCategory::InvalidValue
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::{BuildHasher, Hash},
+};
+
+use crate::{
+    error::{Error, ErrorKind, EventKind, KeyPathSegment},
+    Date, Dictionary, Integer, Uid, Value,
+};
+
+fn unexpected_type(expected: EventKind, value: &Value) -> Error {
+    ErrorKind::UnexpectedEventType {
+        expected,
+        found: EventKind::of_value(value),
+    }
+    .without_position()
+}
+
+/// Implements `TryFrom<&Value>` using the given pattern, and `TryFrom<Value>` using that.
+macro_rules! impl_try_from_value_ref {
+    ($ty:ty, $expected:ident, $pattern:pat => $convert:expr) => {
+        impl TryFrom<&Value> for $ty {
+            type Error = Error;
+
+            fn try_from(value: &Value) -> Result<$ty, Error> {
+                match value {
+                    $pattern => $convert,
+                    _ => Err(unexpected_type(EventKind::$expected, value)),
+                }
+            }
+        }
+
+        impl TryFrom<Value> for $ty {
+            type Error = Error;
+
+            fn try_from(value: Value) -> Result<$ty, Error> {
+                <$ty>::try_from(&value)
+            }
+        }
+    };
+}
+
+impl_try_from_value_ref!(bool, Boolean, Value::Boolean(value) => Ok(*value));
+impl_try_from_value_ref!(Date, Date, Value::Date(value) => Ok(*value));
+impl_try_from_value_ref!(Uid, Uid, Value::Uid(value) => Ok(*value));
+impl_try_from_value_ref!(Integer, Integer, Value::Integer(value) => Ok(*value));
+impl_try_from_value_ref!(f64, Real, Value::Real(value) => Ok(*value));
+impl_try_from_value_ref!(f32, Real, Value::Real(value) => {
+    let real = *value as f32;
+    if real.is_infinite() && value.is_finite() {
+        Err(ErrorKind::RealOutOfRange.without_position())
+    } else {
+        Ok(real)
+    }
+});
+
+macro_rules! impl_try_from_value_for_integers {
+    ($($ty:ty),*) => {
+        $(
+            impl_try_from_value_ref!($ty, Integer, Value::Integer(value) => {
+                <$ty>::try_from(value.as_i128())
+                    .map_err(|_| ErrorKind::IntegerOutOfRange.without_position())
+            });
+        )*
+    };
+}
+
+impl_try_from_value_for_integers!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl TryFrom<Value> for String {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<String, Error> {
+        match value {
+            Value::String(value) => Ok(value),
+            _ => Err(unexpected_type(EventKind::String, &value)),
+        }
+    }
+}
+
+impl TryFrom<&Value> for String {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<String, Error> {
+        match value {
+            Value::String(value) => Ok(value.clone()),
+            _ => Err(unexpected_type(EventKind::String, value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for Dictionary {
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Dictionary, Error> {
+        match value {
+            Value::Dictionary(value) => Ok(value),
+            _ => Err(unexpected_type(EventKind::StartDictionary, &value)),
+        }
+    }
+}
+
+impl TryFrom<&Value> for Dictionary {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Dictionary, Error> {
+        match value {
+            Value::Dictionary(value) => Ok(value.clone()),
+            _ => Err(unexpected_type(EventKind::StartDictionary, value)),
+        }
+    }
+}
+
+/// Converts an array into a `Vec`.
+///
+/// Data is not converted, as this impl also covers `Vec<u8>`, which is converted from an array of
+/// integers. Use [`Value::into_data`] or [`Value::as_data`] to get the bytes of data.
+impl<T> TryFrom<Value> for Vec<T>
+where
+    T: TryFrom<Value, Error = Error>,
+{
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<Vec<T>, Error> {
+        match value {
+            Value::Array(array) => array
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    T::try_from(value)
+                        .map_err(|err| err.with_outer_key_path_segment(KeyPathSegment::Index(i)))
+                })
+                .collect(),
+            _ => Err(unexpected_type(EventKind::StartArray, &value)),
+        }
+    }
+}
+
+/// Converts an array into a `Vec`.
+///
+/// Data is not converted, as this impl also covers `Vec<u8>`, which is converted from an array of
+/// integers. Use [`Value::into_data`] or [`Value::as_data`] to get the bytes of data.
+impl<T> TryFrom<&Value> for Vec<T>
+where
+    T: for<'a> TryFrom<&'a Value, Error = Error>,
+{
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Vec<T>, Error> {
+        match value {
+            Value::Array(array) => array
+                .iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    T::try_from(value)
+                        .map_err(|err| err.with_outer_key_path_segment(KeyPathSegment::Index(i)))
+                })
+                .collect(),
+            _ => Err(unexpected_type(EventKind::StartArray, value)),
+        }
+    }
+}
+
+/// Converts the entries of a dictionary, collecting them into a map.
+fn try_from_dictionary<T, C>(value: Value) -> Result<C, Error>
+where
+    T: TryFrom<Value, Error = Error>,
+    C: FromIterator<(String, T)>,
+{
+    let Value::Dictionary(dict) = value else {
+        return Err(unexpected_type(EventKind::StartDictionary, &value));
+    };
+    dict.into_iter()
+        .map(|(key, value)| match T::try_from(value) {
+            Ok(value) => Ok((key, value)),
+            Err(err) => Err(err.with_outer_key_path_segment(KeyPathSegment::Key(&key))),
+        })
+        .collect()
+}
+
+/// Converts the entries of a dictionary, collecting them into a map.
+fn try_from_dictionary_ref<T, C>(value: &Value) -> Result<C, Error>
+where
+    T: for<'a> TryFrom<&'a Value, Error = Error>,
+    C: FromIterator<(String, T)>,
+{
+    let Value::Dictionary(dict) = value else {
+        return Err(unexpected_type(EventKind::StartDictionary, value));
+    };
+    dict.iter()
+        .map(|(key, value)| {
+            T::try_from(value)
+                .map(|value| (key.clone(), value))
+                .map_err(|err| err.with_outer_key_path_segment(KeyPathSegment::Key(key)))
+        })
+        .collect()
+}
+
+impl<T, S> TryFrom<Value> for HashMap<String, T, S>
+where
+    T: TryFrom<Value, Error = Error>,
+    S: BuildHasher + Default,
+{
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<HashMap<String, T, S>, Error> {
+        try_from_dictionary(value)
+    }
+}
+
+impl<T, S> TryFrom<&Value> for HashMap<String, T, S>
+where
+    T: for<'a> TryFrom<&'a Value, Error = Error>,
+    S: BuildHasher + Default,
+{
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<HashMap<String, T, S>, Error> {
+        try_from_dictionary_ref(value)
+    }
+}
+
+impl<T> TryFrom<Value> for BTreeMap<String, T>
+where
+    T: TryFrom<Value, Error = Error>,
+{
+    type Error = Error;
+
+    fn try_from(value: Value) -> Result<BTreeMap<String, T>, Error> {
+        try_from_dictionary(value)
+    }
+}
+
+impl<T> TryFrom<&Value> for BTreeMap<String, T>
+where
+    T: for<'a> TryFrom<&'a Value, Error = Error>,
+{
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<BTreeMap<String, T>, Error> {
+        try_from_dictionary_ref(value)
+    }
+}
+
+impl<K, V, S> From<HashMap<K, V, S>> for Value
+where
+    K: Into<String> + Eq + Hash,
+    V: Into<Value>,
+{
+    /// Converts a `HashMap` into a dictionary. The order of the keys is unspecified.
+    fn from(from: HashMap<K, V, S>) -> Value {
+        Value::Dictionary(from.into_iter().collect())
+    }
+}
+
+impl<K, V> From<BTreeMap<K, V>> for Value
+where
+    K: Into<String>,
+    V: Into<Value>,
+{
+    fn from(from: BTreeMap<K, V>) -> Value {
+        Value::Dictionary(from.into_iter().collect())
+    }
+}
+
+/// Collects values into an array.
+impl<T: Into<Value>> FromIterator<T> for Value {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Value {
+        Value::Array(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorCategory;
+
+    #[test]
+    fn scalars() {
+        assert_eq!(u8::try_from(Value::from(255)).unwrap(), 255);
+        assert_eq!(i32::try_from(&Value::from(-1)).unwrap(), -1);
+        assert_eq!(
+            usize::try_from(Value::from(u64::MAX)).ok(),
+            usize::try_from(u64::MAX).ok()
+        );
+        assert_eq!(f32::try_from(Value::from(1.5)).unwrap(), 1.5);
+        assert!(bool::try_from(Value::from(true)).unwrap());
+        assert_eq!(String::try_from(&Value::from("a")).unwrap(), "a");
+        assert_eq!(Uid::try_from(Value::Uid(Uid::new(2))).unwrap(), Uid::new(2));
+
+        let err = u8::try_from(Value::from(256)).unwrap_err();
+        assert_eq!(err.category(), ErrorCategory::InvalidValue);
+        let err = u32::try_from(Value::from(-1)).unwrap_err();
+        assert_eq!(err.category(), ErrorCategory::InvalidValue);
+        let err = f32::try_from(Value::from(f64::MAX)).unwrap_err();
+        assert_eq!(err.category(), ErrorCategory::InvalidValue);
+
+        let err = String::try_from(Value::from(1)).unwrap_err();
+        assert_eq!(err.category(), ErrorCategory::UnexpectedStructure);
+        assert_eq!(
+            err.to_string(),
+            "UnexpectedEventType { expected: String, found: Integer }"
+        );
+    }
+
+    #[test]
+    fn collections() {
+        let value: Value = [1, 2, 3].into_iter().collect();
+        assert_eq!(Vec::<u16>::try_from(&value).unwrap(), [1, 2, 3]);
+        assert_eq!(Vec::<u8>::try_from(&value).unwrap(), [1, 2, 3]);
+
+        for err in [
+            Vec::<u8>::try_from(Value::data(vec![1])).unwrap_err(),
+            Vec::<u8>::try_from(&Value::data(vec![1])).unwrap_err(),
+            Vec::<i64>::try_from(&Value::data(vec![1])).unwrap_err(),
+        ] {
+            assert_eq!(
+                err.to_string(),
+                "UnexpectedEventType { expected: StartArray, found: Data }"
+            );
+        }
+
+        let mut dict = BTreeMap::new();
+        dict.insert("a", vec![Value::from(1)]);
+        dict.insert("b", vec![Value::from(2), Value::from("c")]);
+        let value = Value::from(dict);
+
+        let err = HashMap::<String, Vec<i64>>::try_from(&value).unwrap_err();
+        assert_eq!(err.key_path(), Some("b[1]"));
+
+        let map = BTreeMap::<String, Dictionary>::try_from(Value::from(
+            [("x", Value::from(Dictionary::new()))]
+                .into_iter()
+                .collect::<HashMap<_, _>>(),
+        ))
+        .unwrap();
+        assert_eq!(map["x"], Dictionary::new());
+    }
+}
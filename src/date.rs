@@ -8,7 +8,7 @@ use time::{format_description::well_known::Rfc3339, OffsetDateTime, UtcOffset};
 ///
 /// Note that while this type implements `Serialize` and `Deserialize` it will behave strangely if
 /// used with serializers from outside this crate.
-#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Date {
     inner: SystemTime,
 }
@@ -81,6 +81,19 @@ impl Date {
             Err(err) => -as_secs_f64(err.duration()),
         }
     }
+
+    /// Rounds the date down to a whole number of seconds.
+    pub(crate) fn truncate_to_seconds(&self) -> Date {
+        let inner = match self.inner.duration_since(UNIX_EPOCH) {
+            Ok(dur) => UNIX_EPOCH + Duration::from_secs(dur.as_secs()),
+            Err(err) => {
+                let dur = err.duration();
+                let secs = dur.as_secs() + u64::from(dur.subsec_nanos() > 0);
+                UNIX_EPOCH - Duration::from_secs(secs)
+            }
+        };
+        Date { inner }
+    }
 }
 
 impl fmt::Debug for Date {
@@ -202,4 +215,18 @@ mod testing {
         let date_str = "1920-01-01T00:00:00Z";
         Date::from_xml_format(date_str).expect("should parse");
     }
+
+    #[test]
+    fn truncate_to_seconds() {
+        let truncate = |date_str| {
+            Date::from_xml_format(date_str)
+                .unwrap()
+                .truncate_to_seconds()
+                .to_xml_format()
+        };
+
+        assert_eq!(truncate("2001-01-01T00:00:00.5Z"), "2001-01-01T00:00:00Z");
+        assert_eq!(truncate("1969-12-31T23:59:59.25Z"), "1969-12-31T23:59:59Z");
+        assert_eq!(truncate("1920-01-01T00:00:00Z"), "1920-01-01T00:00:00Z");
+    }
 }
@@ -1,22 +1,24 @@
 use serde::de::{
     self,
-    value::{MapAccessDeserializer, MapDeserializer},
+    value::{MapAccessDeserializer, MapDeserializer, StrDeserializer},
     IntoDeserializer,
 };
 use std::{
     borrow::Cow,
+    cell::Cell,
     fmt::Display,
     fs::File,
     io::{BufReader, Cursor, Read, Seek},
     iter::Peekable,
     mem,
     path::Path,
+    rc::Rc,
 };
 
 use crate::{
     date::serde_impls::DATE_NEWTYPE_STRUCT_NAME,
-    error::{self, Error, ErrorKind, EventKind},
-    stream::{self, Event},
+    error::{self, Error, ErrorKind, EventKind, FilePosition, KeyPathSegment},
+    stream::{self, Event, EventPosition},
     u64_to_usize,
     uid::serde_impls::UID_NEWTYPE_STRUCT_NAME,
     value::serde_impls::VALUE_NEWTYPE_STRUCT_NAME,
@@ -67,6 +69,8 @@ where
     events: Peekable<<I as IntoIterator>::IntoIter>,
     option_mode: OptionMode,
     in_plist_value: bool,
+    /// The position of the most recently read event, if known.
+    position: Option<Rc<Cell<FilePosition>>>,
 }
 
 impl<'event, I> Deserializer<'event, I>
@@ -78,6 +82,7 @@ where
             events: iter.into_iter().peekable(),
             option_mode: OptionMode::Root,
             in_plist_value: false,
+            position: None,
         }
     }
 
@@ -327,6 +332,10 @@ where
     de: &'a mut Deserializer<'event, I>,
     is_struct: bool,
     remaining: Option<usize>,
+    /// The index of the next array element, used to report the location of errors.
+    index: usize,
+    /// The most recent dictionary key, used to report the location of errors.
+    key: Option<Cow<'event, str>>,
 }
 
 impl<'a, 'event, I> MapAndSeqAccess<'a, 'event, I>
@@ -342,6 +351,8 @@ where
             de,
             is_struct,
             remaining: len,
+            index: 0,
+            key: None,
         }
     }
 }
@@ -361,9 +372,12 @@ where
         }
 
         self.remaining = self.remaining.map(|r| r.saturating_sub(1));
+        let index = self.index;
+        self.index += 1;
         self.de
             .with_option_mode(OptionMode::Explicit, |this| seed.deserialize(this))
             .map(Some)
+            .map_err(|err| err.with_outer_key_path_segment(KeyPathSegment::Index(index)))
     }
 
     fn size_hint(&self) -> Option<usize> {
@@ -386,6 +400,22 @@ where
         }
 
         self.remaining = self.remaining.map(|r| r.saturating_sub(1));
+
+        // String keys are kept, without copying, until the value has been deserialized so they
+        // can be added to the key path of any error.
+        if let Some(Ok(Event::String(_))) = self.de.events.peek() {
+            let Some(Ok(Event::String(key))) = self.de.events.next() else {
+                unreachable!()
+            };
+            let key = self.key.insert(key);
+            let deserializer: StrDeserializer<Error> = key.as_ref().into_deserializer();
+            return seed
+                .deserialize(deserializer)
+                .map(Some)
+                .map_err(|err| err.with_outer_key_path_segment(KeyPathSegment::Key(key)));
+        }
+
+        self.key = None;
         self.de
             .with_option_mode(OptionMode::Explicit, |this| seed.deserialize(this))
             .map(Some)
@@ -400,8 +430,13 @@ where
         } else {
             OptionMode::Explicit
         };
+        let key = self.key.take();
         self.de
             .with_option_mode(option_mode, |this| seed.deserialize(this))
+            .map_err(|err| match &key {
+                Some(key) => err.with_outer_key_path_segment(KeyPathSegment::Key(key)),
+                None => err,
+            })
     }
 
     fn size_hint(&self) -> Option<usize> {
@@ -424,19 +459,19 @@ pub fn from_file<P: AsRef<Path>, T: de::DeserializeOwned>(path: P) -> Result<T,
 /// Deserializes an instance of type `T` from a seekable byte stream containing a plist of any encoding.
 pub fn from_reader<R: Read + Seek, T: de::DeserializeOwned>(reader: R) -> Result<T, Error> {
     let reader = stream::Reader::new(reader);
-    from_stream(reader)
+    from_positioned_stream(reader)
 }
 
 /// Deserializes an instance of type `T` from a byte stream containing an ASCII encoded plist.
 pub fn from_reader_ascii<R: Read, T: de::DeserializeOwned>(reader: R) -> Result<T, Error> {
     let reader = stream::AsciiReader::new(reader);
-    from_stream(reader)
+    from_positioned_stream(reader)
 }
 
 /// Deserializes an instance of type `T` from a byte stream containing an XML encoded plist.
 pub fn from_reader_xml<R: Read, T: de::DeserializeOwned>(reader: R) -> Result<T, Error> {
     let reader = stream::XmlReader::new(BufReader::new(reader));
-    from_stream(reader)
+    from_positioned_stream(reader)
 }
 
 /// Interprets a [`Value`] as an instance of type `T`.
@@ -448,16 +483,64 @@ pub fn from_value<T: de::DeserializeOwned>(value: &Value) -> Result<T, Error> {
 pub(crate) fn from_stream<'event, T: de::DeserializeOwned>(
     stream: impl IntoIterator<Item = Result<Event<'event>, Error>>,
 ) -> Result<T, Error> {
-    let mut de = Deserializer::new(stream);
-    let value = de::Deserialize::deserialize(&mut de)?;
+    deserialize_all(Deserializer::new(stream))
+}
 
-    // TODO: Ideally this check would be inside the `Deserializer` implementation.
-    if let Some(event) = de.events.next().transpose()? {
-        return Err(ErrorKind::ExpectedEndOfEventStream {
-            found: EventKind::of_event(&event),
+/// Deserializes from a reader, reporting the position of the event which caused any error.
+fn from_positioned_stream<R, T>(reader: R) -> Result<T, Error>
+where
+    R: Iterator<Item = Result<Event<'static>, Error>> + EventPosition,
+    T: de::DeserializeOwned,
+{
+    let position = Rc::new(Cell::new(reader.event_position()));
+    let mut de = Deserializer::new(PositionedEvents {
+        reader,
+        position: Rc::clone(&position),
+    });
+    de.position = Some(position);
+    deserialize_all(de)
+}
+
+fn deserialize_all<'event, I, T>(mut de: Deserializer<'event, I>) -> Result<T, Error>
+where
+    I: IntoIterator<Item = Result<Event<'event>, Error>>,
+    T: de::DeserializeOwned,
+{
+    let result = de::Deserialize::deserialize(&mut de).and_then(|value| {
+        // TODO: Ideally this check would be inside the `Deserializer` implementation.
+        if let Some(event) = de.events.next().transpose()? {
+            return Err(ErrorKind::ExpectedEndOfEventStream {
+                found: EventKind::of_event(&event),
+            }
+            .without_position());
         }
-        .without_position());
+        Ok(value)
+    });
+
+    // Errors raised by the deserializer are reported at the most recently read event. This is
+    // either the event which caused the error or, for example, the end of a dictionary with a
+    // missing field.
+    match de.position {
+        Some(position) => result.map_err(|err| err.with_default_position(position.get())),
+        None => result,
     }
+}
+
+/// Records the position of the most recent event read from a reader.
+struct PositionedEvents<R> {
+    reader: R,
+    position: Rc<Cell<FilePosition>>,
+}
 
-    Ok(value)
+impl<R> Iterator for PositionedEvents<R>
+where
+    R: Iterator<Item = Result<Event<'static>, Error>> + EventPosition,
+{
+    type Item = Result<Event<'static>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = self.reader.next()?;
+        self.position.set(self.reader.event_position());
+        Some(event)
+    }
 }
@@ -1,21 +1,52 @@
 //! A map of `String` to `plist::Value`.
 //!
-//! The map is currently backed by an [`IndexMap`]. This may be changed in a future minor release.
+//! A [`Dictionary`] is backed by an [`IndexMap`] by default, which keeps keys in the order in
+//! which they are inserted or read. To write plists with sorted keys use
+//! [`CanonicalOptions`](crate::CanonicalOptions), or sort a dictionary in place with
+//! [`Dictionary::sort_keys`].
+//!
+//! A `Dictionary<BTreeMap<String, Value>>` is instead backed by a [`BTreeMap`] and always keeps
+//! its keys sorted, so it is serialized with its keys in order however they were inserted. It
+//! supports the same map, iterator and entry methods, but not those which work with positions,
+//! and can be converted to and from the default `Dictionary`.
+//!
+//! ```
+//! use std::collections::BTreeMap;
+//! use plist::{dictionary::Dictionary, Value};
+//!
+//! let mut dict = Dictionary::<BTreeMap<String, Value>>::default();
+//! dict.insert("CFBundleVersion".to_owned(), "1".into());
+//! dict.insert("CFBundleName".to_owned(), "App".into());
+//!
+//! assert_eq!(dict.keys().collect::<Vec<_>>(), ["CFBundleName", "CFBundleVersion"]);
+//! ```
 //!
 //! [`IndexMap`]: https://docs.rs/indexmap/latest/indexmap/map/struct.IndexMap.html
 
 use indexmap::{map, IndexMap};
 use std::{
+    cmp::Ordering,
+    collections::{btree_map, BTreeMap},
     fmt::{self, Debug},
     ops,
 };
 
-use crate::Value;
+use crate::{
+    merge::{self, DeepMergeOptions},
+    Error, Value,
+};
+#[cfg(any(
+    test,
+    feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
+))]
+use private::{Occupied, Vacant};
 
 /// Represents a plist dictionary type.
+///
+/// `M` is the map which stores the entries. See the [module documentation](self) for details.
 #[derive(Clone, Default, PartialEq)]
-pub struct Dictionary {
-    map: IndexMap<String, Value>,
+pub struct Dictionary<M: Map = IndexMap<String, Value>> {
+    map: M,
 }
 
 impl Dictionary {
@@ -27,6 +58,228 @@ impl Dictionary {
         }
     }
 
+    /// Inserts a key-value pair into the dictionary at the given position.
+    ///
+    /// If the dictionary already had this key present, the entry is moved to the given position
+    /// and its value is updated, and the old value is returned. Otherwise the entries after the
+    /// position are shifted to make room and `None` is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the length of the dictionary, or equal to it when the key
+    /// is already present.
+    ///
+    /// ```
+    /// let mut dict = plist::Dictionary::new();
+    /// dict.insert("CFBundleVersion".to_owned(), "1".into());
+    /// dict.shift_insert(0, "CFBundleName".to_owned(), "App".into());
+    ///
+    /// assert_eq!(dict.keys().collect::<Vec<_>>(), ["CFBundleName", "CFBundleVersion"]);
+    /// ```
+    #[inline]
+    pub fn shift_insert(&mut self, index: usize, k: String, v: Value) -> Option<Value> {
+        self.map.shift_insert(index, k, v)
+    }
+
+    /// Removes a key from the dictionary, returning the value at the key if the key was previously
+    /// in the dictionary.
+    ///
+    /// The last entry takes the place of the removed entry. This is fast but does not preserve the
+    /// order of the remaining keys.
+    #[inline]
+    pub fn swap_remove(&mut self, key: &str) -> Option<Value> {
+        self.map.swap_remove(key)
+    }
+
+    /// Removes a key from the dictionary, returning the value at the key if the key was previously
+    /// in the dictionary.
+    ///
+    /// The entries after the removed entry are shifted to fill the gap. This preserves the order
+    /// of the remaining keys but takes time proportional to their number.
+    #[inline]
+    pub fn shift_remove(&mut self, key: &str) -> Option<Value> {
+        self.map.shift_remove(key)
+    }
+
+    /// Returns the entry at the given position, if it exists.
+    #[inline]
+    pub fn get_index(&self, index: usize) -> Option<(&String, &Value)> {
+        self.map.get_index(index)
+    }
+
+    /// Returns the key and a mutable reference to the value of the entry at the given position,
+    /// if it exists.
+    #[inline]
+    pub fn get_index_mut(&mut self, index: usize) -> Option<(&String, &mut Value)> {
+        self.map.get_index_mut(index)
+    }
+
+    /// Returns the position of the entry with the given key, if it exists.
+    #[inline]
+    pub fn get_index_of(&self, key: &str) -> Option<usize> {
+        self.map.get_index_of(key)
+    }
+
+    /// Returns the first entry in the dictionary.
+    #[inline]
+    pub fn first(&self) -> Option<(&String, &Value)> {
+        self.map.first()
+    }
+
+    /// Returns the last entry in the dictionary.
+    #[inline]
+    pub fn last(&self) -> Option<(&String, &Value)> {
+        self.map.last()
+    }
+
+    /// Moves the entry at position `from` to position `to`, shifting the entries in between.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `from` or `to` are out of bounds.
+    ///
+    /// ```
+    /// let mut dict: plist::Dictionary = [("a", 1), ("b", 2), ("c", 3)].into_iter().collect();
+    /// dict.move_index(2, 0);
+    ///
+    /// assert_eq!(dict.keys().collect::<Vec<_>>(), ["c", "a", "b"]);
+    /// ```
+    #[inline]
+    pub fn move_index(&mut self, from: usize, to: usize) {
+        self.map.move_index(from, to);
+    }
+
+    /// Removes the entries in the given range of positions from the dictionary and returns them
+    /// in an iterator.
+    ///
+    /// The entries after the range are shifted to fill the gap, preserving their order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than the end, or the end is greater than the
+    /// length of the dictionary.
+    #[inline]
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_>
+    where
+        R: ops::RangeBounds<usize>,
+    {
+        Drain {
+            iter: self.map.drain(range),
+        }
+    }
+
+    /// Sort the dictionary keys.
+    ///
+    /// This uses the default ordering defined on [`str`].
+    ///
+    /// This function is useful if you are serializing to XML, and wish to
+    /// ensure a consistent key order.
+    #[inline]
+    pub fn sort_keys(&mut self) {
+        self.map.sort_keys();
+    }
+
+    /// Sort the dictionary entries using a comparison function.
+    ///
+    /// The comparison function is passed the key and value of two entries. The sort is stable.
+    ///
+    /// ```
+    /// let mut dict: plist::Dictionary = [("a", 3), ("b", 1), ("c", 2)].into_iter().collect();
+    /// dict.sort_by(|_, a, _, b| {
+    ///     a.as_signed_integer().cmp(&b.as_signed_integer())
+    /// });
+    ///
+    /// assert_eq!(dict.keys().collect::<Vec<_>>(), ["b", "c", "a"]);
+    /// ```
+    #[inline]
+    pub fn sort_by<F>(&mut self, compare: F)
+    where
+        F: FnMut(&String, &Value, &String, &Value) -> Ordering,
+    {
+        self.map.sort_by(compare);
+    }
+
+    /// Sort the dictionary entries using a comparison function.
+    ///
+    /// The comparison function is passed the key and value of two entries. The sort is unstable
+    /// so entries which compare equal may be reordered.
+    #[inline]
+    pub fn sort_unstable_by<F>(&mut self, compare: F)
+    where
+        F: FnMut(&String, &Value, &String, &Value) -> Ordering,
+    {
+        self.map.sort_unstable_by(compare);
+    }
+
+    /// Compares two dictionaries using a total order.
+    ///
+    /// See [`Value::total_cmp`] for details.
+    pub fn total_cmp(&self, other: &Dictionary) -> Ordering {
+        crate::total_ord::cmp_dictionaries(self, other)
+    }
+
+    /// Recursively merges `patch` into this dictionary, following the semantics of
+    /// [RFC 7396] (JSON Merge Patch).
+    ///
+    /// Where both dictionaries contain a dictionary for the same key, the two are merged.
+    /// Otherwise values in `patch` are inserted, replacing any existing value. Plists have no
+    /// `null` value so, unlike JSON Merge Patch, a patch cannot remove entries. Use a
+    /// [`Patch`](crate::Patch) to remove entries.
+    ///
+    /// [RFC 7396]: https://tools.ietf.org/html/rfc7396
+    ///
+    /// ```
+    /// use plist::{Dictionary, Value};
+    ///
+    /// let mut settings = Dictionary::new();
+    /// settings.insert("SDKROOT".to_owned(), "iphoneos".into());
+    /// let mut info = Dictionary::new();
+    /// info.insert("BuildSettings".to_owned(), settings.into());
+    ///
+    /// let mut patch = Dictionary::new();
+    /// patch.insert("BuildSettings".to_owned(), {
+    ///     let mut settings = Dictionary::new();
+    ///     settings.insert("ENABLE_BITCODE".to_owned(), "NO".into());
+    ///     settings.into()
+    /// });
+    /// info.merge_patch(&patch);
+    ///
+    /// let settings = info["BuildSettings"].as_dictionary().unwrap();
+    /// assert_eq!(settings.len(), 2);
+    /// ```
+    pub fn merge_patch(&mut self, patch: &Dictionary) {
+        crate::patch::merge_patch(self, patch);
+    }
+
+    /// Recursively merges `overlay` into this dictionary.
+    ///
+    /// See [`Value::deep_merge`] for details.
+    pub fn deep_merge(&mut self, overlay: Dictionary) {
+        merge::deep_merge_dictionaries(
+            self,
+            overlay,
+            &DeepMergeOptions::default(),
+            &mut Vec::new(),
+        )
+        .expect("default options never return an error");
+    }
+
+    /// Recursively merges `overlay` into this dictionary using the specified options.
+    ///
+    /// See [`Value::deep_merge_with_options`] for details.
+    pub fn deep_merge_with_options(
+        &mut self,
+        overlay: Dictionary,
+        options: &DeepMergeOptions,
+    ) -> Result<(), Error> {
+        let mut merged = self.clone();
+        merge::deep_merge_dictionaries(&mut merged, overlay, options, &mut Vec::new())?;
+        *self = merged;
+        Ok(())
+    }
+}
+
+impl<M: Map> Dictionary<M> {
     /// Clears the dictionary, removing all values.
     #[inline]
     pub fn clear(&mut self) {
@@ -39,6 +292,12 @@ impl Dictionary {
         self.map.get(key)
     }
 
+    /// Returns the key and a reference to the value corresponding to the key.
+    #[inline]
+    pub(crate) fn get_key_value(&self, key: &str) -> Option<(&String, &Value)> {
+        self.map.get_key_value(key)
+    }
+
     /// Returns true if the dictionary contains a value for the specified key.
     #[inline]
     pub fn contains_key(&self, key: &str) -> bool {
@@ -62,11 +321,33 @@ impl Dictionary {
         self.map.insert(k, v)
     }
 
+    /// Inserts a key-value pair into the dictionary if `v` is `Some`.
+    ///
+    /// Plists have no null value so an absent optional value is usually represented by a missing
+    /// key. Returns the old value if a new value was inserted in its place.
+    ///
+    /// ```
+    /// let name: Option<&str> = None;
+    /// let mut dict = plist::Dictionary::new();
+    /// dict.insert_some("CFBundleName".to_owned(), name);
+    /// dict.insert_some("CFBundleVersion".to_owned(), Some(1));
+    ///
+    /// assert_eq!(dict.keys().collect::<Vec<_>>(), ["CFBundleVersion"]);
+    /// ```
+    #[inline]
+    pub fn insert_some<V: Into<Value>>(&mut self, k: String, v: Option<V>) -> Option<Value> {
+        v.and_then(|v| self.map.insert(k, v.into()))
+    }
+
     /// Removes a key from the dictionary, returning the value at the key if the key was previously
     /// in the dictionary.
+    ///
+    /// For a dictionary backed by an `IndexMap` this is equivalent to
+    /// [`swap_remove`](Dictionary::swap_remove) and does not preserve the order of the remaining
+    /// keys.
     #[inline]
     pub fn remove(&mut self, key: &str) -> Option<Value> {
-        self.map.swap_remove(key)
+        self.map.remove(key)
     }
 
     /// Scan through each key-value pair in the map and keep those where the
@@ -79,17 +360,6 @@ impl Dictionary {
         self.map.retain(keep);
     }
 
-    /// Sort the dictionary keys.
-    ///
-    /// This uses the default ordering defined on [`str`].
-    ///
-    /// This function is useful if you are serializing to XML, and wish to
-    /// ensure a consistent key order.
-    #[inline]
-    pub fn sort_keys(&mut self) {
-        self.map.sort_keys();
-    }
-
     /// Gets the given key's corresponding entry in the dictionary for in-place manipulation.
     // Entry functionality is unstable until I can figure out how to use either Cow<str> or
     // T: AsRef<str> + Into<String>
@@ -97,14 +367,11 @@ impl Dictionary {
         test,
         feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
     ))]
-    pub fn entry<S>(&mut self, key: S) -> Entry<'_>
+    pub fn entry<S>(&mut self, key: S) -> Entry<'_, M>
     where
         S: Into<String>,
     {
-        match self.map.entry(key.into()) {
-            map::Entry::Vacant(vacant) => Entry::Vacant(VacantEntry { vacant }),
-            map::Entry::Occupied(occupied) => Entry::Occupied(OccupiedEntry { occupied }),
-        }
+        self.map.entry(key.into())
     }
 
     /// Returns the number of elements in the dictionary.
@@ -121,7 +388,7 @@ impl Dictionary {
 
     /// Gets an iterator over the entries of the dictionary.
     #[inline]
-    pub fn iter(&self) -> Iter<'_> {
+    pub fn iter(&self) -> Iter<'_, M> {
         Iter {
             iter: self.map.iter(),
         }
@@ -129,7 +396,7 @@ impl Dictionary {
 
     /// Gets a mutable iterator over the entries of the dictionary.
     #[inline]
-    pub fn iter_mut(&mut self) -> IterMut<'_> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, M> {
         IterMut {
             iter: self.map.iter_mut(),
         }
@@ -137,7 +404,7 @@ impl Dictionary {
 
     /// Gets an iterator over the keys of the dictionary.
     #[inline]
-    pub fn keys(&self) -> Keys<'_> {
+    pub fn keys(&self) -> Keys<'_, M> {
         Keys {
             iter: self.map.keys(),
         }
@@ -145,7 +412,7 @@ impl Dictionary {
 
     /// Gets an iterator over the values of the dictionary.
     #[inline]
-    pub fn values(&self) -> Values<'_> {
+    pub fn values(&self) -> Values<'_, M> {
         Values {
             iter: self.map.values(),
         }
@@ -153,7 +420,7 @@ impl Dictionary {
 
     /// Gets an iterator over mutable values of the dictionary.
     #[inline]
-    pub fn values_mut(&mut self) -> ValuesMut<'_> {
+    pub fn values_mut(&mut self) -> ValuesMut<'_, M> {
         ValuesMut {
             iter: self.map.values_mut(),
         }
@@ -175,11 +442,11 @@ impl Dictionary {
 /// }
 /// # ;
 /// ```
-impl ops::Index<&str> for Dictionary {
+impl<M: Map> ops::Index<&str> for Dictionary<M> {
     type Output = Value;
 
     fn index(&self, index: &str) -> &Value {
-        self.map.index(index)
+        self.map.get(index).expect("no entry found for key")
     }
 }
 
@@ -192,20 +459,20 @@ impl ops::Index<&str> for Dictionary {
 /// #
 /// dict["key"] = "value".into();
 /// ```
-impl ops::IndexMut<&str> for Dictionary {
+impl<M: Map> ops::IndexMut<&str> for Dictionary<M> {
     fn index_mut(&mut self, index: &str) -> &mut Value {
         self.map.get_mut(index).expect("no entry found for key")
     }
 }
 
-impl Debug for Dictionary {
+impl<M: Map> Debug for Dictionary<M> {
     #[inline]
     fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         self.map.fmt(formatter)
     }
 }
 
-impl<K: Into<String>, V: Into<Value>> FromIterator<(K, V)> for Dictionary {
+impl<M: Map, K: Into<String>, V: Into<Value>> FromIterator<(K, V)> for Dictionary<M> {
     fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
         Dictionary {
             map: iter
@@ -216,7 +483,7 @@ impl<K: Into<String>, V: Into<Value>> FromIterator<(K, V)> for Dictionary {
     }
 }
 
-impl Extend<(String, Value)> for Dictionary {
+impl<M: Map> Extend<(String, Value)> for Dictionary<M> {
     fn extend<T>(&mut self, iter: T)
     where
         T: IntoIterator<Item = (String, Value)>,
@@ -225,9 +492,297 @@ impl Extend<(String, Value)> for Dictionary {
     }
 }
 
+/// Converts a sorted dictionary into one which keeps its keys in insertion order, starting in
+/// sorted order.
+impl From<Dictionary<BTreeMap<String, Value>>> for Dictionary {
+    fn from(dict: Dictionary<BTreeMap<String, Value>>) -> Dictionary {
+        dict.into_iter().collect()
+    }
+}
+
+impl From<Dictionary> for Dictionary<BTreeMap<String, Value>> {
+    fn from(dict: Dictionary) -> Dictionary<BTreeMap<String, Value>> {
+        dict.into_iter().collect()
+    }
+}
+
+/// A map which can store the entries of a [`Dictionary`].
+///
+/// This trait is sealed. It is implemented for `IndexMap<String, Value>`, which keeps keys in
+/// insertion order, and `BTreeMap<String, Value>`, which keeps keys sorted.
+pub trait Map: private::Sealed {}
+
+impl Map for IndexMap<String, Value> {}
+
+impl Map for BTreeMap<String, Value> {}
+
+mod private {
+    use super::*;
+
+    /// The operations a [`Dictionary`] needs from the map which stores its entries. Only this
+    /// crate can implement it, which seals [`Map`](super::Map).
+    pub trait Sealed:
+        Clone
+        + Debug
+        + Default
+        + PartialEq
+        + Extend<(String, Value)>
+        + FromIterator<(String, Value)>
+        + IntoIterator<Item = (String, Value), IntoIter: DoubleEndedIterator + ExactSizeIterator>
+        + 'static
+    {
+        type Iter<'a>: DoubleEndedIterator<Item = (&'a String, &'a Value)> + ExactSizeIterator
+        where
+            Self: 'a;
+        type IterMut<'a>: DoubleEndedIterator<Item = (&'a String, &'a mut Value)>
+            + ExactSizeIterator
+        where
+            Self: 'a;
+        type Keys<'a>: DoubleEndedIterator<Item = &'a String> + ExactSizeIterator
+        where
+            Self: 'a;
+        type Values<'a>: DoubleEndedIterator<Item = &'a Value> + ExactSizeIterator
+        where
+            Self: 'a;
+        type ValuesMut<'a>: DoubleEndedIterator<Item = &'a mut Value> + ExactSizeIterator
+        where
+            Self: 'a;
+        #[cfg(any(
+            test,
+            feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
+        ))]
+        type VacantEntry<'a>: Vacant<'a>
+        where
+            Self: 'a;
+        #[cfg(any(
+            test,
+            feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
+        ))]
+        type OccupiedEntry<'a>: Occupied<'a>
+        where
+            Self: 'a;
+
+        fn clear(&mut self);
+        fn get(&self, key: &str) -> Option<&Value>;
+        fn get_key_value(&self, key: &str) -> Option<(&String, &Value)>;
+        fn contains_key(&self, key: &str) -> bool;
+        fn get_mut(&mut self, key: &str) -> Option<&mut Value>;
+        fn insert(&mut self, key: String, value: Value) -> Option<Value>;
+        fn remove(&mut self, key: &str) -> Option<Value>;
+        fn retain<F>(&mut self, keep: F)
+        where
+            F: FnMut(&String, &mut Value) -> bool;
+        #[cfg(any(
+            test,
+            feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
+        ))]
+        fn entry(&mut self, key: String) -> Entry<'_, Self>
+        where
+            Self: super::Map;
+        fn len(&self) -> usize;
+        fn is_empty(&self) -> bool;
+        fn iter(&self) -> Self::Iter<'_>;
+        fn iter_mut(&mut self) -> Self::IterMut<'_>;
+        fn keys(&self) -> Self::Keys<'_>;
+        fn values(&self) -> Self::Values<'_>;
+        fn values_mut(&mut self) -> Self::ValuesMut<'_>;
+    }
+
+    #[cfg(any(
+        test,
+        feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
+    ))]
+    pub trait Vacant<'a> {
+        fn key(&self) -> &String;
+        fn insert(self, value: Value) -> &'a mut Value;
+    }
+
+    #[cfg(any(
+        test,
+        feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
+    ))]
+    pub trait Occupied<'a> {
+        fn key(&self) -> &String;
+        fn get(&self) -> &Value;
+        fn get_mut(&mut self) -> &mut Value;
+        fn into_mut(self) -> &'a mut Value;
+        fn insert(&mut self, value: Value) -> Value;
+        fn remove(self) -> Value;
+    }
+
+    macro_rules! impl_map {
+        ($map:ident, $entries:ident, $remove:ident) => {
+            impl Sealed for $map<String, Value> {
+                type Iter<'a> = $entries::Iter<'a, String, Value>;
+                type IterMut<'a> = $entries::IterMut<'a, String, Value>;
+                type Keys<'a> = $entries::Keys<'a, String, Value>;
+                type Values<'a> = $entries::Values<'a, String, Value>;
+                type ValuesMut<'a> = $entries::ValuesMut<'a, String, Value>;
+                #[cfg(any(
+                    test,
+                    feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
+                ))]
+                type VacantEntry<'a> = $entries::VacantEntry<'a, String, Value>;
+                #[cfg(any(
+                    test,
+                    feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
+                ))]
+                type OccupiedEntry<'a> = $entries::OccupiedEntry<'a, String, Value>;
+
+                #[inline]
+                fn clear(&mut self) {
+                    $map::clear(self);
+                }
+
+                #[inline]
+                fn get(&self, key: &str) -> Option<&Value> {
+                    $map::get(self, key)
+                }
+
+                #[inline]
+                fn get_key_value(&self, key: &str) -> Option<(&String, &Value)> {
+                    $map::get_key_value(self, key)
+                }
+
+                #[inline]
+                fn contains_key(&self, key: &str) -> bool {
+                    $map::contains_key(self, key)
+                }
+
+                #[inline]
+                fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+                    $map::get_mut(self, key)
+                }
+
+                #[inline]
+                fn insert(&mut self, key: String, value: Value) -> Option<Value> {
+                    $map::insert(self, key, value)
+                }
+
+                #[inline]
+                fn remove(&mut self, key: &str) -> Option<Value> {
+                    $map::$remove(self, key)
+                }
+
+                #[inline]
+                fn retain<F>(&mut self, keep: F)
+                where
+                    F: FnMut(&String, &mut Value) -> bool,
+                {
+                    $map::retain(self, keep);
+                }
+
+                #[cfg(any(
+                    test,
+                    feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
+                ))]
+                #[inline]
+                fn entry(&mut self, key: String) -> Entry<'_, Self> {
+                    match $map::entry(self, key) {
+                        $entries::Entry::Vacant(vacant) => Entry::Vacant(VacantEntry { vacant }),
+                        $entries::Entry::Occupied(occupied) => {
+                            Entry::Occupied(OccupiedEntry { occupied })
+                        }
+                    }
+                }
+
+                #[inline]
+                fn len(&self) -> usize {
+                    $map::len(self)
+                }
+
+                #[inline]
+                fn is_empty(&self) -> bool {
+                    $map::is_empty(self)
+                }
+
+                #[inline]
+                fn iter(&self) -> Self::Iter<'_> {
+                    $map::iter(self)
+                }
+
+                #[inline]
+                fn iter_mut(&mut self) -> Self::IterMut<'_> {
+                    $map::iter_mut(self)
+                }
+
+                #[inline]
+                fn keys(&self) -> Self::Keys<'_> {
+                    $map::keys(self)
+                }
+
+                #[inline]
+                fn values(&self) -> Self::Values<'_> {
+                    $map::values(self)
+                }
+
+                #[inline]
+                fn values_mut(&mut self) -> Self::ValuesMut<'_> {
+                    $map::values_mut(self)
+                }
+            }
+
+            #[cfg(any(
+                test,
+                feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
+            ))]
+            impl<'a> Vacant<'a> for $entries::VacantEntry<'a, String, Value> {
+                #[inline]
+                fn key(&self) -> &String {
+                    $entries::VacantEntry::key(self)
+                }
+
+                #[inline]
+                fn insert(self, value: Value) -> &'a mut Value {
+                    $entries::VacantEntry::insert(self, value)
+                }
+            }
+
+            #[cfg(any(
+                test,
+                feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
+            ))]
+            impl<'a> Occupied<'a> for $entries::OccupiedEntry<'a, String, Value> {
+                #[inline]
+                fn key(&self) -> &String {
+                    $entries::OccupiedEntry::key(self)
+                }
+
+                #[inline]
+                fn get(&self) -> &Value {
+                    $entries::OccupiedEntry::get(self)
+                }
+
+                #[inline]
+                fn get_mut(&mut self) -> &mut Value {
+                    $entries::OccupiedEntry::get_mut(self)
+                }
+
+                #[inline]
+                fn into_mut(self) -> &'a mut Value {
+                    $entries::OccupiedEntry::into_mut(self)
+                }
+
+                #[inline]
+                fn insert(&mut self, value: Value) -> Value {
+                    $entries::OccupiedEntry::insert(self, value)
+                }
+
+                #[inline]
+                fn remove(self) -> Value {
+                    $entries::OccupiedEntry::$remove(self)
+                }
+            }
+        };
+    }
+
+    impl_map!(IndexMap, map, swap_remove);
+    impl_map!(BTreeMap, btree_map, remove);
+}
+
 macro_rules! delegate_iterator {
-    (($name:ident $($generics:tt)*) => $item:ty) => {
-        impl $($generics)* Iterator for $name $($generics)* {
+    (impl[$($generics:tt)*] $ty:ty => $item:ty) => {
+        impl<$($generics)*> Iterator for $ty {
             type Item = $item;
             #[inline]
             fn next(&mut self) -> Option<Self::Item> {
@@ -239,14 +794,14 @@ macro_rules! delegate_iterator {
             }
         }
 
-        /*impl $($generics)* DoubleEndedIterator for $name $($generics)* {
+        impl<$($generics)*> DoubleEndedIterator for $ty {
             #[inline]
             fn next_back(&mut self) -> Option<Self::Item> {
                 self.iter.next_back()
             }
-        }*/
+        }
 
-        impl $($generics)* ExactSizeIterator for $name $($generics)* {
+        impl<$($generics)*> ExactSizeIterator for $ty {
             #[inline]
             fn len(&self) -> usize {
                 self.iter.len()
@@ -266,11 +821,11 @@ macro_rules! delegate_iterator {
     test,
     feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
 ))]
-pub enum Entry<'a> {
+pub enum Entry<'a, M: Map = IndexMap<String, Value>> {
     /// A vacant Entry.
-    Vacant(VacantEntry<'a>),
+    Vacant(VacantEntry<'a, M>),
     /// An occupied Entry.
-    Occupied(OccupiedEntry<'a>),
+    Occupied(OccupiedEntry<'a, M>),
 }
 
 /// A vacant Entry. It is part of the [`Entry`] enum.
@@ -280,8 +835,8 @@ pub enum Entry<'a> {
     test,
     feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
 ))]
-pub struct VacantEntry<'a> {
-    vacant: map::VacantEntry<'a, String, Value>,
+pub struct VacantEntry<'a, M: Map = IndexMap<String, Value>> {
+    vacant: M::VacantEntry<'a>,
 }
 
 /// An occupied Entry. It is part of the [`Entry`] enum.
@@ -291,15 +846,15 @@ pub struct VacantEntry<'a> {
     test,
     feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
 ))]
-pub struct OccupiedEntry<'a> {
-    occupied: map::OccupiedEntry<'a, String, Value>,
+pub struct OccupiedEntry<'a, M: Map = IndexMap<String, Value>> {
+    occupied: M::OccupiedEntry<'a>,
 }
 
 #[cfg(any(
     test,
     feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
 ))]
-impl<'a> Entry<'a> {
+impl<'a, M: Map> Entry<'a, M> {
     /// Returns a reference to this entry's key.
     ///
     /// # Examples
@@ -353,13 +908,56 @@ impl<'a> Entry<'a> {
             Entry::Occupied(entry) => entry.into_mut(),
         }
     }
+
+    /// Ensures a value is in the entry by inserting the default value if empty, and returns a
+    /// mutable reference to the value in the entry.
+    ///
+    /// The default value is an empty dictionary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut dict = plist::Dictionary::new();
+    /// dict.entry("serde").or_default();
+    ///
+    /// assert_eq!(dict["serde"], plist::Dictionary::new().into());
+    /// ```
+    pub fn or_default(self) -> &'a mut Value {
+        self.or_insert_with(|| Dictionary::new().into())
+    }
+
+    /// Provides in-place mutable access to an occupied entry before any potential inserts into
+    /// the dictionary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut dict = plist::Dictionary::new();
+    /// dict.entry("count").and_modify(|v| *v = 2.into()).or_insert(1.into());
+    /// assert_eq!(dict["count"], 1.into());
+    ///
+    /// dict.entry("count").and_modify(|v| *v = 2.into()).or_insert(1.into());
+    /// assert_eq!(dict["count"], 2.into());
+    /// ```
+    pub fn and_modify<F>(self, f: F) -> Self
+    where
+        F: FnOnce(&mut Value),
+    {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
 }
 
 #[cfg(any(
     test,
     feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
 ))]
-impl<'a> VacantEntry<'a> {
+impl<'a, M: Map> VacantEntry<'a, M> {
     /// Gets a reference to the key that would be used when inserting a value through the
     /// VacantEntry.
     ///
@@ -405,7 +1003,7 @@ impl<'a> VacantEntry<'a> {
     test,
     feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"
 ))]
-impl<'a> OccupiedEntry<'a> {
+impl<'a, M: Map> OccupiedEntry<'a, M> {
     /// Gets a reference to the key in the entry.
     ///
     /// # Examples
@@ -539,15 +1137,15 @@ impl<'a> OccupiedEntry<'a> {
     /// ```
     #[inline]
     pub fn remove(self) -> Value {
-        self.occupied.swap_remove()
+        self.occupied.remove()
     }
 }
 
 //////////////////////////////////////////////////////////////////////////////
 
-impl<'a> IntoIterator for &'a Dictionary {
+impl<'a, M: Map> IntoIterator for &'a Dictionary<M> {
     type Item = (&'a String, &'a Value);
-    type IntoIter = Iter<'a>;
+    type IntoIter = Iter<'a, M>;
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
         Iter {
@@ -557,19 +1155,17 @@ impl<'a> IntoIterator for &'a Dictionary {
 }
 
 /// An iterator over a `plist::Dictionary`'s entries.
-pub struct Iter<'a> {
-    iter: IterImpl<'a>,
+pub struct Iter<'a, M: Map = IndexMap<String, Value>> {
+    iter: M::Iter<'a>,
 }
 
-type IterImpl<'a> = map::Iter<'a, String, Value>;
-
-delegate_iterator!((Iter<'a>) => (&'a String, &'a Value));
+delegate_iterator!(impl['a, M: Map] Iter<'a, M> => (&'a String, &'a Value));
 
 //////////////////////////////////////////////////////////////////////////////
 
-impl<'a> IntoIterator for &'a mut Dictionary {
+impl<'a, M: Map> IntoIterator for &'a mut Dictionary<M> {
     type Item = (&'a String, &'a mut Value);
-    type IntoIter = IterMut<'a>;
+    type IntoIter = IterMut<'a, M>;
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
         IterMut {
@@ -579,17 +1175,17 @@ impl<'a> IntoIterator for &'a mut Dictionary {
 }
 
 /// A mutable iterator over a `plist::Dictionary`'s entries.
-pub struct IterMut<'a> {
-    iter: map::IterMut<'a, String, Value>,
+pub struct IterMut<'a, M: Map = IndexMap<String, Value>> {
+    iter: M::IterMut<'a>,
 }
 
-delegate_iterator!((IterMut<'a>) => (&'a String, &'a mut Value));
+delegate_iterator!(impl['a, M: Map] IterMut<'a, M> => (&'a String, &'a mut Value));
 
 //////////////////////////////////////////////////////////////////////////////
 
-impl IntoIterator for Dictionary {
+impl<M: Map> IntoIterator for Dictionary<M> {
     type Item = (String, Value);
-    type IntoIter = IntoIter;
+    type IntoIter = IntoIter<M>;
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
         IntoIter {
@@ -599,47 +1195,58 @@ impl IntoIterator for Dictionary {
 }
 
 /// An owning iterator over a `plist::Dictionary`'s entries.
-pub struct IntoIter {
-    iter: map::IntoIter<String, Value>,
+pub struct IntoIter<M: Map = IndexMap<String, Value>> {
+    iter: <M as IntoIterator>::IntoIter,
 }
 
-delegate_iterator!((IntoIter) => (String, Value));
+delegate_iterator!(impl[M: Map] IntoIter<M> => (String, Value));
 
 //////////////////////////////////////////////////////////////////////////////
 
 /// An iterator over a `plist::Dictionary`'s keys.
-pub struct Keys<'a> {
-    iter: map::Keys<'a, String, Value>,
+pub struct Keys<'a, M: Map = IndexMap<String, Value>> {
+    iter: M::Keys<'a>,
 }
 
-delegate_iterator!((Keys<'a>) => &'a String);
+delegate_iterator!(impl['a, M: Map] Keys<'a, M> => &'a String);
 
 //////////////////////////////////////////////////////////////////////////////
 
 /// An iterator over a `plist::Dictionary`'s values.
-pub struct Values<'a> {
-    iter: map::Values<'a, String, Value>,
+pub struct Values<'a, M: Map = IndexMap<String, Value>> {
+    iter: M::Values<'a>,
 }
 
-delegate_iterator!((Values<'a>) => &'a Value);
+delegate_iterator!(impl['a, M: Map] Values<'a, M> => &'a Value);
 
 //////////////////////////////////////////////////////////////////////////////
 
 /// A mutable iterator over a `plist::Dictionary`'s values.
-pub struct ValuesMut<'a> {
-    iter: map::ValuesMut<'a, String, Value>,
+pub struct ValuesMut<'a, M: Map = IndexMap<String, Value>> {
+    iter: M::ValuesMut<'a>,
 }
 
-delegate_iterator!((ValuesMut<'a>) => &'a mut Value);
+delegate_iterator!(impl['a, M: Map] ValuesMut<'a, M> => &'a mut Value);
+
+//////////////////////////////////////////////////////////////////////////////
+
+/// A draining iterator over a range of a `plist::Dictionary`'s entries.
+///
+/// This struct is created by [`Dictionary::drain`].
+pub struct Drain<'a> {
+    iter: map::Drain<'a, String, Value>,
+}
+
+delegate_iterator!(impl['a] Drain<'a> => (String, Value));
 
 #[cfg(feature = "serde")]
 pub mod serde_impls {
     use serde::{de, ser};
-    use std::fmt;
+    use std::{fmt, marker::PhantomData};
 
-    use crate::Dictionary;
+    use super::{Dictionary, Map};
 
-    impl ser::Serialize for Dictionary {
+    impl<M: Map> ser::Serialize for Dictionary<M> {
         #[inline]
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where
@@ -655,16 +1262,16 @@ pub mod serde_impls {
         }
     }
 
-    impl<'de> de::Deserialize<'de> for Dictionary {
+    impl<'de, M: Map> de::Deserialize<'de> for Dictionary<M> {
         #[inline]
         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where
             D: de::Deserializer<'de>,
         {
-            struct Visitor;
+            struct Visitor<M>(PhantomData<M>);
 
-            impl<'de> de::Visitor<'de> for Visitor {
-                type Value = Dictionary;
+            impl<'de, M: Map> de::Visitor<'de> for Visitor<M> {
+                type Value = Dictionary<M>;
 
                 fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
                     formatter.write_str("a map")
@@ -675,7 +1282,7 @@ pub mod serde_impls {
                 where
                     E: de::Error,
                 {
-                    Ok(Dictionary::new())
+                    Ok(Dictionary::default())
                 }
 
                 #[inline]
@@ -683,7 +1290,7 @@ pub mod serde_impls {
                 where
                     V: de::MapAccess<'de>,
                 {
-                    let mut values = Dictionary::new();
+                    let mut values = Dictionary::default();
 
                     while let Some((key, value)) = visitor.next_entry()? {
                         values.insert(key, value);
@@ -693,14 +1300,85 @@ pub mod serde_impls {
                 }
             }
 
-            deserializer.deserialize_map(Visitor)
+            deserializer.deserialize_map(Visitor(PhantomData))
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
     use super::Dictionary;
+    use crate::Value;
+
+    fn keys(dict: &Dictionary) -> Vec<&str> {
+        dict.keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn ordered_operations() {
+        let mut dict: Dictionary = [("a", 1), ("b", 2), ("c", 3), ("d", 4)]
+            .into_iter()
+            .collect();
+
+        assert_eq!(dict.get_index(1), Some((&"b".to_owned(), &Value::from(2))));
+        assert_eq!(dict.get_index(4), None);
+        assert_eq!(dict.get_index_of("c"), Some(2));
+        assert_eq!(dict.first().map(|(k, _)| k.as_str()), Some("a"));
+        assert_eq!(dict.last().map(|(k, _)| k.as_str()), Some("d"));
+        assert_eq!(dict.iter().next_back().map(|(k, _)| k.as_str()), Some("d"));
+
+        assert_eq!(dict.shift_remove("b"), Some(2.into()));
+        assert_eq!(keys(&dict), ["a", "c", "d"]);
+        assert_eq!(dict.swap_remove("a"), Some(1.into()));
+        assert_eq!(keys(&dict), ["d", "c"]);
+
+        assert_eq!(dict.shift_insert(1, "e".to_owned(), 5.into()), None);
+        assert_eq!(
+            dict.shift_insert(0, "c".to_owned(), 6.into()),
+            Some(3.into())
+        );
+        assert_eq!(keys(&dict), ["c", "d", "e"]);
+
+        dict.move_index(0, 2);
+        assert_eq!(keys(&dict), ["d", "e", "c"]);
+        dict.sort_unstable_by(|a, _, b, _| b.cmp(a));
+        assert_eq!(keys(&dict), ["e", "d", "c"]);
+
+        let drained: Vec<_> = dict.drain(..2).map(|(k, _)| k).collect();
+        assert_eq!(drained, ["e", "d"]);
+        assert_eq!(keys(&dict), ["c"]);
+    }
+
+    #[test]
+    fn sorted_map() {
+        let mut dict: Dictionary<BTreeMap<String, Value>> =
+            [("c", 3), ("a", 1)].into_iter().collect();
+        dict.insert("b".to_owned(), 2.into());
+        *dict.entry("d").or_insert(0.into()) = 4.into();
+        dict.entry("a").and_modify(|a| *a = 5.into());
+
+        assert_eq!(
+            dict.keys().map(String::as_str).collect::<Vec<_>>(),
+            ["a", "b", "c", "d"]
+        );
+        assert_eq!(dict["a"], 5.into());
+        assert_eq!(dict.iter().next_back().map(|(k, _)| k.as_str()), Some("d"));
+        assert_eq!(dict.remove("b"), Some(2.into()));
+
+        let value = Value::from(dict.clone());
+        let mut buf = Vec::new();
+        value.to_writer_xml(&mut buf).unwrap();
+        let xml = String::from_utf8(buf).unwrap();
+        assert!(xml.find("<key>a</key>") < xml.find("<key>c</key>"));
+        assert!(xml.find("<key>c</key>") < xml.find("<key>d</key>"));
+
+        let mut unsorted: Dictionary = dict.clone().into();
+        assert_eq!(keys(&unsorted), ["a", "c", "d"]);
+        unsorted.move_index(0, 2);
+        assert_eq!(Dictionary::<BTreeMap<_, _>>::from(unsorted), dict);
+    }
 
     #[test]
     fn from_hash_map_to_dict() {
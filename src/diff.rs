@@ -0,0 +1,397 @@
+use std::fmt;
+
+use crate::{key_path, query::PathElement, Dictionary, Value};
+
+/// Options for customizing how [`diff_with_options`] compares values.
+#[derive(Clone, Debug, Default)]
+pub struct DiffOptions {
+    ordered_dictionaries: bool,
+}
+
+impl DiffOptions {
+    /// Default options for comparing values.
+    pub fn new() -> Self {
+        DiffOptions::default()
+    }
+
+    /// Selects whether the order of keys in a dictionary is significant.
+    ///
+    /// If `true` a [`Change::Reordered`] is reported for any dictionary whose keys common to both
+    /// values appear in a different order. The default is `false`.
+    pub fn ordered_dictionaries(mut self, ordered: bool) -> Self {
+        self.ordered_dictionaries = ordered;
+        self
+    }
+}
+
+/// The structural differences between two values, as returned by [`diff`].
+///
+/// The `Display` implementation renders one change per line, for example:
+///
+/// ```text
+/// ~ CFBundleVersion: "41" -> "42"
+/// + CFBundleURLTypes.0.CFBundleURLSchemes.1: "app-dev"
+/// - NSCameraUsageDescription: "Scan documents"
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diff<'a> {
+    changes: Vec<Change<'a>>,
+}
+
+/// A single difference between two values.
+///
+/// Paths refer to the location of the change in the new value, except for removed values whose
+/// path refers to the old value.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Change<'a> {
+    /// A dictionary entry or array element is only present in the new value.
+    Added {
+        path: Vec<PathElement<'a>>,
+        value: &'a Value,
+    },
+    /// A dictionary entry or array element is only present in the old value.
+    Removed {
+        path: Vec<PathElement<'a>>,
+        value: &'a Value,
+    },
+    /// A value differs between the old and new values and they cannot be compared further, for
+    /// example because they have different types.
+    Changed {
+        path: Vec<PathElement<'a>>,
+        old: &'a Value,
+        new: &'a Value,
+    },
+    /// The keys of a dictionary appear in a different order.
+    ///
+    /// Only reported when [`DiffOptions::ordered_dictionaries`] is enabled. The key lists contain
+    /// only the keys present in both dictionaries.
+    Reordered {
+        path: Vec<PathElement<'a>>,
+        old_keys: Vec<&'a str>,
+        new_keys: Vec<&'a str>,
+    },
+}
+
+/// Compares two values, ignoring the order of dictionary keys.
+///
+/// Dictionaries are compared by key and arrays element by element. Reals are compared by their
+/// bit patterns so a `NaN` is equal to itself.
+///
+/// ```
+/// use plist::{Change, Dictionary, Value};
+///
+/// let mut old = Dictionary::new();
+/// old.insert("CFBundleVersion".to_owned(), "41".into());
+/// old.insert("UIRequiresFullScreen".to_owned(), true.into());
+/// let mut new = Dictionary::new();
+/// new.insert("CFBundleVersion".to_owned(), "42".into());
+/// new.insert("ITSAppUsesNonExemptEncryption".to_owned(), false.into());
+/// let (old, new) = (Value::from(old), Value::from(new));
+///
+/// let diff = plist::diff(&old, &new);
+///
+/// assert_eq!(diff.changes().len(), 3);
+/// assert!(matches!(diff.changes()[0], Change::Changed { .. }));
+/// assert_eq!(
+///     diff.to_string(),
+///     "~ CFBundleVersion: \"41\" -> \"42\"\n\
+///      - UIRequiresFullScreen: true\n\
+///      + ITSAppUsesNonExemptEncryption: false\n"
+/// );
+/// ```
+pub fn diff<'a>(old: &'a Value, new: &'a Value) -> Diff<'a> {
+    diff_with_options(old, new, &DiffOptions::default())
+}
+
+/// Compares two values using the specified options.
+///
+/// See [`diff`] for details.
+pub fn diff_with_options<'a>(old: &'a Value, new: &'a Value, options: &DiffOptions) -> Diff<'a> {
+    let mut differ = Differ {
+        options,
+        path: Vec::new(),
+        changes: Vec::new(),
+    };
+    differ.diff_values(old, new);
+    Diff {
+        changes: differ.changes,
+    }
+}
+
+impl<'a> Diff<'a> {
+    /// Returns `true` if the values are equal.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Returns the changes in the order they occur in the values.
+    pub fn changes(&self) -> &[Change<'a>] {
+        &self.changes
+    }
+
+    /// Consumes the diff and returns its changes.
+    pub fn into_changes(self) -> Vec<Change<'a>> {
+        self.changes
+    }
+}
+
+impl fmt::Display for Diff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Change<'a> {
+    /// Returns the location of this change.
+    pub fn path(&self) -> &[PathElement<'a>] {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Changed { path, .. }
+            | Change::Reordered { path, .. } => path,
+        }
+    }
+
+    /// Returns the location of this change as a dotted key path which can be passed to
+    /// [`Value::get_at_path`].
+    pub fn key_path(&self) -> String {
+        key_path::join(self.path())
+    }
+}
+
+impl fmt::Display for Change<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut key_path = self.key_path();
+        if key_path.is_empty() {
+            key_path.push_str("(root)");
+        }
+        match self {
+            Change::Added { value, .. } => write!(f, "+ {key_path}: {}", Summary(value)),
+            Change::Removed { value, .. } => write!(f, "- {key_path}: {}", Summary(value)),
+            Change::Changed { old, new, .. } => {
+                write!(f, "~ {key_path}: {} -> {}", Summary(old), Summary(new))
+            }
+            Change::Reordered {
+                old_keys, new_keys, ..
+            } => write!(
+                f,
+                "~ {key_path}: keys reordered from [{}] to [{}]",
+                old_keys.join(", "),
+                new_keys.join(", ")
+            ),
+        }
+    }
+}
+
+/// Renders a value on a single line.
+pub(crate) struct Summary<'a>(pub(crate) &'a Value);
+
+impl fmt::Display for Summary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Value::Array(array) => write!(f, "array of {} elements", array.len()),
+            Value::Dictionary(dict) => write!(f, "dictionary of {} entries", dict.len()),
+            Value::Boolean(b) => write!(f, "{b}"),
+            Value::Data(data) => write!(f, "data of {} bytes", data.len()),
+            Value::Date(date) => write!(f, "{}", date.to_xml_format()),
+            Value::Real(real) => write!(f, "{real:?}"),
+            Value::Integer(integer) => write!(f, "{integer}"),
+            Value::String(string) => write!(f, "{string:?}"),
+            Value::Uid(uid) => write!(f, "{uid:?}"),
+        }
+    }
+}
+
+struct Differ<'a, 'o> {
+    options: &'o DiffOptions,
+    path: Vec<PathElement<'a>>,
+    changes: Vec<Change<'a>>,
+}
+
+impl<'a> Differ<'a, '_> {
+    fn diff_values(&mut self, old: &'a Value, new: &'a Value) {
+        match (old, new) {
+            (Value::Dictionary(old), Value::Dictionary(new)) => self.diff_dictionaries(old, new),
+            (Value::Array(old), Value::Array(new)) => self.diff_arrays(old, new),
+            (Value::Real(a), Value::Real(b)) if a.to_bits() == b.to_bits() => (),
+            (Value::Real(_), Value::Real(_)) => self.changed(old, new),
+            _ if old == new => (),
+            _ => self.changed(old, new),
+        }
+    }
+
+    fn diff_dictionaries(&mut self, old: &'a Dictionary, new: &'a Dictionary) {
+        if self.options.ordered_dictionaries {
+            let old_keys: Vec<&str> = old
+                .keys()
+                .filter(|key| new.contains_key(key))
+                .map(String::as_str)
+                .collect();
+            let new_keys: Vec<&str> = new
+                .keys()
+                .filter(|key| old.contains_key(key))
+                .map(String::as_str)
+                .collect();
+            if old_keys != new_keys {
+                self.changes.push(Change::Reordered {
+                    path: self.path.clone(),
+                    old_keys,
+                    new_keys,
+                });
+            }
+        }
+
+        for (key, old_value) in old {
+            self.path.push(PathElement::Key(key));
+            match new.get(key) {
+                Some(new_value) => self.diff_values(old_value, new_value),
+                None => self.changes.push(Change::Removed {
+                    path: self.path.clone(),
+                    value: old_value,
+                }),
+            }
+            self.path.pop();
+        }
+
+        for (key, new_value) in new {
+            if !old.contains_key(key) {
+                self.path.push(PathElement::Key(key));
+                self.changes.push(Change::Added {
+                    path: self.path.clone(),
+                    value: new_value,
+                });
+                self.path.pop();
+            }
+        }
+    }
+
+    fn diff_arrays(&mut self, old: &'a [Value], new: &'a [Value]) {
+        for (i, (old_value, new_value)) in old.iter().zip(new).enumerate() {
+            self.path.push(PathElement::Index(i));
+            self.diff_values(old_value, new_value);
+            self.path.pop();
+        }
+
+        for (i, value) in old.iter().enumerate().skip(new.len()) {
+            self.path.push(PathElement::Index(i));
+            self.changes.push(Change::Removed {
+                path: self.path.clone(),
+                value,
+            });
+            self.path.pop();
+        }
+
+        for (i, value) in new.iter().enumerate().skip(old.len()) {
+            self.path.push(PathElement::Index(i));
+            self.changes.push(Change::Added {
+                path: self.path.clone(),
+                value,
+            });
+            self.path.pop();
+        }
+    }
+
+    fn changed(&mut self, old: &'a Value, new: &'a Value) {
+        self.changes.push(Change::Changed {
+            path: self.path.clone(),
+            old,
+            new,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dict(entries: Vec<(&str, Value)>) -> Value {
+        let mut dict = Dictionary::new();
+        for (key, value) in entries {
+            dict.insert(key.to_owned(), value);
+        }
+        dict.into()
+    }
+
+    #[test]
+    fn nested_changes() {
+        let old = dict(vec![
+            ("Name", "App".into()),
+            ("Schemes", vec![Value::from("app")].into()),
+            (
+                "Nested",
+                dict(vec![("a.b", 1.into()), ("Real", f64::NAN.into())]),
+            ),
+        ]);
+        let new = dict(vec![
+            ("Name", "App".into()),
+            (
+                "Schemes",
+                vec![Value::from("app"), Value::from("app-dev")].into(),
+            ),
+            (
+                "Nested",
+                dict(vec![("a.b", 1.5.into()), ("Real", f64::NAN.into())]),
+            ),
+        ]);
+
+        let diff = diff(&old, &new);
+        assert_eq!(
+            diff.changes()
+                .iter()
+                .map(Change::key_path)
+                .collect::<Vec<_>>(),
+            vec!["Schemes.1", r"Nested.a\.b"]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "+ Schemes.1: \"app-dev\"\n~ Nested.a\\.b: 1 -> 1.5\n"
+        );
+        assert!(super::diff(&old, &old.clone()).is_empty());
+    }
+
+    #[test]
+    fn root_and_type_changes() {
+        let old = Value::from(vec![Value::from(1), Value::from(2)]);
+        let new = Value::from("string");
+
+        assert_eq!(
+            diff(&old, &new).to_string(),
+            "~ (root): array of 2 elements -> \"string\"\n"
+        );
+        assert_eq!(
+            diff(&Value::from(vec![Value::from(1)]), &old).into_changes(),
+            vec![Change::Added {
+                path: vec![PathElement::Index(1)],
+                value: &Value::from(2),
+            }]
+        );
+    }
+
+    #[test]
+    fn dictionary_order() {
+        let old = dict(vec![("a", 1.into()), ("b", 2.into()), ("c", 3.into())]);
+        let new = dict(vec![("b", 2.into()), ("a", 1.into()), ("d", 4.into())]);
+
+        assert_eq!(diff(&old, &new).to_string(), "- c: 3\n+ d: 4\n");
+
+        let options = DiffOptions::new().ordered_dictionaries(true);
+        let diff = diff_with_options(&old, &new, &options);
+        assert_eq!(
+            diff.changes()[0],
+            Change::Reordered {
+                path: Vec::new(),
+                old_keys: vec!["a", "b"],
+                new_keys: vec!["b", "a"],
+            }
+        );
+        assert_eq!(
+            diff.changes()[0].to_string(),
+            "~ (root): keys reordered from [a, b] to [b, a]"
+        );
+        assert_eq!(diff.changes().len(), 3);
+    }
+}
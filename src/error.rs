@@ -2,9 +2,8 @@ use std::{error, fmt, io};
 use quick_xml::escape::EscapeError;
 use quick_xml::encoding::EncodingError;
 
-#[cfg(feature = "serde")]
 use crate::stream::Event;
-use crate::{InvalidXmlDate, Value};
+use crate::{InvalidXmlDate, Value, ValueRef};
 
 /// This type represents all possible errors that can occur when working with plist data.
 #[derive(Debug)]
@@ -16,6 +15,8 @@ pub struct Error {
 pub(crate) struct ErrorImpl {
     kind: ErrorKind,
     file_position: Option<FilePosition>,
+    context: Option<String>,
+    key_path: Option<String>,
 }
 
 #[derive(Debug)]
@@ -53,6 +54,7 @@ pub(crate) enum ErrorKind {
     InvalidIntegerString,
     InvalidRealString,
     UidNotSupportedInXmlPlist,
+    InvalidXmlCharacter,
 
     // Binary format-specific errors
     ObjectTooLarge,
@@ -66,6 +68,7 @@ pub(crate) enum ErrorKind {
     NullObjectUnimplemented,
     FillObjectUnimplemented,
     IntegerOutOfRange,
+    RealOutOfRange,
     InfiniteOrNanDate,
     InvalidUtf8String,
     InvalidUtf16String,
@@ -74,6 +77,22 @@ pub(crate) enum ErrorKind {
         #[allow(dead_code)] u8,
     ),
 
+    // Document editing errors
+    KeyPathNotFound,
+
+    // Query errors
+    InvalidQuery,
+
+    // Patch errors
+    InvalidPatch,
+    PatchTestFailed,
+
+    // Merge errors
+    MergeTypeConflict,
+
+    // Canonicalization errors
+    DuplicateDictionaryKey,
+
     Io(io::Error),
     #[cfg(feature = "serde")]
     Serde(
@@ -82,8 +101,47 @@ pub(crate) enum ErrorKind {
     ),
 }
 
+/// A component of the key path to the value which caused an error.
+pub(crate) enum KeyPathSegment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
 #[derive(Debug, Clone, Copy)]
-pub(crate) struct FilePosition(pub(crate) u64);
+pub(crate) struct FilePosition {
+    pub(crate) offset: u64,
+    /// The one-based line and byte column, if known.
+    pub(crate) line_column: Option<(u64, u64)>,
+}
+
+/// The general category of an [`Error`].
+///
+/// New categories may be added in minor releases as the errors produced by this crate are
+/// refined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCategory {
+    /// Reading or writing bytes on an IO stream failed.
+    ///
+    /// These errors may be transient and it can be worth retrying the operation.
+    Io,
+    /// The input data ended before the plist was complete.
+    Eof,
+    /// The input data is not a well-formed plist in its encoding.
+    Syntax,
+    /// A value in the input data could not be decoded, for example an invalid date or integer.
+    InvalidValue,
+    /// The plist contains a value or object which is not supported by its encoding or by this
+    /// crate.
+    Unsupported,
+    /// The plist does not have the structure expected by the operation, for example a
+    /// dictionary where a string was expected when deserializing.
+    UnexpectedStructure,
+    /// A key path did not refer to a value in a document.
+    KeyPathNotFound,
+    /// A patch `test` operation found a value different to the one expected.
+    PatchTestFailed,
+}
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub(crate) enum EventKind {
@@ -113,6 +171,103 @@ impl Error {
         matches!(self.inner.kind, ErrorKind::UnexpectedEof)
     }
 
+    /// Returns the general category of this error.
+    pub fn category(&self) -> ErrorCategory {
+        self.inner.kind.category()
+    }
+
+    /// Returns the byte offset in the input data at which this error occurred, if known.
+    pub fn byte_offset(&self) -> Option<u64> {
+        self.inner.file_position.map(|pos| pos.offset)
+    }
+
+    /// Returns the one-based line in the input data at which this error occurred, if known.
+    ///
+    /// Lines are only tracked when reading XML and ASCII plists.
+    pub fn line(&self) -> Option<u64> {
+        self.line_column().map(|(line, _)| line)
+    }
+
+    /// Returns the one-based column, in bytes, in the input data at which this error occurred, if
+    /// known.
+    ///
+    /// Columns are only tracked when reading XML and ASCII plists.
+    pub fn column(&self) -> Option<u64> {
+        self.line_column().map(|(_, column)| column)
+    }
+
+    fn line_column(&self) -> Option<(u64, u64)> {
+        self.inner.file_position?.line_column
+    }
+
+    /// Returns a type which renders this error as a report showing the line of `source` the
+    /// error occurred on, with a caret under the column.
+    ///
+    /// `source` must be the text the plist was read from. This is intended for XML and ASCII
+    /// plists and is of little use for binary plists.
+    ///
+    /// ```
+    /// let source = "<plist>\n<array>\n  <string>a</strin>\n</array>\n</plist>";
+    /// let err = plist::Value::from_reader_xml(source.as_bytes()).unwrap_err();
+    /// let report = err.display_with_source(source).to_string();
+    /// assert!(report.contains("3 |   <string>a</strin>"));
+    /// assert!(report.contains("expected </string> to close element opened at line 3"));
+    /// ```
+    pub fn display_with_source<'a>(&'a self, source: &'a str) -> impl fmt::Display + 'a {
+        DisplayWithSource {
+            error: self,
+            source,
+        }
+    }
+
+    /// Returns the path to the value which caused this error when serializing, deserializing,
+    /// merging or converting, if known.
+    ///
+    /// Dictionary keys are separated by `.` and array indices are written in brackets, for
+    /// example `Targets[3].BuildSettings.SDKROOT`. A literal `.`, `[` or `\` in a dictionary key
+    /// is escaped with a preceding `\`.
+    pub fn key_path(&self) -> Option<&str> {
+        self.inner.key_path.as_deref()
+    }
+
+    /// Adds a key path segment for a collection containing the value which caused this error.
+    pub(crate) fn with_outer_key_path_segment(mut self, segment: KeyPathSegment) -> Error {
+        let inner_path = self.inner.key_path.take().unwrap_or_default();
+        let separator = if inner_path.is_empty() || inner_path.starts_with('[') {
+            ""
+        } else {
+            "."
+        };
+        let mut key_path = String::new();
+        match segment {
+            KeyPathSegment::Key(key) => {
+                for c in key.chars() {
+                    if matches!(c, '.' | '[' | '\\') {
+                        key_path.push('\\');
+                    }
+                    key_path.push(c);
+                }
+            }
+            KeyPathSegment::Index(index) => key_path.push_str(&format!("[{index}]")),
+        }
+        key_path.push_str(separator);
+        key_path.push_str(&inner_path);
+        self.inner.key_path = Some(key_path);
+        self
+    }
+
+    /// Sets the position of this error if it is not already known.
+    #[cfg(feature = "serde")]
+    pub(crate) fn with_default_position(mut self, position: FilePosition) -> Error {
+        self.inner.file_position.get_or_insert(position);
+        self
+    }
+
+    pub(crate) fn with_context(mut self, context: String) -> Error {
+        self.inner.context = Some(context);
+        self
+    }
+
     /// Returns the underlying error if it was caused by a failure to read or write bytes on an IO
     /// stream.
     pub fn as_io(&self) -> Option<&io::Error> {
@@ -146,16 +301,97 @@ impl error::Error for Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if let Some(position) = &self.inner.file_position {
-            write!(f, "{:?} ({})", &self.inner.kind, position)
+            write!(f, "{:?} ({})", &self.inner.kind, position)?;
         } else {
-            fmt::Debug::fmt(&self.inner.kind, f)
+            fmt::Debug::fmt(&self.inner.kind, f)?;
+        }
+        if let Some(key_path) = &self.inner.key_path {
+            write!(f, " at {key_path}")?;
+        }
+        if let Some(context) = &self.inner.context {
+            write!(f, ": {context}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders an [`Error`] alongside the line of source it occurred on.
+struct DisplayWithSource<'a> {
+    error: &'a Error,
+    source: &'a str,
+}
+
+impl fmt::Display for DisplayWithSource<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let inner = &self.error.inner;
+        writeln!(f, "error: {:?}", inner.kind)?;
+
+        if let Some((line_number, column, line)) = self.locate() {
+            let gutter = " ".repeat(line_number.to_string().len());
+            // Keep tabs so the caret lines up with the source line.
+            let caret_indent: String = line
+                .char_indices()
+                .take_while(|(i, _)| *i + 1 < column)
+                .map(|(_, c)| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+
+            writeln!(f, "{gutter}--> line {line_number}, column {column}")?;
+            writeln!(f, "{gutter} |")?;
+            writeln!(f, "{line_number} | {line}")?;
+            writeln!(f, "{gutter} | {caret_indent}^")?;
+            if let Some(context) = &inner.context {
+                writeln!(f, "{gutter} = note: {context}")?;
+            }
+        } else if let Some(context) = &inner.context {
+            writeln!(f, " = note: {context}")?;
         }
+        Ok(())
+    }
+}
+
+impl DisplayWithSource<'_> {
+    /// Returns the one-based line number and byte column of the error along with the text of its
+    /// line.
+    fn locate(&self) -> Option<(u64, usize, &str)> {
+        let position = self.error.inner.file_position?;
+        let (line_number, line_start, column) = match position.line_column {
+            Some((line_number, column)) => {
+                let line_start = if line_number == 1 {
+                    0
+                } else {
+                    self.source
+                        .match_indices('\n')
+                        .nth(usize::try_from(line_number - 2).ok()?)?
+                        .0
+                        + 1
+                };
+                (line_number, line_start, usize::try_from(column).ok()?)
+            }
+            None => {
+                let offset = usize::try_from(position.offset).ok()?;
+                let before = self.source.as_bytes().get(..offset)?;
+                let line_start = before
+                    .iter()
+                    .rposition(|&b| b == b'\n')
+                    .map_or(0, |i| i + 1);
+                let line_number = before.iter().filter(|&&b| b == b'\n').count() as u64 + 1;
+                (line_number, line_start, offset - line_start + 1)
+            }
+        };
+
+        let rest = self.source.get(line_start..)?;
+        let line = rest.split('\n').next().unwrap_or(rest);
+        Some((line_number, column, line.strip_suffix('\r').unwrap_or(line)))
     }
 }
 
 impl fmt::Display for FilePosition {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "offset {}", self.0)
+        write!(f, "offset {}", self.offset)?;
+        if let Some((line, column)) = self.line_column {
+            write!(f, ", line {line}, column {column}")?;
+        }
+        Ok(())
     }
 }
 
@@ -167,7 +403,10 @@ impl From<InvalidXmlDate> for Error {
 
 impl ErrorKind {
     pub fn with_byte_offset(self, offset: u64) -> Error {
-        self.with_position(FilePosition(offset))
+        self.with_position(FilePosition {
+            offset,
+            line_column: None,
+        })
     }
 
     pub fn with_position(self, pos: FilePosition) -> Error {
@@ -175,6 +414,8 @@ impl ErrorKind {
             inner: Box::new(ErrorImpl {
                 kind: self,
                 file_position: Some(pos),
+                context: None,
+                key_path: None,
             }),
         }
     }
@@ -184,11 +425,69 @@ impl ErrorKind {
             inner: Box::new(ErrorImpl {
                 kind: self,
                 file_position: None,
+                context: None,
+                key_path: None,
             }),
         }
     }
 }
 
+impl ErrorKind {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            ErrorKind::UnexpectedEof => ErrorCategory::Eof,
+            ErrorKind::UnexpectedEndOfEventStream
+            | ErrorKind::UnexpectedEventType { .. }
+            | ErrorKind::ExpectedEndOfEventStream { .. } => ErrorCategory::UnexpectedStructure,
+
+            ErrorKind::UnclosedString
+            | ErrorKind::IncompleteComment
+            | ErrorKind::InvalidUtf8AsciiStream
+            | ErrorKind::InvalidOctalString
+            | ErrorKind::UnclosedXmlElement
+            | ErrorKind::UnexpectedXmlCharactersExpectedElement
+            | ErrorKind::UnexpectedXmlOpeningTag
+            | ErrorKind::UnknownXmlElement
+            | ErrorKind::InvalidXmlSyntax
+            | ErrorKind::InvalidXmlUtf8
+            | ErrorKind::ObjectTooLarge
+            | ErrorKind::InvalidMagic
+            | ErrorKind::InvalidTrailerObjectOffsetSize
+            | ErrorKind::InvalidTrailerObjectReferenceSize
+            | ErrorKind::InvalidObjectLength
+            | ErrorKind::ObjectReferenceTooLarge
+            | ErrorKind::ObjectOffsetTooLarge
+            | ErrorKind::RecursiveObject
+            | ErrorKind::UnknownObjectType(_)
+            | ErrorKind::InvalidQuery => ErrorCategory::Syntax,
+
+            ErrorKind::InvalidDataString
+            | ErrorKind::InvalidDateString
+            | ErrorKind::InvalidIntegerString
+            | ErrorKind::InvalidRealString
+            | ErrorKind::IntegerOutOfRange
+            | ErrorKind::RealOutOfRange
+            | ErrorKind::InfiniteOrNanDate
+            | ErrorKind::InvalidUtf8String
+            | ErrorKind::InvalidUtf16String => ErrorCategory::InvalidValue,
+
+            ErrorKind::UidNotSupportedInXmlPlist
+            | ErrorKind::InvalidXmlCharacter
+            | ErrorKind::NullObjectUnimplemented
+            | ErrorKind::FillObjectUnimplemented => ErrorCategory::Unsupported,
+
+            ErrorKind::KeyPathNotFound => ErrorCategory::KeyPathNotFound,
+            ErrorKind::InvalidPatch
+            | ErrorKind::MergeTypeConflict
+            | ErrorKind::DuplicateDictionaryKey => ErrorCategory::UnexpectedStructure,
+            ErrorKind::PatchTestFailed => ErrorCategory::PatchTestFailed,
+            ErrorKind::Io(_) => ErrorCategory::Io,
+            #[cfg(feature = "serde")]
+            ErrorKind::Serde(_) => ErrorCategory::UnexpectedStructure,
+        }
+    }
+}
+
 impl From<InvalidXmlDate> for ErrorKind {
     fn from(_: InvalidXmlDate) -> Self {
         ErrorKind::InvalidDateString
@@ -208,7 +507,6 @@ impl From<EncodingError> for ErrorKind {
 }
 
 impl EventKind {
-    #[cfg(feature = "serde")]
     pub fn of_event(event: &Event) -> EventKind {
         match event {
             Event::StartArray(_) => EventKind::StartArray,
@@ -237,6 +535,20 @@ impl EventKind {
             Value::Uid(_) => EventKind::Uid,
         }
     }
+
+    pub fn of_value_ref(value: &ValueRef) -> EventKind {
+        match value {
+            ValueRef::Array(_) => EventKind::StartArray,
+            ValueRef::Dictionary(_) => EventKind::StartDictionary,
+            ValueRef::Boolean(_) => EventKind::Boolean,
+            ValueRef::Data(_) => EventKind::Data,
+            ValueRef::Date(_) => EventKind::Date,
+            ValueRef::Integer(_) => EventKind::Integer,
+            ValueRef::Real(_) => EventKind::Real,
+            ValueRef::String(_) => EventKind::String,
+            ValueRef::Uid(_) => EventKind::Uid,
+        }
+    }
 }
 
 impl fmt::Display for EventKind {
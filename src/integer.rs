@@ -17,13 +17,36 @@ impl Integer {
         u64::try_from(self.value).ok()
     }
 
+    pub(crate) fn as_i128(self) -> i128 {
+        self.value
+    }
+
     pub(crate) fn from_str(s: &str) -> Result<Self, ParseIntError> {
-        if s.starts_with("0x") {
-            // NetBSD dialect adds the `0x` numeric objects,
-            // which are always unsigned.
-            // See the `PROP_NUMBER(3)` man page
-            let s = s.trim_start_matches("0x");
-            u64::from_str_radix(s, 16).map(Into::into)
+        let (is_negative, unsigned) = match s.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+
+        // CoreFoundation accepts an optional sign followed by a `0x` or `0X` prefix for
+        // hexadecimal integers. The NetBSD dialect also adds `0x` numeric objects.
+        // See the `PROP_NUMBER(3)` man page
+        let hex = unsigned
+            .strip_prefix("0x")
+            .or_else(|| unsigned.strip_prefix("0X"));
+
+        if let Some(hex) = hex {
+            if hex.starts_with(['+', '-']) {
+                // `from_str_radix` accepts a sign, but one isn't allowed after the prefix. Parse
+                // the sign on its own so it's reported as an invalid digit.
+                return u64::from_str_radix(&hex[..1], 16).map(Into::into);
+            }
+            if is_negative {
+                // Negative hexadecimal integers must fit in an i64. Include the sign so the
+                // parser handles `-0x8000000000000000` correctly.
+                i64::from_str_radix(&format!("-{hex}"), 16).map(Into::into)
+            } else {
+                u64::from_str_radix(hex, 16).map(Into::into)
+            }
         } else {
             // Match Apple's implementation in CFPropertyList.h - always try to parse as an i64 first.
             // TODO: Use IntErrorKind once stable and retry parsing on overflow only.
@@ -190,4 +213,25 @@ mod tests {
         );
         assert!(Integer::from_str("18446744073709551616").is_err());
     }
+
+    #[test]
+    fn from_str_hex() {
+        assert_eq!(Integer::from_str("0x1F"), Ok(31.into()));
+        assert_eq!(Integer::from_str("0X1f"), Ok(31.into()));
+        assert_eq!(Integer::from_str("+0x1F"), Ok(31.into()));
+        assert_eq!(Integer::from_str("-0x1F"), Ok((-31).into()));
+        assert_eq!(
+            Integer::from_str("0xFFFFFFFFFFFFFFFF"),
+            Ok(18446744073709551615u64.into())
+        );
+        assert_eq!(
+            Integer::from_str("-0x8000000000000000"),
+            Ok((-9223372036854775808i64).into())
+        );
+        assert!(Integer::from_str("-0x8000000000000001").is_err());
+        assert!(Integer::from_str("0x").is_err());
+        assert!(Integer::from_str("-0x-1").is_err());
+        assert!(Integer::from_str("0x+1F").is_err());
+        assert!(Integer::from_str("-0x+1F").is_err());
+    }
 }
@@ -0,0 +1,153 @@
+//! Key paths in the dotted syntax used by `plutil`.
+//!
+//! A key path is a sequence of components separated by `.`. Each component is either a
+//! dictionary key or, when the containing value is an array, a decimal array index. A literal `.`
+//! or `\` in a dictionary key is escaped with a preceding `\`. The empty key path refers to the
+//! root value.
+
+use crate::{query::PathElement, Value};
+
+/// Splits a key path into its components, resolving any escapes.
+pub(crate) fn split(key_path: &str) -> Vec<String> {
+    let mut components = Vec::new();
+    if key_path.is_empty() {
+        return components;
+    }
+
+    let mut component = String::new();
+    let mut chars = key_path.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => component.push(chars.next().unwrap_or('\\')),
+            '.' => components.push(std::mem::take(&mut component)),
+            c => component.push(c),
+        }
+    }
+    components.push(component);
+    components
+}
+
+/// Joins path elements into a key path, escaping any `.` and `\` in dictionary keys.
+pub(crate) fn join(path: &[PathElement]) -> String {
+    let mut key_path = String::new();
+    for (i, element) in path.iter().enumerate() {
+        if i > 0 {
+            key_path.push('.');
+        }
+        match element {
+            PathElement::Key(key) => {
+                for c in key.chars() {
+                    if c == '.' || c == '\\' {
+                        key_path.push('\\');
+                    }
+                    key_path.push(c);
+                }
+            }
+            PathElement::Index(index) => key_path.push_str(&index.to_string()),
+        }
+    }
+    key_path
+}
+
+/// Joins path elements into a JSON pointer (RFC 6901), escaping any `~` and `/` in dictionary
+/// keys.
+pub(crate) fn to_pointer(path: &[PathElement]) -> String {
+    let mut pointer = String::new();
+    for element in path {
+        pointer.push('/');
+        match element {
+            PathElement::Key(key) => pointer.push_str(&key.replace('~', "~0").replace('/', "~1")),
+            PathElement::Index(index) => pointer.push_str(&index.to_string()),
+        }
+    }
+    pointer
+}
+
+/// Splits a JSON pointer (RFC 6901) into its reference tokens, resolving any escapes.
+///
+/// Returns `None` if the pointer is neither empty nor starts with a `/`.
+pub(crate) fn split_pointer(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+    let tokens = pointer.strip_prefix('/')?;
+    Some(
+        tokens
+            .split('/')
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+/// Parses a key path component as an array index.
+pub(crate) fn index(component: &str) -> Option<usize> {
+    // `usize::from_str` accepts a leading `+` and leading zeros which are not valid array
+    // indices.
+    if component.starts_with('+') || (component.starts_with('0') && component.len() != 1) {
+        return None;
+    }
+    component.parse().ok()
+}
+
+/// Returns the value at the given key path components.
+pub(crate) fn get<'a, S: AsRef<str>>(value: &'a Value, components: &[S]) -> Option<&'a Value> {
+    components
+        .iter()
+        .try_fold(value, |value, component| match value {
+            Value::Dictionary(dict) => dict.get(component.as_ref()),
+            Value::Array(array) => array.get(index(component.as_ref())?),
+            _ => None,
+        })
+}
+
+/// Returns a mutable reference to the value at the given key path components.
+pub(crate) fn get_mut<'a, S: AsRef<str>>(
+    value: &'a mut Value,
+    components: &[S],
+) -> Option<&'a mut Value> {
+    components
+        .iter()
+        .try_fold(value, |value, component| match value {
+            Value::Dictionary(dict) => dict.get_mut(component.as_ref()),
+            Value::Array(array) => array.get_mut(index(component.as_ref())?),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_key_paths() {
+        assert_eq!(split(""), Vec::<String>::new());
+        assert_eq!(split("a"), ["a"]);
+        assert_eq!(split("a.0.b"), ["a", "0", "b"]);
+        assert_eq!(split("a..b"), ["a", "", "b"]);
+        assert_eq!(
+            split(r"com\.apple\.security.x\\y"),
+            ["com.apple.security", r"x\y"]
+        );
+    }
+
+    #[test]
+    fn split_pointers() {
+        assert_eq!(split_pointer(""), Some(Vec::new()));
+        assert_eq!(split_pointer("/"), Some(vec![String::new()]));
+        assert_eq!(
+            split_pointer("/a~1b/~01"),
+            Some(vec!["a/b".to_owned(), "~1".to_owned()])
+        );
+        assert_eq!(split_pointer("a"), None);
+    }
+
+    #[test]
+    fn array_indices() {
+        assert_eq!(index("0"), Some(0));
+        assert_eq!(index("12"), Some(12));
+        assert_eq!(index("+1"), None);
+        assert_eq!(index("01"), None);
+        assert_eq!(index("-1"), None);
+        assert_eq!(index("a"), None);
+    }
+}
@@ -75,31 +75,53 @@
 #![deny(warnings)] // Treat all warnings as errors
 #![deny(rustdoc::broken_intra_doc_links)]
 
+pub mod arena;
 pub mod dictionary;
+pub mod query;
+pub mod value_ref;
+pub mod visit;
 
 #[cfg(feature = "enable_unstable_features_that_may_break_with_minor_version_bumps")]
 pub mod stream;
 #[cfg(not(feature = "enable_unstable_features_that_may_break_with_minor_version_bumps"))]
 mod stream;
 
+mod convert;
 #[cfg(feature = "serde")]
 mod data;
 mod date;
+mod diff;
 mod error;
 mod integer;
+mod key_path;
+mod merge;
+mod patch;
+mod total_ord;
+mod tree_builder;
 mod uid;
 mod value;
 mod macros;
+mod xml_document;
 
 #[cfg(feature = "serde")]
 pub use data::Data;
 pub use date::{Date, InvalidXmlDate};
 pub use dictionary::Dictionary;
-pub use error::Error;
+pub use diff::{diff, diff_with_options, Change, Diff, DiffOptions};
+pub use error::{Error, ErrorCategory};
 pub use integer::Integer;
-pub use stream::XmlWriteOptions;
+pub use merge::{
+    three_way_merge, three_way_merge_with_options, ArrayMergeStrategy, DeepMergeOptions,
+    MergeArrays, MergeConflict, MergeConflicts, MergeScalars, MergeTypeConflicts,
+    ThreeWayMergeOptions,
+};
+pub use patch::{Patch, PatchOperation};
+pub use stream::{BinaryWriteOptions, CanonicalOptions, InvalidXmlCharPolicy, XmlWriteOptions};
+pub use total_ord::TotalOrd;
 pub use uid::Uid;
 pub use value::Value;
+pub use value_ref::{DictionaryRef, ValueRef};
+pub use xml_document::XmlDocument;
 
 // Optional serde module
 #[cfg(feature = "serde")]
@@ -121,8 +143,8 @@ pub use self::{de::Deserializer, ser::Serializer};
 pub use self::{
     de::{from_bytes, from_file, from_reader, from_reader_ascii, from_reader_xml, from_value},
     ser::{
-        to_file_binary, to_file_xml, to_value, to_writer_binary, to_writer_xml,
-        to_writer_xml_with_options,
+        to_file_binary, to_file_xml, to_value, to_writer_binary, to_writer_binary_with_options,
+        to_writer_xml, to_writer_xml_with_options,
     },
 };
 
@@ -0,0 +1,825 @@
+use std::{borrow::Cow, error, fmt, mem};
+
+use crate::{
+    diff::Summary,
+    error::{Error, ErrorKind, KeyPathSegment},
+    key_path,
+    query::PathElement,
+    Dictionary, TotalOrd, Value,
+};
+
+/// Options for customizing how [`three_way_merge_with_options`] merges values.
+#[derive(Clone, Debug, Default)]
+pub struct ThreeWayMergeOptions {
+    arrays: ArrayMergeStrategy,
+}
+
+/// How [`three_way_merge_with_options`] merges arrays which were changed on both sides.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ArrayMergeStrategy {
+    /// Report a conflict.
+    #[default]
+    Conflict,
+    /// Merge the elements at each index if all three arrays have the same length, otherwise
+    /// report a conflict.
+    ByIndex,
+    /// Treat arrays as ordered sets. Elements removed on either side are removed, and elements
+    /// added by `theirs` are appended to the elements of `ours`.
+    ///
+    /// This suits arrays of identifiers such as the children of a group in an Xcode project.
+    Union,
+}
+
+impl ThreeWayMergeOptions {
+    /// Default options for merging values.
+    pub fn new() -> Self {
+        ThreeWayMergeOptions::default()
+    }
+
+    /// Specifies how to merge arrays which were changed on both sides.
+    ///
+    /// The default is [`ArrayMergeStrategy::Conflict`].
+    pub fn arrays(mut self, strategy: ArrayMergeStrategy) -> Self {
+        self.arrays = strategy;
+        self
+    }
+}
+
+/// The conflicts which prevented [`three_way_merge`] from merging two values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MergeConflicts {
+    merged: Value,
+    conflicts: Vec<MergeConflict>,
+}
+
+/// A value which was changed differently by both sides of a three-way merge.
+///
+/// Each side is `None` if the value was not present on that side.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MergeConflict {
+    key_path: String,
+    base: Option<Value>,
+    ours: Option<Value>,
+    theirs: Option<Value>,
+}
+
+/// Merges the changes made by `ours` and `theirs` to their common ancestor `base`.
+///
+/// A value changed on only one side takes the changed value. Dictionaries changed on both sides
+/// are merged key by key, keeping the key order of `ours` and appending keys added by `theirs`.
+/// Arrays changed on both sides are a conflict. Use [`three_way_merge_with_options`] to merge
+/// arrays instead.
+///
+/// ```
+/// use plist::Value;
+///
+/// let read = |xml: &str| Value::from_reader_xml(xml.as_bytes()).unwrap();
+/// let base = read("<plist><dict><key>Version</key><string>1</string></dict></plist>");
+/// let ours = read("<plist><dict><key>Version</key><string>2</string></dict></plist>");
+/// let theirs = read(
+///     "<plist><dict>
+///         <key>Version</key><string>1</string>
+///         <key>Name</key><string>App</string>
+///     </dict></plist>",
+/// );
+///
+/// let merged = plist::three_way_merge(&base, &ours, &theirs).unwrap();
+/// assert_eq!(merged.pointer("/Version"), Some(&Value::from("2")));
+/// assert_eq!(merged.pointer("/Name"), Some(&Value::from("App")));
+///
+/// let conflicts = plist::three_way_merge(&base, &ours, &read("<plist><dict/></plist>"))
+///     .unwrap_err();
+/// assert_eq!(conflicts.conflicts()[0].key_path(), "Version");
+/// ```
+pub fn three_way_merge(
+    base: &Value,
+    ours: &Value,
+    theirs: &Value,
+) -> Result<Value, MergeConflicts> {
+    three_way_merge_with_options(base, ours, theirs, &ThreeWayMergeOptions::default())
+}
+
+/// Merges the changes made by `ours` and `theirs` to their common ancestor `base` using the
+/// specified options.
+///
+/// See [`three_way_merge`] for details.
+pub fn three_way_merge_with_options(
+    base: &Value,
+    ours: &Value,
+    theirs: &Value,
+    options: &ThreeWayMergeOptions,
+) -> Result<Value, MergeConflicts> {
+    let mut merger = Merger {
+        options,
+        path: Vec::new(),
+        conflicts: Vec::new(),
+    };
+    let merged = merger
+        .merge(Some(base), Some(ours), Some(theirs))
+        .expect("root value is present on all sides");
+    if merger.conflicts.is_empty() {
+        Ok(merged)
+    } else {
+        Err(MergeConflicts {
+            merged,
+            conflicts: merger.conflicts,
+        })
+    }
+}
+
+impl MergeConflicts {
+    /// Returns the conflicting values.
+    pub fn conflicts(&self) -> &[MergeConflict] {
+        &self.conflicts
+    }
+
+    /// Returns the result of the merge with the value from `ours` chosen for each conflict.
+    pub fn merged(&self) -> &Value {
+        &self.merged
+    }
+
+    /// Consumes the conflicts and returns the result of the merge with the value from `ours`
+    /// chosen for each conflict.
+    pub fn into_merged(self) -> Value {
+        self.merged
+    }
+}
+
+impl fmt::Display for MergeConflicts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for conflict in &self.conflicts {
+            writeln!(f, "{conflict}")?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for MergeConflicts {}
+
+impl MergeConflict {
+    /// Returns the location of the conflicting value as a dotted key path which can be passed to
+    /// [`Value::get_at_path`].
+    pub fn key_path(&self) -> &str {
+        &self.key_path
+    }
+
+    /// Returns the value in the common ancestor.
+    pub fn base(&self) -> Option<&Value> {
+        self.base.as_ref()
+    }
+
+    /// Returns the value on our side.
+    pub fn ours(&self) -> Option<&Value> {
+        self.ours.as_ref()
+    }
+
+    /// Returns the value on their side.
+    pub fn theirs(&self) -> Option<&Value> {
+        self.theirs.as_ref()
+    }
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let key_path = if self.key_path.is_empty() {
+            "(root)"
+        } else {
+            &self.key_path
+        };
+        write!(f, "conflict at {key_path}:")?;
+        for (side, value) in [
+            ("base", &self.base),
+            ("ours", &self.ours),
+            ("theirs", &self.theirs),
+        ] {
+            match value {
+                Some(value) => write!(f, " {side} {}", Summary(value))?,
+                None => write!(f, " {side} absent")?,
+            }
+            if side != "theirs" {
+                f.write_str(",")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Merger<'a, 'o> {
+    options: &'o ThreeWayMergeOptions,
+    path: Vec<PathElement<'a>>,
+    conflicts: Vec<MergeConflict>,
+}
+
+impl<'a> Merger<'a, '_> {
+    /// Returns the merged value, or `None` if it was removed.
+    fn merge(
+        &mut self,
+        base: Option<&'a Value>,
+        ours: Option<&'a Value>,
+        theirs: Option<&'a Value>,
+    ) -> Option<Value> {
+        if same(ours, theirs) || same(base, theirs) {
+            return ours.cloned();
+        }
+        if same(base, ours) {
+            return theirs.cloned();
+        }
+
+        match (ours, theirs) {
+            (Some(Value::Dictionary(ours)), Some(Value::Dictionary(theirs))) => {
+                let base = base.and_then(Value::as_dictionary);
+                return Some(self.merge_dictionaries(base, ours, theirs).into());
+            }
+            (Some(Value::Array(ours)), Some(Value::Array(theirs))) => {
+                let base = base.and_then(Value::as_array).map(Vec::as_slice);
+                if let Some(merged) = self.merge_arrays(base, ours, theirs) {
+                    return Some(merged.into());
+                }
+            }
+            _ => (),
+        }
+
+        self.conflicts.push(MergeConflict {
+            key_path: key_path::join(&self.path),
+            base: base.cloned(),
+            ours: ours.cloned(),
+            theirs: theirs.cloned(),
+        });
+        ours.cloned()
+    }
+
+    fn merge_dictionaries(
+        &mut self,
+        base: Option<&'a Dictionary>,
+        ours: &'a Dictionary,
+        theirs: &'a Dictionary,
+    ) -> Dictionary {
+        let mut merged = Dictionary::new();
+        let base_value = |key: &str| base.and_then(|base| base.get(key));
+
+        for (key, value) in ours {
+            self.path.push(PathElement::Key(key));
+            if let Some(value) = self.merge(base_value(key), Some(value), theirs.get(key)) {
+                merged.insert(key.clone(), value);
+            }
+            self.path.pop();
+        }
+
+        for (key, value) in theirs {
+            if !ours.contains_key(key) {
+                self.path.push(PathElement::Key(key));
+                if let Some(value) = self.merge(base_value(key), None, Some(value)) {
+                    merged.insert(key.clone(), value);
+                }
+                self.path.pop();
+            }
+        }
+
+        merged
+    }
+
+    /// Returns the merged array, or `None` if the arrays conflict.
+    fn merge_arrays(
+        &mut self,
+        base: Option<&'a [Value]>,
+        ours: &'a [Value],
+        theirs: &'a [Value],
+    ) -> Option<Vec<Value>> {
+        match self.options.arrays {
+            ArrayMergeStrategy::Conflict => None,
+            ArrayMergeStrategy::ByIndex => {
+                let base = base.filter(|base| base.len() == ours.len())?;
+                if ours.len() != theirs.len() {
+                    return None;
+                }
+                let mut merged = Vec::with_capacity(ours.len());
+                for (i, ((base, ours), theirs)) in base.iter().zip(ours).zip(theirs).enumerate() {
+                    self.path.push(PathElement::Index(i));
+                    // Elements can only be removed by removing them from the array.
+                    merged.extend(self.merge(Some(base), Some(ours), Some(theirs)));
+                    self.path.pop();
+                }
+                Some(merged)
+            }
+            ArrayMergeStrategy::Union => {
+                let base = base.unwrap_or_default();
+                // Keep our elements unless they were removed by theirs.
+                let mut merged: Vec<Value> = ours
+                    .iter()
+                    .filter(|value| contains(theirs, value) || !contains(base, value))
+                    .cloned()
+                    .collect();
+                for value in theirs {
+                    if !contains(base, value) && !contains(&merged, value) {
+                        merged.push(value.clone());
+                    }
+                }
+                Some(merged)
+            }
+        }
+    }
+}
+
+/// Returns `true` if both values are absent or are equal in the total order, so that, as in
+/// [`diff`](crate::diff()), an unchanged `NaN` is not seen as a change.
+fn same(a: Option<&Value>, b: Option<&Value>) -> bool {
+    a.map(TotalOrd) == b.map(TotalOrd)
+}
+
+/// Returns `true` if `values` contains a value equal to `value` in the total order.
+fn contains(values: &[Value], value: &Value) -> bool {
+    values.iter().any(|v| TotalOrd(v) == TotalOrd(value))
+}
+
+/// Options for customizing how [`Value::deep_merge_with_options`] merges an overlay into a value.
+#[derive(Clone, Debug, Default)]
+pub struct DeepMergeOptions {
+    arrays: MergeArrays,
+    scalars: MergeScalars,
+    type_conflicts: MergeTypeConflicts,
+    overrides: Vec<(Vec<String>, DeepMergeOptions)>,
+}
+
+/// How a deep merge combines two arrays.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MergeArrays {
+    /// Append the elements of the overlay array.
+    Append,
+    /// Replace the array with the overlay array.
+    #[default]
+    Replace,
+    /// Append the elements of the overlay array which are not already present.
+    Union,
+}
+
+/// How a deep merge combines two values of the same type which are not collections.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MergeScalars {
+    /// Take the overlay value.
+    #[default]
+    PreferOverlay,
+    /// Keep the existing value.
+    PreferBase,
+}
+
+/// How a deep merge combines two values of different types.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MergeTypeConflicts {
+    /// Return an error.
+    Error,
+    /// Take the overlay value.
+    #[default]
+    PreferOverlay,
+}
+
+impl DeepMergeOptions {
+    /// Default options for merging values.
+    pub fn new() -> Self {
+        DeepMergeOptions::default()
+    }
+
+    /// Specifies how to merge two arrays.
+    ///
+    /// The default is [`MergeArrays::Replace`].
+    pub fn arrays(mut self, strategy: MergeArrays) -> Self {
+        self.arrays = strategy;
+        self
+    }
+
+    /// Specifies how to merge two values of the same type which are not collections.
+    ///
+    /// The default is [`MergeScalars::PreferOverlay`].
+    pub fn scalars(mut self, strategy: MergeScalars) -> Self {
+        self.scalars = strategy;
+        self
+    }
+
+    /// Specifies how to merge two values of different types.
+    ///
+    /// The default is [`MergeTypeConflicts::PreferOverlay`].
+    pub fn type_conflicts(mut self, strategy: MergeTypeConflicts) -> Self {
+        self.type_conflicts = strategy;
+        self
+    }
+
+    /// Uses different options to merge the value at `key_path` and its descendants.
+    ///
+    /// The key path is relative to the value these options are used to merge and uses the syntax
+    /// described in [`Value::get_at_path`]. Only dictionary keys are matched as arrays are not
+    /// merged element by element. Key paths of `options` are relative to `key_path`.
+    ///
+    /// ```
+    /// use plist::{DeepMergeOptions, MergeArrays, MergeScalars};
+    ///
+    /// // Keep the user's settings but combine their plugins with the defaults.
+    /// let options = DeepMergeOptions::new()
+    ///     .scalars(MergeScalars::PreferBase)
+    ///     .key_path_options("Editor.Plugins", DeepMergeOptions::new().arrays(MergeArrays::Union));
+    /// ```
+    pub fn key_path_options(mut self, key_path: &str, options: DeepMergeOptions) -> Self {
+        self.overrides.push((key_path::split(key_path), options));
+        self
+    }
+
+    /// Returns the options for merging the value with the given key.
+    ///
+    /// These are the last override for `key` if there is one and otherwise these options, along
+    /// with any overrides for descendants of `key`.
+    fn for_key(&self, key: &str) -> Cow<'_, DeepMergeOptions> {
+        if self.overrides.is_empty() {
+            return Cow::Borrowed(self);
+        }
+
+        // Later overrides take precedence over earlier ones.
+        let mut options = match self
+            .overrides
+            .iter()
+            .rev()
+            .find(|(path, _)| path.len() == 1 && path[0] == key)
+        {
+            Some((_, options)) => options.clone(),
+            None => DeepMergeOptions {
+                arrays: self.arrays,
+                scalars: self.scalars,
+                type_conflicts: self.type_conflicts,
+                overrides: Vec::new(),
+            },
+        };
+        options.overrides.extend(
+            self.overrides
+                .iter()
+                .filter(|(path, _)| path.len() > 1 && path[0] == key)
+                .map(|(path, options)| (path[1..].to_vec(), options.clone())),
+        );
+        Cow::Owned(options)
+    }
+}
+
+pub(crate) fn deep_merge(
+    base: &mut Value,
+    overlay: Value,
+    options: &DeepMergeOptions,
+    path: &mut Vec<String>,
+) -> Result<(), Error> {
+    match (base, overlay) {
+        (Value::Dictionary(base), Value::Dictionary(overlay)) => {
+            deep_merge_dictionaries(base, overlay, options, path)?;
+        }
+        (Value::Array(base), Value::Array(overlay)) => match options.arrays {
+            MergeArrays::Append => base.extend(overlay),
+            MergeArrays::Replace => *base = overlay,
+            MergeArrays::Union => {
+                for value in overlay {
+                    if !contains(base, &value) {
+                        base.push(value);
+                    }
+                }
+            }
+        },
+        (base, overlay) if mem::discriminant(base) == mem::discriminant(&overlay) => {
+            if options.scalars == MergeScalars::PreferOverlay {
+                *base = overlay;
+            }
+        }
+        (base, overlay) => match options.type_conflicts {
+            MergeTypeConflicts::Error => {
+                let error = path.iter().rev().fold(
+                    ErrorKind::MergeTypeConflict.without_position(),
+                    |error, key| error.with_outer_key_path_segment(KeyPathSegment::Key(key)),
+                );
+                return Err(error);
+            }
+            MergeTypeConflicts::PreferOverlay => *base = overlay,
+        },
+    }
+    Ok(())
+}
+
+pub(crate) fn deep_merge_dictionaries(
+    base: &mut Dictionary,
+    overlay: Dictionary,
+    options: &DeepMergeOptions,
+    path: &mut Vec<String>,
+) -> Result<(), Error> {
+    for (key, value) in overlay {
+        match base.get_mut(&key) {
+            Some(base_value) => {
+                let options = options.for_key(&key);
+                path.push(key);
+                deep_merge(base_value, value, &options, path)?;
+                path.pop();
+            }
+            None => {
+                base.insert(key, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(xml: &str) -> Value {
+        Value::from_reader_xml(format!("<plist>{xml}</plist>").as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn dictionaries() {
+        let base = read(
+            "<dict>
+                <key>a</key><integer>1</integer>
+                <key>b</key><dict><key>x</key><integer>1</integer><key>y</key><integer>1</integer></dict>
+                <key>c</key><integer>1</integer>
+            </dict>",
+        );
+        let ours = read(
+            "<dict>
+                <key>b</key><dict><key>x</key><integer>2</integer><key>y</key><integer>1</integer></dict>
+                <key>c</key><integer>1</integer>
+                <key>d</key><integer>1</integer>
+            </dict>",
+        );
+        let theirs = read(
+            "<dict>
+                <key>a</key><integer>1</integer>
+                <key>b</key><dict><key>x</key><integer>1</integer><key>y</key><integer>3</integer></dict>
+                <key>e</key><integer>1</integer>
+            </dict>",
+        );
+
+        let merged = three_way_merge(&base, &ours, &theirs).unwrap();
+        assert_eq!(
+            merged,
+            read(
+                "<dict>
+                    <key>b</key><dict><key>x</key><integer>2</integer><key>y</key><integer>3</integer></dict>
+                    <key>d</key><integer>1</integer>
+                    <key>e</key><integer>1</integer>
+                </dict>"
+            )
+        );
+        let keys: Vec<_> = merged.as_dictionary().unwrap().keys().collect();
+        assert_eq!(keys, ["b", "d", "e"]);
+    }
+
+    #[test]
+    fn conflicts() {
+        let base = read("<dict><key>a</key><dict><key>b.c</key><integer>1</integer></dict></dict>");
+        let ours = read("<dict><key>a</key><dict><key>b.c</key><integer>2</integer></dict></dict>");
+        let theirs = read("<dict><key>a</key><dict/></dict>");
+
+        let conflicts = three_way_merge(&base, &ours, &theirs).unwrap_err();
+        assert_eq!(conflicts.merged(), &ours);
+        assert_eq!(
+            conflicts.conflicts(),
+            [MergeConflict {
+                key_path: r"a.b\.c".to_owned(),
+                base: Some(1.into()),
+                ours: Some(2.into()),
+                theirs: None,
+            }]
+        );
+        assert_eq!(
+            conflicts.to_string(),
+            "conflict at a.b\\.c: base 1, ours 2, theirs absent\n"
+        );
+
+        let conflicts = three_way_merge(&base, &Value::from(1), &Value::from(2)).unwrap_err();
+        assert_eq!(conflicts.conflicts()[0].key_path(), "");
+    }
+
+    #[test]
+    fn arrays() {
+        let base = read("<array><string>a</string><string>b</string><string>c</string></array>");
+        let ours = read("<array><string>a</string><string>B</string><string>c</string></array>");
+        let theirs = read("<array><string>a</string><string>b</string><string>C</string></array>");
+        let merge = |strategy, ours: &Value, theirs: &Value| {
+            let options = ThreeWayMergeOptions::new().arrays(strategy);
+            three_way_merge_with_options(&base, ours, theirs, &options)
+        };
+
+        assert!(merge(ArrayMergeStrategy::Conflict, &ours, &theirs).is_err());
+        assert_eq!(
+            merge(ArrayMergeStrategy::ByIndex, &ours, &theirs).unwrap(),
+            read("<array><string>a</string><string>B</string><string>C</string></array>")
+        );
+
+        let ours = read("<array><string>a</string><string>c</string><string>d</string></array>");
+        let theirs = read("<array><string>e</string><string>b</string><string>c</string></array>");
+        assert!(merge(ArrayMergeStrategy::ByIndex, &ours, &read("<array/>")).is_err());
+        assert_eq!(
+            merge(ArrayMergeStrategy::Union, &ours, &theirs).unwrap(),
+            read("<array><string>c</string><string>d</string><string>e</string></array>")
+        );
+    }
+
+    #[test]
+    fn unchanged_nan() {
+        let read_dict = |a, b| {
+            read(&format!(
+                "<dict><key>r</key><real>nan</real><key>a</key><integer>{a}</integer>\
+                <key>b</key><integer>{b}</integer></dict>"
+            ))
+        };
+        let merged = three_way_merge(&read_dict(1, 1), &read_dict(2, 1), &read_dict(1, 2)).unwrap();
+        assert_eq!(TotalOrd(&merged), TotalOrd(&read_dict(2, 2)));
+
+        let base = read("<array><real>nan</real><string>a</string></array>");
+        let ours = read("<array><real>nan</real><string>b</string></array>");
+        let theirs = read("<array><real>nan</real><string>a</string><string>c</string></array>");
+        let options = ThreeWayMergeOptions::new().arrays(ArrayMergeStrategy::Union);
+        let merged = three_way_merge_with_options(&base, &ours, &theirs, &options).unwrap();
+        assert_eq!(
+            TotalOrd(&merged),
+            TotalOrd(&read(
+                "<array><real>nan</real><string>b</string><string>c</string></array>"
+            ))
+        );
+    }
+
+    #[test]
+    fn deep_merge_strategies() {
+        let base = read(
+            "<dict>
+                <key>a</key><array><integer>1</integer><integer>2</integer></array>
+                <key>b</key><dict><key>c</key><integer>1</integer><key>d</key><string>x</string></dict>
+                <key>e</key><string>base</string>
+            </dict>",
+        );
+        let overlay = read(
+            "<dict>
+                <key>a</key><array><integer>2</integer><integer>3</integer></array>
+                <key>b</key><dict><key>d</key><string>y</string><key>f</key><true/></dict>
+                <key>e</key><integer>1</integer>
+            </dict>",
+        );
+        let merge = |options: &DeepMergeOptions| {
+            let mut merged = base.clone();
+            merged
+                .deep_merge_with_options(overlay.clone(), options)
+                .map(|()| merged)
+        };
+
+        let mut merged = base.clone();
+        merged.deep_merge(overlay.clone());
+        assert_eq!(merged, merge(&DeepMergeOptions::new()).unwrap());
+        assert_eq!(
+            merged,
+            read(
+                "<dict>
+                    <key>a</key><array><integer>2</integer><integer>3</integer></array>
+                    <key>b</key><dict><key>c</key><integer>1</integer><key>d</key><string>y</string><key>f</key><true/></dict>
+                    <key>e</key><integer>1</integer>
+                </dict>"
+            )
+        );
+
+        let merged = merge(&DeepMergeOptions::new().arrays(MergeArrays::Append)).unwrap();
+        assert_eq!(merged["a"], read("<array><integer>1</integer><integer>2</integer><integer>2</integer><integer>3</integer></array>"));
+        let merged = merge(&DeepMergeOptions::new().arrays(MergeArrays::Union)).unwrap();
+        assert_eq!(
+            merged["a"],
+            read("<array><integer>1</integer><integer>2</integer><integer>3</integer></array>")
+        );
+
+        let merged = merge(&DeepMergeOptions::new().scalars(MergeScalars::PreferBase)).unwrap();
+        assert_eq!(merged["b"]["d"], Value::from("x"));
+        assert_eq!(merged["b"]["f"], Value::Boolean(true));
+        assert_eq!(merged["e"], Value::from(1));
+
+        let options = DeepMergeOptions::new().type_conflicts(MergeTypeConflicts::Error);
+        let err = merge(&options).unwrap_err();
+        assert_eq!(err.category(), crate::ErrorCategory::UnexpectedStructure);
+        assert_eq!(err.key_path(), Some("e"));
+    }
+
+    #[test]
+    fn deep_merge_key_path_options() {
+        let mut base = read(
+            "<dict><key>x.y</key><dict>
+                <key>a</key><array><integer>1</integer></array>
+                <key>b</key><dict><key>c</key><array><integer>1</integer></array></dict>
+            </dict></dict>",
+        );
+        let overlay = read(
+            "<dict><key>x.y</key><dict>
+                <key>a</key><array><integer>2</integer></array>
+                <key>b</key><dict><key>c</key><array><integer>2</integer></array></dict>
+            </dict></dict>",
+        );
+        let options = DeepMergeOptions::new()
+            .arrays(MergeArrays::Append)
+            .key_path_options(r"x\.y.a", DeepMergeOptions::new())
+            .key_path_options(
+                r"x\.y.b",
+                DeepMergeOptions::new()
+                    .key_path_options("c", DeepMergeOptions::new().arrays(MergeArrays::Union)),
+            );
+        base.as_dictionary_mut()
+            .unwrap()
+            .deep_merge_with_options(overlay.into_dictionary().unwrap(), &options)
+            .unwrap();
+
+        assert_eq!(
+            base.get_at_path(r"x\.y.a"),
+            Some(&read("<array><integer>2</integer></array>"))
+        );
+        assert_eq!(
+            base.get_at_path(r"x\.y.b.c"),
+            Some(&read(
+                "<array><integer>1</integer><integer>2</integer></array>"
+            ))
+        );
+    }
+
+    #[test]
+    fn deep_merge_nested_key_path_options() {
+        let read_dict = |xml| read(xml).into_dictionary().unwrap();
+        let mut base = read_dict(
+            "<dict>
+                <key>Editor</key><dict>
+                    <key>Plugins</key><array><string>a</string></array>
+                    <key>Font</key><string>Menlo</string>
+                </dict>
+                <key>Window</key><dict>
+                    <key>Themes</key><array><string>a</string></array>
+                </dict>
+            </dict>",
+        );
+        let overlay = read_dict(
+            "<dict>
+                <key>Editor</key><dict>
+                    <key>Plugins</key><array><string>b</string></array>
+                    <key>Font</key><string>Monaco</string>
+                </dict>
+                <key>Window</key><dict>
+                    <key>Themes</key><array><string>b</string></array>
+                </dict>
+            </dict>",
+        );
+        let options = DeepMergeOptions::new()
+            .arrays(MergeArrays::Append)
+            .key_path_options(
+                "Editor.Plugins",
+                DeepMergeOptions::new().arrays(MergeArrays::Union),
+            )
+            .key_path_options(
+                "Editor",
+                DeepMergeOptions::new().scalars(MergeScalars::PreferBase),
+            )
+            // Key paths are relative so this does not match `Window.Themes`.
+            .key_path_options("Themes", DeepMergeOptions::new());
+        base.deep_merge_with_options(overlay, &options).unwrap();
+        let merged = Value::from(base);
+
+        let strings = |key_path| {
+            merged
+                .get_at_path(key_path)
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|value| value.as_string().unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(strings("Editor.Plugins"), ["a", "b"]);
+        assert_eq!(
+            merged.get_at_path("Editor.Font").and_then(Value::as_string),
+            Some("Menlo")
+        );
+        assert_eq!(strings("Window.Themes"), ["a", "b"]);
+    }
+
+    #[test]
+    fn deep_merge_escapes_error_key_path() {
+        let mut base =
+            read("<dict><key>a.b</key><dict><key>c</key><integer>1</integer></dict></dict>");
+        let overlay =
+            read("<dict><key>a.b</key><dict><key>c</key><string>x</string></dict></dict>");
+        let options = DeepMergeOptions::new().type_conflicts(MergeTypeConflicts::Error);
+        let err = base.deep_merge_with_options(overlay, &options).unwrap_err();
+        assert_eq!(err.key_path(), Some(r"a\.b.c"));
+
+        let mut base = read("<dict><key>a[0]</key><integer>1</integer></dict>");
+        let overlay = read("<dict><key>a[0]</key><string>x</string></dict>");
+        let err = base.deep_merge_with_options(overlay, &options).unwrap_err();
+        assert_eq!(err.key_path(), Some(r"a\[0]"));
+    }
+
+    #[test]
+    fn deep_merge_union_nan() {
+        let mut base = read("<array><real>nan</real></array>");
+        let options = DeepMergeOptions::new().arrays(MergeArrays::Union);
+        for _ in 0..2 {
+            let overlay = base.clone();
+            base.deep_merge_with_options(overlay, &options).unwrap();
+        }
+        assert_eq!(base.as_array().unwrap().len(), 1);
+    }
+}
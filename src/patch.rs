@@ -0,0 +1,459 @@
+use crate::{
+    error::{Error, ErrorKind},
+    key_path, Dictionary, Value,
+};
+
+/// A sequence of edits to apply to a value, as described by [RFC 6902] (JSON Patch).
+///
+/// Each operation refers to values by JSON Pointer (see [`Value::pointer`]). A patch can be read
+/// from and converted to a `Value` with the same structure as a JSON Patch document: an array of
+/// dictionaries each with an `op` key naming the operation, a `path` key, and `from` or `value`
+/// keys as required by the operation.
+///
+/// [RFC 6902]: https://tools.ietf.org/html/rfc6902
+///
+/// ```
+/// use plist::{Dictionary, Patch, PatchOperation, Value};
+///
+/// let mut info = Value::from(Dictionary::new());
+/// info.insert_at_path("CFBundleIdentifier", "com.example.app", false).unwrap();
+///
+/// let patch = Patch::new(vec![
+///     PatchOperation::Test {
+///         path: "/CFBundleIdentifier".to_owned(),
+///         value: "com.example.app".into(),
+///     },
+///     PatchOperation::Replace {
+///         path: "/CFBundleIdentifier".to_owned(),
+///         value: "com.example.app.staging".into(),
+///     },
+/// ]);
+/// patch.apply(&mut info).unwrap();
+///
+/// assert_eq!(
+///     info.pointer("/CFBundleIdentifier"),
+///     Some(&Value::from("com.example.app.staging"))
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Patch {
+    operations: Vec<PatchOperation>,
+}
+
+/// A single operation in a [`Patch`].
+///
+/// All paths are JSON Pointers. When adding to an array the final token of `path` may be `-` to
+/// append to the array.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum PatchOperation {
+    /// Inserts a value into a dictionary or array, replacing any existing dictionary entry.
+    Add { path: String, value: Value },
+    /// Removes an existing value.
+    Remove { path: String },
+    /// Replaces an existing value.
+    Replace { path: String, value: Value },
+    /// Removes the value at `from` and adds it at `path`.
+    Move { from: String, path: String },
+    /// Adds a copy of the value at `from` at `path`.
+    Copy { from: String, path: String },
+    /// Checks the value at `path` is equal to `value`.
+    Test { path: String, value: Value },
+}
+
+impl Patch {
+    /// Creates a patch from a list of operations.
+    pub fn new(operations: Vec<PatchOperation>) -> Patch {
+        Patch { operations }
+    }
+
+    /// Reads a patch from its representation as a `Value`.
+    ///
+    /// ```
+    /// use plist::{Patch, Value};
+    ///
+    /// let value = Value::from_reader_xml(
+    ///     r#"<plist><array>
+    ///         <dict>
+    ///             <key>op</key><string>add</string>
+    ///             <key>path</key><string>/ITSAppUsesNonExemptEncryption</string>
+    ///             <key>value</key><false/>
+    ///         </dict>
+    ///     </array></plist>"#
+    ///         .as_bytes(),
+    /// )
+    /// .unwrap();
+    /// let patch = Patch::from_value(&value).unwrap();
+    ///
+    /// assert_eq!(patch.operations().len(), 1);
+    /// assert_eq!(Value::from(patch), value);
+    /// ```
+    pub fn from_value(value: &Value) -> Result<Patch, Error> {
+        let operations = value
+            .as_array()
+            .ok_or_else(|| invalid_patch("a patch must be an array".to_owned()))?
+            .iter()
+            .enumerate()
+            .map(|(i, operation)| {
+                PatchOperation::from_value(operation)
+                    .map_err(|message| invalid_patch(format!("operation {i}: {message}")))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Patch { operations })
+    }
+
+    /// Returns the operations in this patch.
+    pub fn operations(&self) -> &[PatchOperation] {
+        &self.operations
+    }
+
+    /// Applies each operation in this patch to `target` in turn.
+    ///
+    /// If any operation fails `target` is left unmodified and an error is returned. A failed
+    /// `test` operation returns an error with the category [`ErrorCategory::PatchTestFailed`] and
+    /// a path that does not refer to a value returns an error with the category
+    /// [`ErrorCategory::KeyPathNotFound`].
+    ///
+    /// [`ErrorCategory::PatchTestFailed`]: crate::ErrorCategory::PatchTestFailed
+    /// [`ErrorCategory::KeyPathNotFound`]: crate::ErrorCategory::KeyPathNotFound
+    pub fn apply(&self, target: &mut Value) -> Result<(), Error> {
+        let mut patched = target.clone();
+        for (i, operation) in self.operations.iter().enumerate() {
+            operation
+                .apply(&mut patched)
+                .map_err(|err| err.with_context(format!("operation {i}: {}", operation.path())))?;
+        }
+        *target = patched;
+        Ok(())
+    }
+}
+
+impl From<Vec<PatchOperation>> for Patch {
+    fn from(operations: Vec<PatchOperation>) -> Patch {
+        Patch::new(operations)
+    }
+}
+
+impl From<Patch> for Value {
+    fn from(patch: Patch) -> Value {
+        Value::Array(
+            patch
+                .operations
+                .into_iter()
+                .map(PatchOperation::into_value)
+                .collect(),
+        )
+    }
+}
+
+impl PatchOperation {
+    /// Returns the path of the value this operation modifies or tests.
+    pub fn path(&self) -> &str {
+        match self {
+            PatchOperation::Add { path, .. }
+            | PatchOperation::Remove { path }
+            | PatchOperation::Replace { path, .. }
+            | PatchOperation::Move { path, .. }
+            | PatchOperation::Copy { path, .. }
+            | PatchOperation::Test { path, .. } => path,
+        }
+    }
+
+    fn from_value(value: &Value) -> Result<PatchOperation, String> {
+        let dict = value
+            .as_dictionary()
+            .ok_or("an operation must be a dictionary")?;
+        let string = |key: &str| {
+            dict.get(key)
+                .and_then(Value::as_string)
+                .map(str::to_owned)
+                .ok_or_else(|| format!("missing string `{key}`"))
+        };
+        let value = || dict.get("value").cloned().ok_or("missing `value`");
+
+        let operation = match string("op")?.as_str() {
+            "add" => PatchOperation::Add {
+                path: string("path")?,
+                value: value()?,
+            },
+            "remove" => PatchOperation::Remove {
+                path: string("path")?,
+            },
+            "replace" => PatchOperation::Replace {
+                path: string("path")?,
+                value: value()?,
+            },
+            "move" => PatchOperation::Move {
+                from: string("from")?,
+                path: string("path")?,
+            },
+            "copy" => PatchOperation::Copy {
+                from: string("from")?,
+                path: string("path")?,
+            },
+            "test" => PatchOperation::Test {
+                path: string("path")?,
+                value: value()?,
+            },
+            op => return Err(format!("unknown operation `{op}`")),
+        };
+        Ok(operation)
+    }
+
+    fn into_value(self) -> Value {
+        let (op, path, from, value) = match self {
+            PatchOperation::Add { path, value } => ("add", path, None, Some(value)),
+            PatchOperation::Remove { path } => ("remove", path, None, None),
+            PatchOperation::Replace { path, value } => ("replace", path, None, Some(value)),
+            PatchOperation::Move { from, path } => ("move", path, Some(from), None),
+            PatchOperation::Copy { from, path } => ("copy", path, Some(from), None),
+            PatchOperation::Test { path, value } => ("test", path, None, Some(value)),
+        };
+        let mut dict = Dictionary::new();
+        dict.insert("op".to_owned(), op.into());
+        if let Some(from) = from {
+            dict.insert("from".to_owned(), from.into());
+        }
+        dict.insert("path".to_owned(), path.into());
+        if let Some(value) = value {
+            dict.insert("value".to_owned(), value);
+        }
+        dict.into()
+    }
+
+    fn apply(&self, target: &mut Value) -> Result<(), Error> {
+        match self {
+            PatchOperation::Add { path, value } => add(target, path, value.clone()),
+            PatchOperation::Remove { path } => remove(target, path).map(drop),
+            PatchOperation::Replace { path, value } => {
+                *target.pointer_mut(path).ok_or_else(not_found)? = value.clone();
+                Ok(())
+            }
+            PatchOperation::Move { from, path } => {
+                if path.starts_with(from.as_str()) && path[from.len()..].starts_with('/') {
+                    return Err(invalid_patch(
+                        "cannot move a value into one of its children".to_owned(),
+                    ));
+                }
+                let value = remove(target, from)?;
+                add(target, path, value)
+            }
+            PatchOperation::Copy { from, path } => {
+                let value = target.pointer(from).ok_or_else(not_found)?.clone();
+                add(target, path, value)
+            }
+            PatchOperation::Test { path, value } => {
+                if target.pointer(path).ok_or_else(not_found)? == value {
+                    Ok(())
+                } else {
+                    Err(ErrorKind::PatchTestFailed.without_position())
+                }
+            }
+        }
+    }
+}
+
+fn invalid_patch(message: String) -> Error {
+    ErrorKind::InvalidPatch
+        .without_position()
+        .with_context(message)
+}
+
+fn not_found() -> Error {
+    ErrorKind::KeyPathNotFound.without_position()
+}
+
+/// Splits a pointer into the tokens of its parent and its final token.
+fn split_parent(pointer: &str) -> Result<Option<(Vec<String>, String)>, Error> {
+    let mut tokens = key_path::split_pointer(pointer)
+        .ok_or_else(|| invalid_patch(format!("invalid pointer `{pointer}`")))?;
+    Ok(tokens.pop().map(|last| (tokens, last)))
+}
+
+fn add(target: &mut Value, pointer: &str, value: Value) -> Result<(), Error> {
+    let Some((parent, last)) = split_parent(pointer)? else {
+        *target = value;
+        return Ok(());
+    };
+    match key_path::get_mut(target, &parent).ok_or_else(not_found)? {
+        Value::Dictionary(dict) => {
+            dict.insert(last, value);
+        }
+        Value::Array(array) => {
+            let index = if last == "-" {
+                array.len()
+            } else {
+                key_path::index(&last)
+                    .filter(|&index| index <= array.len())
+                    .ok_or_else(not_found)?
+            };
+            array.insert(index, value);
+        }
+        _ => return Err(not_found()),
+    }
+    Ok(())
+}
+
+fn remove(target: &mut Value, pointer: &str) -> Result<Value, Error> {
+    let Some((parent, last)) = split_parent(pointer)? else {
+        return Err(invalid_patch("cannot remove the root value".to_owned()));
+    };
+    match key_path::get_mut(target, &parent).ok_or_else(not_found)? {
+        Value::Dictionary(dict) => dict.shift_remove(&last).ok_or_else(not_found),
+        Value::Array(array) => {
+            let index = key_path::index(&last)
+                .filter(|&index| index < array.len())
+                .ok_or_else(not_found)?;
+            Ok(array.remove(index))
+        }
+        _ => Err(not_found()),
+    }
+}
+
+/// Merges `patch` into `target` as described by [RFC 7396] (JSON Merge Patch).
+///
+/// [RFC 7396]: https://tools.ietf.org/html/rfc7396
+pub(crate) fn merge_patch(target: &mut Dictionary, patch: &Dictionary) {
+    for (key, patch_value) in patch {
+        match (target.get_mut(key), patch_value) {
+            (Some(Value::Dictionary(target)), Value::Dictionary(patch)) => {
+                merge_patch(target, patch)
+            }
+            _ => {
+                target.insert(key.clone(), patch_value.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorCategory;
+
+    fn base() -> Value {
+        let mut value = Value::from(Dictionary::new());
+        value.insert_at_path("a.b", 1, true).unwrap();
+        value
+            .insert_at_path("list", vec![Value::from("x"), Value::from("y")], false)
+            .unwrap();
+        value
+    }
+
+    fn apply(value: &mut Value, patch: &str) -> Result<(), Error> {
+        let patch = Value::from_reader_xml(patch.as_bytes()).unwrap();
+        Patch::from_value(&patch)?.apply(value)
+    }
+
+    #[test]
+    fn operations() {
+        let mut value = base();
+        Patch::new(vec![
+            PatchOperation::Add {
+                path: "/list/-".to_owned(),
+                value: "z".into(),
+            },
+            PatchOperation::Add {
+                path: "/list/0".to_owned(),
+                value: "w".into(),
+            },
+            PatchOperation::Remove {
+                path: "/list/1".to_owned(),
+            },
+            PatchOperation::Replace {
+                path: "/a/b".to_owned(),
+                value: 2.into(),
+            },
+            PatchOperation::Copy {
+                from: "/a".to_owned(),
+                path: "/c".to_owned(),
+            },
+            PatchOperation::Move {
+                from: "/a/b".to_owned(),
+                path: "/list/0".to_owned(),
+            },
+            PatchOperation::Test {
+                path: "/c/b".to_owned(),
+                value: 2.into(),
+            },
+        ])
+        .apply(&mut value)
+        .unwrap();
+
+        assert_eq!(
+            value.pointer("/list"),
+            Some(&Value::from(vec![
+                2.into(),
+                "w".into(),
+                "y".into(),
+                "z".into()
+            ]))
+        );
+        assert_eq!(value.pointer("/a"), Some(&Value::from(Dictionary::new())));
+        assert_eq!(value.pointer("/c/b"), Some(&Value::from(2)));
+    }
+
+    #[test]
+    fn failed_patches_leave_target_unmodified() {
+        let mut value = base();
+
+        let err = apply(
+            &mut value,
+            "<plist><array>
+                <dict><key>op</key><string>remove</string><key>path</key><string>/a</string></dict>
+                <dict><key>op</key><string>test</string><key>path</key><string>/list/0</string><key>value</key><string>y</string></dict>
+            </array></plist>",
+        )
+        .unwrap_err();
+        assert_eq!(err.category(), ErrorCategory::PatchTestFailed);
+        assert!(err.to_string().ends_with("operation 1: /list/0"));
+        assert_eq!(value, base());
+
+        let err = apply(
+            &mut value,
+            "<plist><array>
+                <dict><key>op</key><string>replace</string><key>path</key><string>/missing</string><key>value</key><true/></dict>
+            </array></plist>",
+        )
+        .unwrap_err();
+        assert_eq!(err.category(), ErrorCategory::KeyPathNotFound);
+
+        let err = Patch::new(vec![PatchOperation::Move {
+            from: "/a".to_owned(),
+            path: "/a/b/c".to_owned(),
+        }])
+        .apply(&mut value)
+        .unwrap_err();
+        assert_eq!(err.category(), ErrorCategory::UnexpectedStructure);
+        assert_eq!(value, base());
+    }
+
+    #[test]
+    fn invalid_patch_values() {
+        for patch in [
+            "<plist><dict/></plist>",
+            "<plist><array><string>add</string></array></plist>",
+            "<plist><array><dict><key>op</key><string>frobnicate</string></dict></array></plist>",
+            "<plist><array><dict><key>op</key><string>add</string><key>path</key><string>/a</string></dict></array></plist>",
+        ] {
+            let err = apply(&mut base(), patch).unwrap_err();
+            assert_eq!(err.category(), ErrorCategory::UnexpectedStructure, "{patch}");
+        }
+    }
+
+    #[test]
+    fn merge_patch() {
+        let mut value = base();
+        let mut patch = Value::from(Dictionary::new());
+        patch.insert_at_path("a.c", true, true).unwrap();
+        patch.insert_at_path("list", "replaced", false).unwrap();
+        value.merge_patch(&patch);
+
+        assert_eq!(value.pointer("/a/b"), Some(&Value::from(1)));
+        assert_eq!(value.pointer("/a/c"), Some(&Value::Boolean(true)));
+        assert_eq!(value.pointer("/list"), Some(&Value::from("replaced")));
+
+        value.merge_patch(&Value::from(3));
+        assert_eq!(value, Value::from(3));
+    }
+}
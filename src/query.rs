@@ -0,0 +1,1071 @@
+//! A JSONPath-style query language for selecting values in a plist.
+//!
+//! A query starts with `$`, which refers to the root value, followed by a sequence of segments
+//! which each select values from the results of the previous segment:
+//!
+//! | Syntax                     | Selects                                                        |
+//! |----------------------------|----------------------------------------------------------------|
+//! | `.name` or `['name']`      | The value with the key `name` in a dictionary.                 |
+//! | `[0]`, `[-1]`              | An array element by index, counting from the end if negative.  |
+//! | `[start:end:step]`         | A slice of array elements. All three parts are optional.       |
+//! | `.*` or `[*]`              | Every value in a dictionary or element of an array.            |
+//! | `[0, 'name', 2:4]`         | The union of several selectors.                                |
+//! | `..name`, `..*`, `..[..]`  | Applies the selector to a value and all of its descendants.    |
+//! | `[?(expr)]`                | Every child value for which the filter expression holds.       |
+//!
+//! Filter expressions refer to the child being tested as `@` and to the root value as `$`, both
+//! of which may be followed by further segments. A path on its own tests whether it selects any
+//! value. Paths, string and number literals, `true`, `false` and `type(path)`, which evaluates to
+//! the type name of a value (`array`, `dictionary`, `boolean`, `data`, `date`, `real`, `integer`,
+//! `string` or `uid`), can be compared with `==`, `!=`, `<`, `<=`, `>` and `>=`. Expressions are
+//! combined with `&&`, `||`, `!` and parentheses. Filters, parentheses, `!` and `type()` can be
+//! nested at most 64 levels deep.
+//!
+//! ```
+//! use plist::{query::Query, Value};
+//!
+//! let project = Value::from_reader_xml(
+//!     r#"<plist><dict>
+//!         <key>objects</key>
+//!         <dict>
+//!             <key>A1</key>
+//!             <dict><key>isa</key><string>PBXNativeTarget</string><key>name</key><string>App</string></dict>
+//!             <key>B2</key>
+//!             <dict><key>isa</key><string>PBXGroup</string><key>name</key><string>Sources</string></dict>
+//!         </dict>
+//!     </dict></plist>"#
+//!         .as_bytes(),
+//! )
+//! .unwrap();
+//!
+//! let query: Query = "$.objects[?(@.isa == 'PBXNativeTarget')].name".parse().unwrap();
+//! let matches = query.select(&project);
+//!
+//! assert_eq!(matches.len(), 1);
+//! assert_eq!(matches[0].value().as_string(), Some("App"));
+//! assert_eq!(matches[0].pointer(), "/objects/A1/name");
+//! ```
+
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use crate::{
+    error::{Error, ErrorKind},
+    key_path, Dictionary, Value,
+};
+
+/// A parsed query which can be used to select values from a plist.
+///
+/// See the [module documentation](self) for the query syntax.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    segments: Vec<Segment>,
+}
+
+/// A value selected by a [`Query`] along with its location.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Match<'a> {
+    path: Vec<PathElement<'a>>,
+    value: &'a Value,
+}
+
+/// A component of the location of a [`Match`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathElement<'a> {
+    /// A dictionary key.
+    Key(&'a str),
+    /// An array index.
+    Index(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Child(Vec<Selector>),
+    Descendant(Vec<Selector>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: Option<i64>,
+    },
+    Filter(Expr),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    /// Chains of `||` and `&&` are stored flat so long chains do not produce deeply nested trees.
+    Or(Vec<Expr>),
+    And(Vec<Expr>),
+    Not(Box<Expr>),
+    Exists(Path),
+    Compare(Comparable, CompareOp, Comparable),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Comparable {
+    Path(Path),
+    TypeOf(Path),
+    Literal(Literal),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Path {
+    relative: bool,
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Literal {
+    String(String),
+    Integer(i128),
+    Real(f64),
+    Boolean(bool),
+}
+
+impl Query {
+    /// Parses a query.
+    pub fn new(query: &str) -> Result<Query, Error> {
+        let mut parser = Parser {
+            query,
+            pos: 0,
+            depth: 0,
+        };
+        parser.expect('$')?;
+        let segments = parser.segments()?;
+        if parser.pos != query.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(Query { segments })
+    }
+
+    /// Returns every value selected by this query from `value`.
+    ///
+    /// Each segment selects values from the results of the previous segment in turn, listing the
+    /// values selected by each selector in the order the selectors are written, so
+    /// `$.Numbers[1, 0]` selects the second element before the first and `$.Numbers[::-1]`
+    /// selects the elements in reverse. A descendant segment applies its selectors to a value and
+    /// then to each of its descendants in document order, so the results are grouped by the
+    /// collection they were selected from: `$..*` lists every child of the root before any of
+    /// their own children.
+    pub fn select<'a>(&self, value: &'a Value) -> Vec<Match<'a>> {
+        self.select_node(Node::Value(value))
+    }
+
+    /// Returns every value selected by this query from `dict`, in the order described by
+    /// [`Query::select`].
+    ///
+    /// The root dictionary is not itself a [`Value`] so is never included in the results.
+    pub fn select_dictionary<'a>(&self, dict: &'a Dictionary) -> Vec<Match<'a>> {
+        self.select_node(Node::Dictionary(dict))
+    }
+
+    fn select_node<'a>(&self, root: Node<'a>) -> Vec<Match<'a>> {
+        select(root, root, &self.segments)
+            .into_iter()
+            .filter_map(|(path, node)| match node {
+                Node::Value(value) => Some(Match { path, value }),
+                Node::Dictionary(_) => None,
+            })
+            .collect()
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Query, Error> {
+        Query::new(s)
+    }
+}
+
+impl<'a> Match<'a> {
+    /// Returns the selected value.
+    pub fn value(&self) -> &'a Value {
+        self.value
+    }
+
+    /// Returns the location of the selected value relative to the root.
+    pub fn path(&self) -> &[PathElement<'a>] {
+        &self.path
+    }
+
+    /// Returns the location of the selected value as a JSON Pointer which can be passed to
+    /// [`Value::pointer`].
+    pub fn pointer(&self) -> String {
+        key_path::to_pointer(&self.path)
+    }
+
+    /// Returns the location of the selected value as a dotted key path which can be passed to
+    /// [`Value::get_at_path`].
+    pub fn key_path(&self) -> String {
+        key_path::join(&self.path)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Node<'a> {
+    Value(&'a Value),
+    Dictionary(&'a Dictionary),
+}
+
+impl<'a> Node<'a> {
+    fn as_dictionary(self) -> Option<&'a Dictionary> {
+        match self {
+            Node::Value(value) => value.as_dictionary(),
+            Node::Dictionary(dict) => Some(dict),
+        }
+    }
+
+    fn as_array(self) -> Option<&'a [Value]> {
+        match self {
+            Node::Value(value) => value.as_array().map(Vec::as_slice),
+            Node::Dictionary(_) => None,
+        }
+    }
+
+    fn children(self) -> Vec<(PathElement<'a>, &'a Value)> {
+        if let Some(dict) = self.as_dictionary() {
+            dict.iter()
+                .map(|(key, value)| (PathElement::Key(key.as_str()), value))
+                .collect()
+        } else if let Some(array) = self.as_array() {
+            array
+                .iter()
+                .enumerate()
+                .map(|(i, value)| (PathElement::Index(i), value))
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+type Selected<'a> = Vec<(Vec<PathElement<'a>>, Node<'a>)>;
+
+fn select<'a>(root: Node<'a>, start: Node<'a>, segments: &[Segment]) -> Selected<'a> {
+    let mut current = vec![(Vec::new(), start)];
+    for segment in segments {
+        let mut next = Vec::new();
+        for (path, node) in current {
+            match segment {
+                Segment::Child(selectors) => {
+                    apply_selectors(root, &path, node, selectors, &mut next)
+                }
+                Segment::Descendant(selectors) => {
+                    let mut stack = vec![(path, node)];
+                    while let Some((path, node)) = stack.pop() {
+                        apply_selectors(root, &path, node, selectors, &mut next);
+                        // Push children in reverse so they are visited in document order.
+                        for (element, child) in node.children().into_iter().rev() {
+                            stack.push((extend(&path, element), Node::Value(child)));
+                        }
+                    }
+                }
+            }
+        }
+        current = next;
+    }
+    current
+}
+
+fn extend<'a>(path: &[PathElement<'a>], element: PathElement<'a>) -> Vec<PathElement<'a>> {
+    let mut path = path.to_vec();
+    path.push(element);
+    path
+}
+
+fn apply_selectors<'a>(
+    root: Node<'a>,
+    path: &[PathElement<'a>],
+    node: Node<'a>,
+    selectors: &[Selector],
+    out: &mut Selected<'a>,
+) {
+    for selector in selectors {
+        match selector {
+            Selector::Name(name) => {
+                if let Some((key, value)) = node.as_dictionary().and_then(|d| d.get_key_value(name))
+                {
+                    out.push((extend(path, PathElement::Key(key)), Node::Value(value)));
+                }
+            }
+            Selector::Wildcard => {
+                for (element, child) in node.children() {
+                    out.push((extend(path, element), Node::Value(child)));
+                }
+            }
+            Selector::Index(index) => {
+                if let Some(array) = node.as_array() {
+                    let len = array.len() as i64;
+                    let index = if *index < 0 { len + index } else { *index };
+                    if (0..len).contains(&index) {
+                        let index = index as usize;
+                        out.push((
+                            extend(path, PathElement::Index(index)),
+                            Node::Value(&array[index]),
+                        ));
+                    }
+                }
+            }
+            Selector::Slice { start, end, step } => {
+                if let Some(array) = node.as_array() {
+                    for index in slice_indices(array.len(), *start, *end, *step) {
+                        out.push((
+                            extend(path, PathElement::Index(index)),
+                            Node::Value(&array[index]),
+                        ));
+                    }
+                }
+            }
+            Selector::Filter(expr) => {
+                for (element, child) in node.children() {
+                    if expr.evaluate(root, Node::Value(child)) {
+                        out.push((extend(path, element), Node::Value(child)));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the indices selected by a slice, following the semantics of RFC 9535.
+fn slice_indices(
+    len: usize,
+    start: Option<i64>,
+    end: Option<i64>,
+    step: Option<i64>,
+) -> Vec<usize> {
+    let len = len as i64;
+    let step = step.unwrap_or(1);
+    let normalize = |i: i64| if i < 0 { len + i } else { i };
+
+    let mut indices = Vec::new();
+    if step > 0 {
+        let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+        let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+        let mut i = lower;
+        while i < upper {
+            indices.push(i as usize);
+            match i.checked_add(step) {
+                Some(next) => i = next,
+                None => break,
+            }
+        }
+    } else if step < 0 {
+        let upper = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+        let lower = end.map_or(-1, |end| normalize(end).clamp(-1, len - 1));
+        let mut i = upper;
+        while lower < i {
+            indices.push(i as usize);
+            match i.checked_add(step) {
+                Some(next) => i = next,
+                None => break,
+            }
+        }
+    }
+    indices
+}
+
+impl Expr {
+    fn evaluate(&self, root: Node, current: Node) -> bool {
+        match self {
+            Expr::Or(exprs) => exprs.iter().any(|expr| expr.evaluate(root, current)),
+            Expr::And(exprs) => exprs.iter().all(|expr| expr.evaluate(root, current)),
+            Expr::Not(expr) => !expr.evaluate(root, current),
+            Expr::Exists(path) => !path.select(root, current).is_empty(),
+            Expr::Compare(lhs, op, rhs) => {
+                let lhs = lhs.evaluate(root, current);
+                let rhs = rhs.evaluate(root, current);
+                match op {
+                    CompareOp::Eq => lhs.equals(&rhs),
+                    CompareOp::Ne => !lhs.equals(&rhs),
+                    CompareOp::Lt => lhs.compare(&rhs) == Some(Ordering::Less),
+                    CompareOp::Le => {
+                        matches!(lhs.compare(&rhs), Some(Ordering::Less | Ordering::Equal))
+                    }
+                    CompareOp::Gt => lhs.compare(&rhs) == Some(Ordering::Greater),
+                    CompareOp::Ge => {
+                        matches!(lhs.compare(&rhs), Some(Ordering::Greater | Ordering::Equal))
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Path {
+    fn select<'a>(&self, root: Node<'a>, current: Node<'a>) -> Selected<'a> {
+        let start = if self.relative { current } else { root };
+        select(root, start, &self.segments)
+    }
+
+    /// Returns the single node selected by this path, or `None` if it selects zero or several.
+    fn select_single<'a>(&self, root: Node<'a>, current: Node<'a>) -> Option<Node<'a>> {
+        let mut selected = self.select(root, current);
+        if selected.len() == 1 {
+            selected.pop().map(|(_, node)| node)
+        } else {
+            None
+        }
+    }
+}
+
+/// The value of one side of a comparison in a filter expression.
+enum Operand<'a> {
+    Nothing,
+    String(&'a str),
+    Number(Number),
+    Boolean(bool),
+    Node(Node<'a>),
+}
+
+#[derive(Clone, Copy)]
+enum Number {
+    Integer(i128),
+    Real(f64),
+}
+
+impl Comparable {
+    fn evaluate<'a>(&'a self, root: Node<'a>, current: Node<'a>) -> Operand<'a> {
+        match self {
+            Comparable::Path(path) => match path.select_single(root, current) {
+                Some(node) => Operand::from_node(node),
+                None => Operand::Nothing,
+            },
+            Comparable::TypeOf(path) => match path.select_single(root, current) {
+                Some(Node::Value(value)) => Operand::String(type_name(value)),
+                Some(Node::Dictionary(_)) => Operand::String("dictionary"),
+                None => Operand::Nothing,
+            },
+            Comparable::Literal(Literal::String(s)) => Operand::String(s),
+            Comparable::Literal(Literal::Integer(i)) => Operand::Number(Number::Integer(*i)),
+            Comparable::Literal(Literal::Real(r)) => Operand::Number(Number::Real(*r)),
+            Comparable::Literal(Literal::Boolean(b)) => Operand::Boolean(*b),
+        }
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Array(_) => "array",
+        Value::Dictionary(_) => "dictionary",
+        Value::Boolean(_) => "boolean",
+        Value::Data(_) => "data",
+        Value::Date(_) => "date",
+        Value::Real(_) => "real",
+        Value::Integer(_) => "integer",
+        Value::String(_) => "string",
+        Value::Uid(_) => "uid",
+    }
+}
+
+impl<'a> Operand<'a> {
+    fn from_node(node: Node<'a>) -> Operand<'a> {
+        let Node::Value(value) = node else {
+            return Operand::Node(node);
+        };
+        match value {
+            Value::String(s) => Operand::String(s),
+            Value::Boolean(b) => Operand::Boolean(*b),
+            Value::Real(r) => Operand::Number(Number::Real(*r)),
+            Value::Integer(i) => Operand::Number(Number::Integer(i.as_i128())),
+            _ => Operand::Node(node),
+        }
+    }
+
+    fn equals(&self, other: &Operand) -> bool {
+        match (self, other) {
+            (Operand::Nothing, Operand::Nothing) => true,
+            (Operand::Boolean(a), Operand::Boolean(b)) => a == b,
+            (Operand::Node(a), Operand::Node(b)) => match (a, b) {
+                (Node::Value(a), Node::Value(b)) => a == b,
+                (Node::Dictionary(a), Node::Dictionary(b)) => a == b,
+                (Node::Value(Value::Dictionary(a)), Node::Dictionary(b))
+                | (Node::Dictionary(b), Node::Value(Value::Dictionary(a))) => a == *b,
+                _ => false,
+            },
+            _ => self.compare(other) == Some(Ordering::Equal),
+        }
+    }
+
+    fn compare(&self, other: &Operand) -> Option<Ordering> {
+        match (self, other) {
+            (Operand::String(a), Operand::String(b)) => Some(a.cmp(b)),
+            (Operand::Number(Number::Integer(a)), Operand::Number(Number::Integer(b))) => {
+                Some(a.cmp(b))
+            }
+            (Operand::Number(a), Operand::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+            _ => None,
+        }
+    }
+}
+
+impl Number {
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::Integer(i) => i as f64,
+            Number::Real(r) => r,
+        }
+    }
+}
+
+/// The maximum nesting depth of filters, parentheses, `!` and `type()` in a query.
+///
+/// This stops queries from overflowing the stack when they are parsed or evaluated.
+const MAX_NESTING_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    query: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    /// Parses a nested expression with `parse`, failing if the query is nested too deeply.
+    fn nested<T>(&mut self, parse: fn(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        if self.depth == MAX_NESTING_DEPTH {
+            return Err(self.error("query is nested too deeply"));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn error(&self, message: &str) -> Error {
+        ErrorKind::InvalidQuery
+            .with_byte_offset(self.pos as u64)
+            .with_context(message.to_owned())
+    }
+
+    fn rest(&self) -> &str {
+        &self.query[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        if self.rest().starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        if self.eat(c.encode_utf8(&mut [0; 4])) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{c}'")))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.rest().trim_start_matches([' ', '\t', '\n', '\r']);
+        self.pos = self.query.len() - trimmed.len();
+    }
+
+    fn segments(&mut self) -> Result<Vec<Segment>, Error> {
+        let mut segments = Vec::new();
+        loop {
+            if self.eat("..") {
+                let selectors = if self.eat("[") {
+                    self.bracketed_selectors()?
+                } else {
+                    vec![self.shorthand_selector()?]
+                };
+                segments.push(Segment::Descendant(selectors));
+            } else if self.eat(".") {
+                segments.push(Segment::Child(vec![self.shorthand_selector()?]));
+            } else if self.eat("[") {
+                segments.push(Segment::Child(self.bracketed_selectors()?));
+            } else {
+                return Ok(segments);
+            }
+        }
+    }
+
+    fn shorthand_selector(&mut self) -> Result<Selector, Error> {
+        if self.eat("*") {
+            return Ok(Selector::Wildcard);
+        }
+        let name = self.name();
+        if name.is_empty() {
+            return Err(self.error("expected a key or '*'"));
+        }
+        Ok(Selector::Name(name.to_owned()))
+    }
+
+    fn name(&mut self) -> &str {
+        let start = self.pos;
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(self.rest().len());
+        self.pos += len;
+        &self.query[start..self.pos]
+    }
+
+    fn bracketed_selectors(&mut self) -> Result<Vec<Selector>, Error> {
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.selector()?);
+            self.skip_whitespace();
+            if self.eat("]") {
+                return Ok(selectors);
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, Error> {
+        match self.peek() {
+            Some('*') => {
+                self.pos += 1;
+                Ok(Selector::Wildcard)
+            }
+            Some('\'' | '"') => Ok(Selector::Name(self.string()?)),
+            Some('?') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                Ok(Selector::Filter(self.nested(Parser::or_expr)?))
+            }
+            _ => {
+                let start = self.optional_integer()?;
+                self.skip_whitespace();
+                if !self.eat(":") {
+                    return start
+                        .map(Selector::Index)
+                        .ok_or_else(|| self.error("expected a selector"));
+                }
+                self.skip_whitespace();
+                let end = self.optional_integer()?;
+                self.skip_whitespace();
+                let step = if self.eat(":") {
+                    self.skip_whitespace();
+                    self.optional_integer()?
+                } else {
+                    None
+                };
+                Ok(Selector::Slice { start, end, step })
+            }
+        }
+    }
+
+    fn optional_integer(&mut self) -> Result<Option<i64>, Error> {
+        let start = self.pos;
+        self.eat("-");
+        let digits = self
+            .rest()
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.rest().len());
+        if digits == 0 {
+            self.pos = start;
+            return Ok(None);
+        }
+        self.pos += digits;
+        self.query[start..self.pos]
+            .parse()
+            .map(Some)
+            .map_err(|_| self.error("integer out of range"))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let quote = self.peek().expect("string starts with a quote");
+        self.pos += 1;
+        let mut string = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                c if c == quote => {
+                    self.pos += i + 1;
+                    return Ok(string);
+                }
+                '\\' => {
+                    let escaped = match chars.next() {
+                        Some((_, 'n')) => '\n',
+                        Some((_, 't')) => '\t',
+                        Some((_, 'r')) => '\r',
+                        Some((_, 'b')) => '\u{8}',
+                        Some((_, 'f')) => '\u{c}',
+                        Some((_, 'u')) => {
+                            let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .filter(|_| hex.len() == 4)
+                                .and_then(char::from_u32)
+                                .ok_or_else(|| self.error("invalid unicode escape"))?
+                        }
+                        Some((_, c @ ('\\' | '/' | '\'' | '"'))) => c,
+                        _ => {
+                            self.pos += i;
+                            return Err(self.error("invalid escape"));
+                        }
+                    };
+                    string.push(escaped);
+                }
+                c => string.push(c),
+            }
+        }
+        self.pos = self.query.len();
+        Err(self.error("unterminated string"))
+    }
+
+    fn or_expr(&mut self) -> Result<Expr, Error> {
+        let mut exprs = vec![self.and_expr()?];
+        while self.eat("||") {
+            self.skip_whitespace();
+            exprs.push(self.and_expr()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.pop().expect("exprs is not empty")
+        } else {
+            Expr::Or(exprs)
+        })
+    }
+
+    fn and_expr(&mut self) -> Result<Expr, Error> {
+        let mut exprs = vec![self.unary_expr()?];
+        while self.eat("&&") {
+            self.skip_whitespace();
+            exprs.push(self.unary_expr()?);
+        }
+        Ok(if exprs.len() == 1 {
+            exprs.pop().expect("exprs is not empty")
+        } else {
+            Expr::And(exprs)
+        })
+    }
+
+    fn unary_expr(&mut self) -> Result<Expr, Error> {
+        let expr = if self.eat("!") && !self.rest().starts_with('=') {
+            self.skip_whitespace();
+            Expr::Not(Box::new(self.nested(Parser::unary_expr)?))
+        } else if self.eat("(") {
+            self.skip_whitespace();
+            let expr = self.nested(Parser::or_expr)?;
+            self.expect(')')?;
+            expr
+        } else {
+            let lhs = self.comparable()?;
+            self.skip_whitespace();
+            match self.compare_op() {
+                Some(op) => {
+                    self.skip_whitespace();
+                    Expr::Compare(lhs, op, self.comparable()?)
+                }
+                None => match lhs {
+                    Comparable::Path(path) => Expr::Exists(path),
+                    _ => return Err(self.error("expected a comparison operator")),
+                },
+            }
+        };
+        self.skip_whitespace();
+        Ok(expr)
+    }
+
+    fn compare_op(&mut self) -> Option<CompareOp> {
+        [
+            ("==", CompareOp::Eq),
+            ("!=", CompareOp::Ne),
+            ("<=", CompareOp::Le),
+            (">=", CompareOp::Ge),
+            ("<", CompareOp::Lt),
+            (">", CompareOp::Gt),
+        ]
+        .into_iter()
+        .find(|(s, _)| self.eat(s))
+        .map(|(_, op)| op)
+    }
+
+    fn comparable(&mut self) -> Result<Comparable, Error> {
+        match self.peek() {
+            Some('@') => {
+                self.pos += 1;
+                Ok(Comparable::Path(Path {
+                    relative: true,
+                    segments: self.segments()?,
+                }))
+            }
+            Some('$') => {
+                self.pos += 1;
+                Ok(Comparable::Path(Path {
+                    relative: false,
+                    segments: self.segments()?,
+                }))
+            }
+            Some('\'' | '"') => Ok(Comparable::Literal(Literal::String(self.string()?))),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            _ if self.eat("true") => Ok(Comparable::Literal(Literal::Boolean(true))),
+            _ if self.eat("false") => Ok(Comparable::Literal(Literal::Boolean(false))),
+            _ if self.eat("type(") => {
+                self.skip_whitespace();
+                let Comparable::Path(path) = self.nested(Parser::comparable)? else {
+                    return Err(self.error("expected a path"));
+                };
+                self.skip_whitespace();
+                self.expect(')')?;
+                Ok(Comparable::TypeOf(path))
+            }
+            _ => Err(self.error("expected a path or literal")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Comparable, Error> {
+        let start = self.pos;
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')))
+            .unwrap_or(self.rest().len());
+        self.pos += len;
+        let number = &self.query[start..self.pos];
+        if let Ok(integer) = number.parse() {
+            return Ok(Comparable::Literal(Literal::Integer(integer)));
+        }
+        match number.parse() {
+            Ok(real) => Ok(Comparable::Literal(Literal::Real(real))),
+            Err(_) => {
+                self.pos = start;
+                Err(self.error("invalid number"))
+            }
+        }
+    }
+}
+
+impl fmt::Display for PathElement<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathElement::Key(key) => write!(f, "{key}"),
+            PathElement::Index(index) => write!(f, "{index}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info_plist() -> Value {
+        Value::from_reader_xml(
+            r#"<plist><dict>
+                <key>CFBundleURLTypes</key>
+                <array>
+                    <dict>
+                        <key>CFBundleURLSchemes</key>
+                        <array><string>app</string><string>app-dev</string></array>
+                    </dict>
+                    <dict>
+                        <key>CFBundleURLName</key><string>com.example</string>
+                        <key>CFBundleURLSchemes</key>
+                        <array><string>example</string></array>
+                    </dict>
+                </array>
+                <key>Numbers</key>
+                <array><integer>1</integer><real>2.5</real><integer>3</integer><integer>4</integer></array>
+                <key>a.b/c</key><true/>
+            </dict></plist>"#
+                .as_bytes(),
+        )
+        .unwrap()
+    }
+
+    fn select<'a>(value: &'a Value, query: &str) -> Vec<&'a Value> {
+        Query::new(query)
+            .unwrap()
+            .select(value)
+            .iter()
+            .map(Match::value)
+            .collect()
+    }
+
+    fn pointers(value: &Value, query: &str) -> Vec<String> {
+        Query::new(query)
+            .unwrap()
+            .select(value)
+            .iter()
+            .map(Match::pointer)
+            .collect()
+    }
+
+    #[test]
+    fn child_and_wildcard_selectors() {
+        let value = info_plist();
+
+        assert_eq!(select(&value, "$"), vec![&value]);
+        assert_eq!(
+            pointers(&value, "$.CFBundleURLTypes[*].CFBundleURLSchemes[*]"),
+            vec![
+                "/CFBundleURLTypes/0/CFBundleURLSchemes/0",
+                "/CFBundleURLTypes/0/CFBundleURLSchemes/1",
+                "/CFBundleURLTypes/1/CFBundleURLSchemes/0",
+            ]
+        );
+        assert_eq!(
+            select(&value, "$['CFBundleURLTypes'][1][\"CFBundleURLName\"]"),
+            vec![&Value::from("com.example")]
+        );
+        assert_eq!(pointers(&value, "$['a.b/c']"), vec!["/a.b~1c"]);
+        assert_eq!(
+            Query::new("$['a.b/c']").unwrap().select(&value)[0].key_path(),
+            r"a\.b/c"
+        );
+        assert!(select(&value, "$.Missing").is_empty());
+    }
+
+    #[test]
+    fn array_selectors() {
+        let value = info_plist();
+        let numbers = |query: &str| {
+            Query::new(query)
+                .unwrap()
+                .select(&value)
+                .iter()
+                .map(|m| m.path().last().unwrap().to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        assert_eq!(numbers("$.Numbers[0, -1]"), "0,3");
+        assert_eq!(numbers("$.Numbers[1:3]"), "1,2");
+        assert_eq!(numbers("$.Numbers[::2]"), "0,2");
+        assert_eq!(numbers("$.Numbers[::-1]"), "3,2,1,0");
+        assert_eq!(numbers("$.Numbers[-2:]"), "2,3");
+        assert_eq!(numbers("$.Numbers[4]"), "");
+        assert_eq!(numbers("$.Numbers[::0]"), "");
+        assert_eq!(numbers("$.Numbers[1:3:9223372036854775807]"), "1");
+        assert_eq!(numbers("$.Numbers[2::-9223372036854775808]"), "2");
+    }
+
+    #[test]
+    fn recursive_descent() {
+        let value = info_plist();
+
+        assert_eq!(
+            select(&value, "$..CFBundleURLSchemes[0]"),
+            vec![&Value::from("app"), &Value::from("example")]
+        );
+        assert_eq!(select(&value, "$..*").len(), 15);
+
+        let value = Value::from_reader_xml(
+            "<plist><dict>
+                <key>a</key><dict><key>b</key><integer>1</integer></dict>
+                <key>c</key><integer>2</integer>
+            </dict></plist>"
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(pointers(&value, "$..*"), vec!["/a", "/c", "/a/b"]);
+    }
+
+    #[test]
+    fn filters() {
+        let value = info_plist();
+
+        assert_eq!(
+            pointers(&value, "$.CFBundleURLTypes[?(@.CFBundleURLName)]"),
+            vec!["/CFBundleURLTypes/1"]
+        );
+        assert_eq!(
+            pointers(&value, "$.CFBundleURLTypes[?!@.CFBundleURLName]"),
+            vec!["/CFBundleURLTypes/0"]
+        );
+        assert_eq!(
+            select(&value, "$..[?(@ == 'app-dev' || @ == \"example\")]"),
+            vec![&Value::from("app-dev"), &Value::from("example")]
+        );
+        assert_eq!(
+            pointers(&value, "$.Numbers[?@ > 2]"),
+            vec!["/Numbers/1", "/Numbers/2", "/Numbers/3"]
+        );
+        assert_eq!(
+            pointers(&value, "$.Numbers[?(@ >= 2.5 && @ < 4)]"),
+            vec!["/Numbers/1", "/Numbers/2"]
+        );
+        assert_eq!(
+            pointers(&value, "$.Numbers[?type(@) == 'real']"),
+            vec!["/Numbers/1"]
+        );
+        assert_eq!(
+            pointers(&value, "$[?type(@) == 'boolean' && @ == true]"),
+            vec!["/a.b~1c"]
+        );
+        assert_eq!(
+            pointers(&value, "$.Numbers[?@ == $.Numbers[0]]"),
+            vec!["/Numbers/0"]
+        );
+    }
+
+    #[test]
+    fn select_dictionary() {
+        let value = info_plist();
+        let dict = value.as_dictionary().unwrap();
+
+        let query = Query::new("$.Numbers[0]").unwrap();
+        assert_eq!(query.select_dictionary(dict), query.select(&value));
+        assert!(Query::new("$").unwrap().select_dictionary(dict).is_empty());
+    }
+
+    #[test]
+    fn invalid_queries() {
+        for (query, offset) in [
+            ("", 0),
+            ("Numbers", 0),
+            ("$.", 2),
+            ("$[0", 3),
+            ("$['a]", 5),
+            ("$[?(@ == )]", 9),
+            ("$[?'a']", 6),
+            ("$x", 1),
+            ("$[?(@.a == 1 && !(@.b)]", 22),
+        ] {
+            let err = Query::new(query).unwrap_err();
+            assert_eq!(err.category(), crate::ErrorCategory::Syntax, "{query}");
+            assert_eq!(err.byte_offset(), Some(offset), "{query}");
+        }
+    }
+
+    #[test]
+    fn nesting_depth() {
+        let value = info_plist();
+
+        let nested = |depth| {
+            format!(
+                "$.CFBundleURLTypes[?({}@.CFBundleURLName{})]",
+                "(".repeat(depth),
+                ")".repeat(depth)
+            )
+        };
+        assert_eq!(pointers(&value, &nested(62)), vec!["/CFBundleURLTypes/1"]);
+        let err = Query::new(&nested(100_000)).unwrap_err();
+        assert_eq!(err.category(), crate::ErrorCategory::Syntax);
+        assert_eq!(err.byte_offset(), Some(84));
+        assert!(Query::new(&format!("$[?({}@)]", "!".repeat(100_000))).is_err());
+        assert!(Query::new(&format!("$[?({}@)]", "@[?(".repeat(100_000))).is_err());
+
+        // Long chains of `||` and `&&` are not nested.
+        let chain = vec!["@.CFBundleURLName"; 100_000].join(" || ");
+        assert_eq!(
+            pointers(&value, &format!("$.CFBundleURLTypes[?({chain})]")),
+            vec!["/CFBundleURLTypes/1"]
+        );
+    }
+}
@@ -10,10 +10,10 @@ use std::{
 
 use crate::{
     date::serde_impls::DATE_NEWTYPE_STRUCT_NAME,
-    error::{self, Error, ErrorKind},
+    error::{self, Error, ErrorKind, KeyPathSegment},
     stream::{self, Writer},
     uid::serde_impls::UID_NEWTYPE_STRUCT_NAME,
-    Date, Integer, Uid, Value, XmlWriteOptions,
+    BinaryWriteOptions, Date, Integer, Uid, Value, XmlWriteOptions,
 };
 
 #[doc(hidden)]
@@ -34,6 +34,9 @@ enum OptionMode {
 pub struct Serializer<W: Writer> {
     writer: W,
     option_mode: OptionMode,
+    /// Set while serializing a map key so the key can be used to report the location of errors.
+    capture_key: bool,
+    key: String,
 }
 
 impl<W: Writer> Serializer<W> {
@@ -41,6 +44,8 @@ impl<W: Writer> Serializer<W> {
         Serializer {
             writer,
             option_mode: OptionMode::Root,
+            capture_key: false,
+            key: String::new(),
         }
     }
 
@@ -109,7 +114,12 @@ impl<W: Writer> Serializer<W> {
 
     fn write_string<'a, T: Into<Cow<'a, str>>>(&mut self, value: T) -> Result<(), Error> {
         self.maybe_write_pending_struct_field_name()?;
-        self.writer.write_string(value.into())
+        let value = value.into();
+        if self.capture_key {
+            self.key.clear();
+            self.key.push_str(&value);
+        }
+        self.writer.write_string(value)
     }
 
     fn write_uid(&mut self, value: Uid) -> Result<(), Error> {
@@ -267,7 +277,7 @@ impl<'a, W: Writer> ser::Serializer for &'a mut Serializer<W> {
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
         let len = len.map(|len| len as u64);
         self.write_start_array(len)?;
-        Ok(Compound { ser: self })
+        Ok(Compound::new(self))
     }
 
     fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Error> {
@@ -297,7 +307,7 @@ impl<'a, W: Writer> ser::Serializer for &'a mut Serializer<W> {
     fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, Error> {
         let len = len.map(|len| len as u64);
         self.write_start_dictionary(len)?;
-        Ok(Compound { ser: self })
+        Ok(Compound::new(self))
     }
 
     fn serialize_struct(
@@ -648,6 +658,40 @@ impl<W: Writer> ser::Serializer for UidSerializer<'_, W> {
 #[doc(hidden)]
 pub struct Compound<'a, W: 'a + Writer> {
     ser: &'a mut Serializer<W>,
+    /// The index of the next element, used to report the location of errors.
+    index: usize,
+    /// The most recent map key, used to report the location of errors.
+    key: String,
+}
+
+impl<'a, W: Writer> Compound<'a, W> {
+    fn new(ser: &'a mut Serializer<W>) -> Compound<'a, W> {
+        Compound {
+            ser,
+            index: 0,
+            key: String::new(),
+        }
+    }
+
+    fn serialize_element<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let index = self.index;
+        self.index += 1;
+        self.ser
+            .serialize_with_option_mode(OptionMode::Explicit, value)
+            .map_err(|err| err.with_outer_key_path_segment(KeyPathSegment::Index(index)))
+    }
+
+    fn serialize_struct_field<T: ?Sized + ser::Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        // We don't want to serialize None if the Option is a struct field as this is how null
+        // fields are represented in plists.
+        self.ser
+            .serialize_with_option_mode(OptionMode::StructField(key), value)
+            .map_err(|err| err.with_outer_key_path_segment(KeyPathSegment::Key(key)))
+    }
 }
 
 impl<W: Writer> ser::SerializeSeq for Compound<'_, W> {
@@ -655,8 +699,7 @@ impl<W: Writer> ser::SerializeSeq for Compound<'_, W> {
     type Error = Error;
 
     fn serialize_element<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<(), Error> {
-        self.ser
-            .serialize_with_option_mode(OptionMode::Explicit, value)
+        Compound::serialize_element(self, value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
@@ -669,8 +712,7 @@ impl<W: Writer> ser::SerializeTuple for Compound<'_, W> {
     type Error = Error;
 
     fn serialize_element<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<(), Error> {
-        self.ser
-            .serialize_with_option_mode(OptionMode::Explicit, value)
+        Compound::serialize_element(self, value)
     }
 
     fn end(self) -> Result<(), Error> {
@@ -683,8 +725,7 @@ impl<W: Writer> ser::SerializeTupleStruct for Compound<'_, W> {
     type Error = Error;
 
     fn serialize_field<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<(), Error> {
-        self.ser
-            .serialize_with_option_mode(OptionMode::Explicit, value)
+        Compound::serialize_element(self, value)
     }
 
     fn end(self) -> Result<(), Error> {
@@ -697,8 +738,7 @@ impl<W: Writer> ser::SerializeTupleVariant for Compound<'_, W> {
     type Error = Error;
 
     fn serialize_field<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<(), Error> {
-        self.ser
-            .serialize_with_option_mode(OptionMode::Explicit, value)
+        Compound::serialize_element(self, value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
@@ -712,13 +752,38 @@ impl<W: Writer> ser::SerializeMap for Compound<'_, W> {
     type Error = Error;
 
     fn serialize_key<T: ?Sized + ser::Serialize>(&mut self, key: &T) -> Result<(), Error> {
-        self.ser
-            .serialize_with_option_mode(OptionMode::Explicit, key)
+        self.ser.capture_key = true;
+        self.ser.key.clear();
+        let result = self
+            .ser
+            .serialize_with_option_mode(OptionMode::Explicit, key);
+        self.ser.capture_key = false;
+        mem::swap(&mut self.key, &mut self.ser.key);
+        result.map_err(|err| err.with_outer_key_path_segment(KeyPathSegment::Key(&self.key)))
     }
 
     fn serialize_value<T: ?Sized + ser::Serialize>(&mut self, value: &T) -> Result<(), Error> {
         self.ser
             .serialize_with_option_mode(OptionMode::Explicit, value)
+            .map_err(|err| err.with_outer_key_path_segment(KeyPathSegment::Key(&self.key)))
+    }
+
+    fn serialize_entry<K, V>(&mut self, key: &K, value: &V) -> Result<(), Error>
+    where
+        K: ?Sized + ser::Serialize,
+        V: ?Sized + ser::Serialize,
+    {
+        // The key is only converted to a string for the key path if serialization fails.
+        let with_key = |err: Error| match to_value(&key) {
+            Ok(Value::String(key)) => err.with_outer_key_path_segment(KeyPathSegment::Key(&key)),
+            _ => err,
+        };
+        self.ser
+            .serialize_with_option_mode(OptionMode::Explicit, key)
+            .map_err(with_key)?;
+        self.ser
+            .serialize_with_option_mode(OptionMode::Explicit, value)
+            .map_err(with_key)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
@@ -735,10 +800,7 @@ impl<W: Writer> ser::SerializeStruct for Compound<'_, W> {
         key: &'static str,
         value: &T,
     ) -> Result<(), Error> {
-        // We don't want to serialize None if the Option is a struct field as this is how null
-        // fields are represented in plists.
-        self.ser
-            .serialize_with_option_mode(OptionMode::StructField(key), value)
+        self.serialize_struct_field(key, value)
     }
 
     fn end(self) -> Result<(), Error> {
@@ -755,8 +817,7 @@ impl<W: Writer> ser::SerializeStructVariant for Compound<'_, W> {
         key: &'static str,
         value: &T,
     ) -> Result<(), Error> {
-        self.ser
-            .serialize_with_option_mode(OptionMode::StructField(key), value)
+        self.serialize_struct_field(key, value)
     }
 
     fn end(self) -> Result<(), Error> {
@@ -783,7 +844,16 @@ pub fn to_file_xml<P: AsRef<Path>, T: ser::Serialize>(path: P, value: &T) -> Res
 
 /// Serializes the given data structure to a byte stream as a binary encoded plist.
 pub fn to_writer_binary<W: Write, T: ser::Serialize>(writer: W, value: &T) -> Result<(), Error> {
-    let writer = stream::BinaryWriter::new(writer);
+    to_writer_binary_with_options(writer, value, &BinaryWriteOptions::default())
+}
+
+/// Serializes to a byte stream as a binary encoded plist, using custom [`BinaryWriteOptions`].
+pub fn to_writer_binary_with_options<W: Write, T: ser::Serialize>(
+    writer: W,
+    value: &T,
+    options: &BinaryWriteOptions,
+) -> Result<(), Error> {
+    let writer = stream::BinaryWriter::new_with_options(writer, options);
     let mut ser = Serializer::new(writer);
     value.serialize(&mut ser)
 }
@@ -967,6 +967,24 @@ fn dictionary_serialize_xml() {
     assert_eq!(xml, comparison);
 }
 
+#[test]
+fn sorted_dictionary_serde() {
+    let xml = b"<plist><dict>
+        <key>b</key><integer>2</integer>
+        <key>a</key><dict><key>d</key><true/><key>c</key><false/></dict>
+    </dict></plist>";
+    let dict: Dictionary<BTreeMap<String, Value>> = crate::from_bytes(xml).unwrap();
+    assert_eq!(dict.keys().collect::<Vec<_>>(), ["a", "b"]);
+
+    // Only the outer dictionary is sorted.
+    let mut buf = Vec::new();
+    crate::to_writer_xml(&mut buf, &dict).unwrap();
+    let roundtrip: Dictionary = crate::from_bytes(&buf).unwrap();
+    assert_eq!(roundtrip.keys().collect::<Vec<_>>(), ["a", "b"]);
+    let inner = roundtrip["a"].as_dictionary().unwrap();
+    assert_eq!(inner.keys().collect::<Vec<_>>(), ["d", "c"]);
+}
+
 #[test]
 fn empty_array_and_dictionary_serialize_to_xml() {
     #[derive(Serialize, Default)]
@@ -1047,3 +1065,136 @@ fn deserialize_with_trailing_events_fails() {
 
     assert!(value.is_err());
 }
+
+#[test]
+fn errors_include_key_path() {
+    #[derive(Debug, Deserialize, Serialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Project {
+        targets: Vec<Target>,
+    }
+
+    #[derive(Debug, Deserialize, Serialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct Target {
+        build_settings: BTreeMap<String, Value>,
+    }
+
+    let xml = r#"<plist><dict>
+        <key>Targets</key>
+        <array>
+            <dict><key>BuildSettings</key><dict/></dict>
+            <dict><key>BuildSettings</key><dict><key>SDKROOT</key><integer>1</integer></dict></dict>
+        </array>
+    </dict></plist>"#;
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    #[allow(dead_code)]
+    struct TypedProject {
+        targets: Vec<BTreeMap<String, BTreeMap<String, String>>>,
+    }
+
+    let err = crate::from_reader_xml::<_, TypedProject>(xml.as_bytes()).unwrap_err();
+    assert_eq!(err.key_path(), Some("Targets[1].BuildSettings.SDKROOT"));
+    assert!(err
+        .to_string()
+        .ends_with(" at Targets[1].BuildSettings.SDKROOT"));
+
+    let mut project: Project = crate::from_reader_xml(xml.as_bytes()).unwrap();
+    project.targets[1]
+        .build_settings
+        .insert("ID".to_owned(), Value::Uid(Uid::new(1)));
+
+    let err = crate::to_writer_xml(Vec::new(), &project).unwrap_err();
+    assert_eq!(err.key_path(), Some("Targets[1].BuildSettings.ID"));
+
+    let err = crate::from_value::<u64>(&Value::from("a")).unwrap_err();
+    assert_eq!(err.key_path(), None);
+}
+
+#[test]
+fn error_key_paths_escape_keys() {
+    let xml = r#"<plist><dict>
+        <key>a.b</key>
+        <dict><key>[0]</key><dict><key>c\d</key><string>e</string></dict></dict>
+    </dict></plist>"#;
+
+    type Nested = BTreeMap<String, BTreeMap<String, BTreeMap<String, u64>>>;
+    let err = crate::from_reader_xml::<_, Nested>(xml.as_bytes()).unwrap_err();
+    assert_eq!(err.key_path(), Some(r"a\.b.\[0].c\\d"));
+
+    let mut value = BTreeMap::new();
+    value.insert("[0]", BTreeMap::from([("a.b", Value::Uid(Uid::new(1)))]));
+    let err = crate::to_writer_xml(Vec::new(), &value).unwrap_err();
+    assert_eq!(err.key_path(), Some(r"\[0].a\.b"));
+}
+
+#[test]
+fn errors_include_position() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Config {
+        name: String,
+        count: u32,
+    }
+
+    let xml = "<plist>
+<dict>
+    <key>name</key>
+    <integer>1</integer>
+</dict>
+</plist>";
+    let err = crate::from_reader_xml::<_, Config>(xml.as_bytes()).unwrap_err();
+    assert_eq!(err.key_path(), Some("name"));
+    assert_eq!(err.line(), Some(4));
+    assert_eq!(err.byte_offset(), Some(59));
+
+    let xml = "<plist>
+<dict>
+    <key>name</key>
+    <string>a</string>
+</dict>
+</plist>";
+    let err = crate::from_reader_xml::<_, Config>(xml.as_bytes()).unwrap_err();
+    assert!(err.to_string().contains("missing field `count`"));
+    assert_eq!(err.line(), Some(5));
+
+    let ascii = "{\n  name = a;\n  count = b;\n}";
+    let err = crate::from_reader_ascii::<_, Config>(ascii.as_bytes()).unwrap_err();
+    assert_eq!(err.key_path(), Some("count"));
+    assert_eq!(err.line(), Some(3));
+
+    let mut binary = Vec::new();
+    crate::to_writer_binary(&mut binary, &Value::from(1)).unwrap();
+    let err = crate::from_bytes::<String>(&binary).unwrap_err();
+    assert!(err.byte_offset().is_some());
+    assert_eq!(err.line(), None);
+
+    let err = crate::from_value::<String>(&Value::from(1)).unwrap_err();
+    assert_eq!(err.byte_offset(), None);
+}
+
+#[test]
+fn canonical_output_is_deterministic() {
+    use crate::{BinaryWriteOptions, CanonicalOptions};
+    use std::collections::HashMap;
+
+    let map: HashMap<String, HashMap<String, u32>> = (0..20)
+        .map(|i| {
+            let inner = (0..5).map(|j| (format!("inner{j}"), j)).collect();
+            (format!("key{i:02}"), inner)
+        })
+        .collect();
+    let sorted: BTreeMap<_, BTreeMap<_, _>> = map
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone().into_iter().collect()))
+        .collect();
+
+    let options = BinaryWriteOptions::default().canonical(CanonicalOptions::new());
+    let mut canonical = Vec::new();
+    crate::to_writer_binary_with_options(&mut canonical, &map, &options).unwrap();
+    let mut expected = Vec::new();
+    crate::to_writer_binary(&mut expected, &sorted).unwrap();
+    assert_eq!(canonical, expected);
+}
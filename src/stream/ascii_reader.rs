@@ -7,8 +7,8 @@
 /// This reader will accept certain ill-formed ascii plist without complaining.
 /// It does not check the integrity of the plist format.
 use crate::{
-    error::{Error, ErrorKind},
-    stream::{Event, OwnedEvent},
+    error::{Error, ErrorKind, FilePosition},
+    stream::{Event, EventPosition, OwnedEvent},
     Integer,
 };
 use std::io::Read;
@@ -16,6 +16,10 @@ use std::io::Read;
 pub struct AsciiReader<R: Read> {
     reader: R,
     current_pos: u64,
+    /// The one-based line of the current char.
+    current_line: u64,
+    /// The value of `current_pos` for the first char of the current line.
+    line_start_pos: u64,
 
     /// lookahead char to avoid backtracking.
     peeked_char: Option<u8>,
@@ -28,6 +32,8 @@ impl<R: Read> AsciiReader<R> {
         Self {
             reader,
             current_pos: 0,
+            current_line: 1,
+            line_start_pos: 1,
             peeked_char: None,
             current_char: None,
         }
@@ -38,7 +44,7 @@ impl<R: Read> AsciiReader<R> {
     }
 
     fn error(&self, kind: ErrorKind) -> Error {
-        kind.with_byte_offset(self.current_pos)
+        kind.with_position(self.event_position())
     }
 
     fn read_one(&mut self) -> Result<Option<u8>, Error> {
@@ -53,6 +59,11 @@ impl<R: Read> AsciiReader<R> {
     /// Consume the reader and set [`Self::current_char`] and
     /// [`Self::peeked_char`]. Returns the current character.
     fn advance(&mut self) -> Result<Option<u8>, Error> {
+        if self.current_char == Some(b'\n') {
+            self.current_line += 1;
+            self.line_start_pos = self.current_pos + 1;
+        }
+
         self.current_char = self.peeked_char;
         self.peeked_char = self.read_one()?;
 
@@ -320,6 +331,18 @@ impl<R: Read> AsciiReader<R> {
     }
 }
 
+impl<R: Read> EventPosition for AsciiReader<R> {
+    fn event_position(&self) -> FilePosition {
+        FilePosition {
+            offset: self.current_pos,
+            line_column: Some((
+                self.current_line,
+                (self.current_pos + 1).saturating_sub(self.line_start_pos).max(1),
+            )),
+        }
+    }
+}
+
 impl<R: Read> Iterator for AsciiReader<R> {
     type Item = Result<OwnedEvent, Error>;
 
@@ -353,7 +376,7 @@ fn map_next_step_to_unicode(c: char) -> char {
 
     let index = c as usize;
 
-    if index < 128 || index > 0xff {
+    if !(128..=0xff).contains(&index) {
         return c;
     }
 
@@ -447,6 +470,18 @@ mod tests {
         assert!(events[6].is_err());
     }
 
+    #[test]
+    fn error_position() {
+        let plist = b"{\n  key = \"\\U123\";\n}";
+        let streaming_parser = AsciiReader::new(&plist[..]);
+        let events: Vec<Result<Event, Error>> = streaming_parser.collect();
+        let err = events[2].as_ref().unwrap_err();
+
+        assert_eq!(err.category(), crate::ErrorCategory::InvalidValue);
+        assert_eq!(err.line(), Some(2));
+        assert_eq!(err.column(), Some(15));
+    }
+
     #[test]
     fn invalid_octal_escapes() {
         let plist = br#"{
@@ -1,12 +1,14 @@
 use std::{
-    io::{self, Read, Seek, SeekFrom},
+    borrow::Cow,
+    collections::HashMap,
+    io::{self, Cursor, Read, Seek, SeekFrom},
     mem::size_of,
 };
 
 use crate::{
     date::{Date, InfiniteOrNanDate},
-    error::{Error, ErrorKind},
-    stream::{Event, OwnedEvent},
+    error::{Error, ErrorKind, FilePosition},
+    stream::{Event, EventPosition, OwnedEvent},
     u64_to_usize, Uid,
 };
 
@@ -21,12 +23,31 @@ enum StackType {
     Dict,
 }
 
+/// A decoded string or data object which is referenced more than once in the plist.
+enum SharedObject {
+    Data(Box<[u8]>),
+    String(Box<str>),
+}
+
+impl SharedObject {
+    fn event(&self) -> OwnedEvent {
+        match self {
+            SharedObject::Data(data) => Event::Data(Cow::Owned(data.to_vec())),
+            SharedObject::String(string) => Event::String(Cow::Owned(string.to_string())),
+        }
+    }
+}
+
 // https://opensource.apple.com/source/CF/CF-550/CFBinaryPList.c
 // https://hg.python.org/cpython/file/3.4/Lib/plistlib.py
 pub struct BinaryReader<R> {
     stack: Vec<StackItem>,
     object_offsets: Vec<u64>,
     object_on_stack: Vec<bool>,
+    object_seen: Vec<bool>,
+    // Binary plist writers store each distinct string once so repeated dictionary keys are
+    // references to the same object. These are decoded once and then reused.
+    shared_objects: HashMap<u64, SharedObject>,
     reader: PosReader<R>,
     ref_size: u8,
     root_object: u64,
@@ -57,7 +78,8 @@ impl<R: Read + Seek> PosReader<R> {
 impl<R: Read> Read for PosReader<R> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let count = self.reader.read(buf)?;
-        self.pos
+        self.pos = self
+            .pos
             .checked_add(count as u64)
             .expect("file cannot be larger than `u64::MAX` bytes");
         Ok(count)
@@ -70,6 +92,8 @@ impl<R: Read + Seek> BinaryReader<R> {
             stack: Vec::new(),
             object_offsets: Vec::new(),
             object_on_stack: Vec::new(),
+            object_seen: Vec::new(),
+            shared_objects: HashMap::new(),
             reader: PosReader { reader, pos: 0 },
             ref_size: 0,
             root_object: 0,
@@ -129,6 +153,7 @@ impl<R: Read + Seek> BinaryReader<R> {
         self.reader.seek(SeekFrom::Start(offset_table_offset))?;
         self.object_offsets = self.read_ints(num_objects, offset_size)?;
         self.object_on_stack = vec![false; self.object_offsets.len()];
+        self.object_seen = vec![false; self.object_offsets.len()];
 
         Ok(())
     }
@@ -182,6 +207,27 @@ impl<R: Read + Seek> BinaryReader<R> {
         Ok(data)
     }
 
+    /// Reads `len` bytes, borrowing them from `source` if the plist is being read from a slice.
+    fn read_data_borrowed<'a>(
+        &mut self,
+        len: u64,
+        source: Option<&'a [u8]>,
+    ) -> Result<Cow<'a, [u8]>, Error> {
+        let Some(source) = source else {
+            return self.read_data(len).map(Cow::Owned);
+        };
+        let end_offset = self
+            .reader
+            .pos
+            .checked_add(len)
+            .filter(|&end_offset| end_offset <= self.trailer_start_offset)
+            .ok_or_else(|| self.with_pos(ErrorKind::ObjectOffsetTooLarge))?;
+        // `trailer_start_offset` lies within `source` so these conversions cannot fail.
+        let data = &source[self.reader.pos as usize..end_offset as usize];
+        self.reader.seek(SeekFrom::Start(end_offset))?;
+        Ok(Cow::Borrowed(data))
+    }
+
     fn seek_to_object(&mut self, object_ref: u64) -> Result<u64, Error> {
         let object_ref = u64_to_usize(object_ref)
             .ok_or_else(|| self.with_pos(ErrorKind::ObjectReferenceTooLarge))?;
@@ -206,6 +252,29 @@ impl<R: Read + Seek> BinaryReader<R> {
         Ok(())
     }
 
+    /// Records that `object_ref` has been decoded, returning `true` if it had been decoded before.
+    fn mark_object_seen(&mut self, object_ref: u64) -> bool {
+        let object_ref = u64_to_usize(object_ref).expect("internal consistency error");
+        std::mem::replace(&mut self.object_seen[object_ref], true)
+    }
+
+    /// Keeps a copy of an owned string or data object if it has been decoded before.
+    fn share_if_repeated(&mut self, object_ref: u64, event: &Event) {
+        let owned = matches!(
+            event,
+            Event::Data(Cow::Owned(_)) | Event::String(Cow::Owned(_))
+        );
+        if !owned || !self.mark_object_seen(object_ref) {
+            return;
+        }
+        let object = match event {
+            Event::Data(data) => SharedObject::Data(data.as_ref().into()),
+            Event::String(string) => SharedObject::String(string.as_ref().into()),
+            _ => return,
+        };
+        self.shared_objects.insert(object_ref, object);
+    }
+
     fn pop_stack_item(&mut self) -> StackItem {
         let item = self.stack.pop().expect("internal consistency error");
         let object_ref = u64_to_usize(item.object_ref).expect("internal consistency error");
@@ -213,7 +282,7 @@ impl<R: Read + Seek> BinaryReader<R> {
         item
     }
 
-    fn read_next(&mut self) -> Result<Option<OwnedEvent>, Error> {
+    fn read_next<'a>(&mut self, source: Option<&'a [u8]>) -> Result<Option<Event<'a>>, Error> {
         let object_ref = if self.ref_size == 0 {
             // Initialise here rather than in new
             self.read_trailer()?;
@@ -239,6 +308,10 @@ impl<R: Read + Seek> BinaryReader<R> {
 
         self.seek_to_object(object_ref)?;
 
+        if let Some(object) = self.shared_objects.get(&object_ref) {
+            return Ok(Some(object.event()));
+        }
+
         let token = self.read_u8()?;
         let ty = (token & 0xf0) >> 4;
         let size = token & 0x0f;
@@ -274,15 +347,21 @@ impl<R: Read + Seek> BinaryReader<R> {
             (0x4, n) => {
                 // Data
                 let len = self.read_object_len(n)?;
-                Some(Event::Data(self.read_data(len)?.into()))
+                let event = Event::Data(self.read_data_borrowed(len, source)?);
+                self.share_if_repeated(object_ref, &event);
+                Some(event)
             }
             (0x5, n) => {
                 // ASCII string
                 let len = self.read_object_len(n)?;
-                let raw = self.read_data(len)?;
-                let string = String::from_utf8(raw)
-                    .map_err(|_| self.with_pos(ErrorKind::InvalidUtf8String))?;
-                Some(Event::String(string.into()))
+                let string = match self.read_data_borrowed(len, source)? {
+                    Cow::Borrowed(raw) => std::str::from_utf8(raw).map(Cow::Borrowed).ok(),
+                    Cow::Owned(raw) => String::from_utf8(raw).map(Cow::Owned).ok(),
+                };
+                let string = string.ok_or_else(|| self.with_pos(ErrorKind::InvalidUtf8String))?;
+                let event = Event::String(string);
+                self.share_if_repeated(object_ref, &event);
+                Some(event)
             }
             (0x6, n) => {
                 // UTF-16 string
@@ -295,7 +374,9 @@ impl<R: Read + Seek> BinaryReader<R> {
 
                 let string = String::from_utf16(&raw_utf16)
                     .map_err(|_| self.with_pos(ErrorKind::InvalidUtf16String))?;
-                Some(Event::String(string.into()))
+                let event = Event::String(string.into());
+                self.share_if_repeated(object_ref, &event);
+                Some(event)
             }
             (0x8, n) if n < 8 => {
                 // Uid
@@ -401,13 +482,9 @@ impl<R: Read + Seek> BinaryReader<R> {
     fn with_pos(&self, kind: ErrorKind) -> Error {
         kind.with_byte_offset(self.reader.pos)
     }
-}
-
-impl<R: Read + Seek> Iterator for BinaryReader<R> {
-    type Item = Result<OwnedEvent, Error>;
 
-    fn next(&mut self) -> Option<Result<OwnedEvent, Error>> {
-        match self.read_next() {
+    fn next_event<'a>(&mut self, source: Option<&'a [u8]>) -> Option<Result<Event<'a>, Error>> {
+        match self.read_next(source) {
             Ok(Some(event)) => Some(Ok(event)),
             Err(err) => {
                 // Mark the plist as finished
@@ -419,6 +496,32 @@ impl<R: Read + Seek> Iterator for BinaryReader<R> {
     }
 }
 
+impl<'a> BinaryReader<Cursor<&'a [u8]>> {
+    /// Returns the next event, borrowing strings and data from the underlying slice where
+    /// possible.
+    pub(crate) fn next_borrowed(&mut self) -> Option<Result<Event<'a>, Error>> {
+        let source: &'a [u8] = self.reader.reader.get_ref();
+        self.next_event(Some(source))
+    }
+}
+
+impl<R: Read + Seek> EventPosition for BinaryReader<R> {
+    fn event_position(&self) -> FilePosition {
+        FilePosition {
+            offset: self.reader.pos,
+            line_column: None,
+        }
+    }
+}
+
+impl<R: Read + Seek> Iterator for BinaryReader<R> {
+    type Item = Result<OwnedEvent, Error>;
+
+    fn next(&mut self) -> Option<Result<OwnedEvent, Error>> {
+        self.next_event(None)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs::File;
@@ -501,6 +604,21 @@ mod tests {
         assert_eq!(events[46], Event::Uid(Uid::new(1)));
     }
 
+    #[test]
+    fn error_offsets_include_bytes_read() {
+        let mut bytes = std::fs::read("./tests/data/binary.plist").unwrap();
+        // Set the trailer's object offset size, which follows six bytes of padding, to an invalid
+        // value.
+        let offset_size_offset = bytes.len() - 32 + 6;
+        bytes[offset_size_offset] = 5;
+
+        let err = BinaryReader::new(Cursor::new(&bytes))
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.byte_offset(), Some(offset_size_offset as u64 + 1));
+    }
+
     #[test]
     fn three_byte_integer_object_offset_plist() {
         let reader =
@@ -513,4 +631,24 @@ mod tests {
         assert_eq!(events[2], Event::StartDictionary(Some(2199)));
         assert_eq!(events[3], Event::String("1838".into()));
     }
+
+    #[test]
+    fn repeated_objects_are_shared() {
+        let value: crate::Value = (0..3)
+            .map(|i| {
+                let mut dict = crate::Dictionary::new();
+                dict.insert("Name".to_owned(), i.into());
+                dict.insert("Na\u{ef}ve".to_owned(), crate::Value::Data(vec![1, 2]));
+                dict
+            })
+            .collect();
+        let mut buf = Vec::new();
+        value.to_writer_binary(&mut buf).unwrap();
+
+        let mut reader = BinaryReader::new(Cursor::new(&buf[..]));
+        let events = std::iter::from_fn(|| reader.next()).map(|e| e.unwrap());
+        assert_eq!(crate::Value::from_events(events.map(Ok)).unwrap(), value);
+        // "Name", "Na\u{ef}ve" and the data are each decoded once and shared.
+        assert_eq!(reader.shared_objects.len(), 3);
+    }
 }
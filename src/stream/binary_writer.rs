@@ -10,7 +10,7 @@ use std::{
 
 use crate::{
     error::{self, Error, ErrorKind, EventKind},
-    stream::Writer,
+    stream::{self, BinaryWriteOptions, Canonicalizer, Writer},
     Date, Integer, Uid,
 };
 
@@ -23,6 +23,7 @@ pub struct BinaryWriter<W: Write> {
     collection_stack: Vec<usize>,
     /// The number of `Collection` and unique `Value` events in `events`.
     num_objects: usize,
+    canonical: Option<Canonicalizer>,
 }
 
 struct PosWriter<W: Write> {
@@ -111,7 +112,12 @@ enum ValueState {
 }
 
 impl<W: Write> BinaryWriter<W> {
+    #[cfg(feature = "enable_unstable_features_that_may_break_with_minor_version_bumps")]
     pub fn new(writer: W) -> BinaryWriter<W> {
+        BinaryWriter::new_with_options(writer, &BinaryWriteOptions::default())
+    }
+
+    pub fn new_with_options(writer: W, opts: &BinaryWriteOptions) -> BinaryWriter<W> {
         BinaryWriter {
             writer: PosWriter { writer, pos: 0 },
             events: Vec::new(),
@@ -119,9 +125,22 @@ impl<W: Write> BinaryWriter<W> {
             values: IndexMap::new(),
             collection_stack: Vec::new(),
             num_objects: 0,
+            canonical: opts.canonical.clone().map(Canonicalizer::new),
         }
     }
 
+    /// Buffers `event` when writing in canonical form, writing the plist once it is complete.
+    fn write_canonical(&mut self, event: stream::Event) -> Result<(), Error> {
+        let mut canonical = self.canonical.take().expect("writing in canonical form");
+        let result = match canonical.push(event) {
+            Ok(Some(value)) => canonical.write_value(&value, self),
+            Ok(None) => Ok(()),
+            Err(err) => Err(err),
+        };
+        self.canonical = Some(canonical);
+        result
+    }
+
     fn write_start_collection(&mut self, ty: CollectionType) -> Result<(), Error> {
         if self.expecting_dictionary_key() {
             let ty_event_kind = match ty {
@@ -314,30 +333,7 @@ impl<W: Write> BinaryWriter<W> {
             }
         }
 
-        // Write object offset table
-        let offset_table_offset = self.writer.pos;
-        let offset_size = plist_ref_size(offset_table_offset);
-        for &offset in &offset_table {
-            write_plist_ref(&mut self.writer, offset_size, offset)?;
-        }
-
-        // Write trailer
-        // 6 zero bytes padding
-        // 1 byte offset size
-        // 1 byte object ref size
-        // 8 bytes number of objects
-        // 8 bytes root object ref (always zero)
-        // 8 bytes file offset of the object offset table
-        let mut trailer = [0; 32];
-        trailer[6] = offset_size;
-        trailer[7] = ref_size;
-        trailer[8..16].copy_from_slice(&(self.num_objects as u64).to_be_bytes());
-        trailer[24..32].copy_from_slice(&(offset_table_offset as u64).to_be_bytes());
-        self.writer.write_exact(&trailer)?;
-
-        self.writer
-            .flush()
-            .map_err(error::from_io_without_position)?;
+        write_plist_trailer(&mut self.writer, &offset_table, ref_size)?;
 
         // Reset plist writer
         self.writer.pos = 0;
@@ -449,122 +445,336 @@ impl<W: Write> BinaryWriter<W> {
         offset_table[object_ref.value()] = self.writer.pos;
         *value_state = ValueState::Written(object_ref);
 
-        match value {
-            Value::Boolean(true) => {
-                self.writer.write_exact(&[0x09])?;
-            }
-            Value::Boolean(false) => {
-                self.writer.write_exact(&[0x08])?;
-            }
-            Value::Data(v) => {
-                write_plist_value_ty_and_size(&mut self.writer, 0x40, v.len())?;
-                self.writer.write_exact(&v[..])?;
+        write_plist_object(&mut self.writer, value)
+    }
+}
+
+impl<W: Write> BinaryWriter<W> {
+    /// Writes `value` as a complete plist.
+    ///
+    /// This encodes the tree directly rather than passing each of its events to the writer, which
+    /// avoids buffering the events and copying every string and data value. The output is the
+    /// same.
+    pub(crate) fn write_value_tree(&mut self, value: &crate::Value) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            for event in value.events() {
+                self.write(event)?;
             }
-            Value::Date(v) => {
-                let secs = v.as_seconds_since_plist_epoch();
-                let mut buf: [_; 9] = [0x33, 0, 0, 0, 0, 0, 0, 0, 0];
-                buf[1..].copy_from_slice(&secs.to_bits().to_be_bytes());
-                self.writer.write_exact(&buf)?;
+            return Ok(());
+        }
+
+        let mut encoder = TreeEncoder {
+            values: IndexMap::new(),
+            value_indices: Vec::new(),
+            next_value_index: 0,
+            collection_refs: Vec::new(),
+            next_object_ref: ObjectRef::zero(),
+            offset_table: Vec::new(),
+            ref_size: 0,
+        };
+        let num_collections = encoder.index_values(value);
+        let num_objects = num_collections + encoder.values.len();
+        encoder.offset_table = vec![0; num_objects];
+        encoder.ref_size = plist_ref_size(num_objects - 1);
+
+        self.writer.write_exact(b"bplist00")?;
+        if is_collection(value) {
+            let object_ref = encoder.next_object_ref.clone_and_increment_self();
+            encoder.write_collection(&mut self.writer, value, object_ref)?;
+        } else {
+            encoder.assign_value_ref(encoder.value_indices[0]);
+            encoder.write_object(&mut self.writer, encoder.value_indices[0])?;
+        }
+        write_plist_trailer(&mut self.writer, &encoder.offset_table, encoder.ref_size)?;
+
+        self.writer.pos = 0;
+        Ok(())
+    }
+}
+
+/// Writes a `Value` tree as a binary plist.
+///
+/// Objects are assigned references and written in the same order as by `BinaryWriter::write_plist`
+/// so the output matches that of the event-based writer.
+struct TreeEncoder<'a> {
+    /// Each unique non-collection value in the tree.
+    values: IndexMap<Value<'a>, ValueState>,
+    /// The indices into `values` of the dictionary keys followed by the non-collection values of
+    /// each collection, in the order in which collections are written.
+    value_indices: Vec<usize>,
+    /// The start in `value_indices` of the next collection to be written.
+    next_value_index: usize,
+    /// The object references of the child collections of each collection being written.
+    collection_refs: Vec<ObjectRef>,
+    next_object_ref: ObjectRef,
+    offset_table: Vec<usize>,
+    ref_size: u8,
+}
+
+impl<'a> TreeEncoder<'a> {
+    /// Deduplicates the non-collection values in `value`, returning the number of collections.
+    ///
+    /// Collections are visited in the order in which they are written by `write_collection`.
+    fn index_values(&mut self, value: &'a crate::Value) -> usize {
+        if !is_collection(value) {
+            self.index_value(Value::borrowed(value).expect("value is not a collection"));
+            return 0;
+        }
+
+        let mut num_collections = 0;
+        let mut stack = vec![value];
+        while let Some(value) = stack.pop() {
+            num_collections += 1;
+            if let crate::Value::Dictionary(dict) = value {
+                for key in dict.keys() {
+                    self.index_value(Value::String(Cow::Borrowed(key)));
+                }
             }
-            Value::Integer(v) => {
-                if let Some(v) = v.as_signed() {
-                    if let Ok(v) = u8::try_from(v) {
-                        self.writer.write_exact(&[0x10, v])?;
-                    } else if let Ok(v) = u16::try_from(v) {
-                        let mut buf: [_; 3] = [0x11, 0, 0];
-                        buf[1..].copy_from_slice(&v.to_be_bytes());
-                        self.writer.write_exact(&buf)?;
-                    } else if let Ok(v) = u32::try_from(v) {
-                        let mut buf: [_; 5] = [0x12, 0, 0, 0, 0];
-                        buf[1..].copy_from_slice(&v.to_be_bytes());
-                        self.writer.write_exact(&buf)?;
-                    } else {
-                        let mut buf: [_; 9] = [0x13, 0, 0, 0, 0, 0, 0, 0, 0];
-                        buf[1..].copy_from_slice(&v.to_be_bytes());
-                        self.writer.write_exact(&buf)?;
-                    }
-                } else if let Some(v) = v.as_unsigned() {
-                    // `u64`s larger than `i64::MAX` are stored as signed 128 bit
-                    // integers.
-                    let mut buf: [_; 17] = [0x14, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-                    buf[1..].copy_from_slice(&i128::from(v).to_be_bytes());
-                    self.writer.write_exact(&buf)?;
-                } else {
-                    unreachable!("an integer can be represented as either an i64 or u64");
+            for child in collection_values(value) {
+                if let Some(child) = Value::borrowed(child) {
+                    self.index_value(child);
                 }
             }
-            Value::Real(v) => {
-                let mut buf: [_; 9] = [0x23, 0, 0, 0, 0, 0, 0, 0, 0];
-                buf[1..].copy_from_slice(&v.to_be_bytes());
-                self.writer.write_exact(&buf)?;
+            // Push child collections in reverse so they are popped in order.
+            let children_start = stack.len();
+            stack.extend(collection_values(value).filter(|child| is_collection(child)));
+            stack[children_start..].reverse();
+        }
+        num_collections
+    }
+
+    fn index_value(&mut self, value: Value<'a>) {
+        let entry = self.values.entry(value);
+        self.value_indices.push(entry.index());
+        entry.or_insert(ValueState::Unassigned);
+    }
+
+    /// Assigns an object reference to the value at `value_index` if it does not already have one.
+    fn assign_value_ref(&mut self, value_index: usize) -> ObjectRef {
+        let (_, value_state) = self
+            .values
+            .get_index_mut(value_index)
+            .expect("internal consistency error");
+        match value_state {
+            ValueState::Unassigned => {
+                let object_ref = self.next_object_ref.clone_and_increment_self();
+                *value_state = ValueState::Unwritten(object_ref.clone());
+                object_ref
+            }
+            ValueState::Unwritten(object_ref) | ValueState::Written(object_ref) => {
+                object_ref.clone()
             }
-            Value::String(v) if v.is_ascii() => {
-                let ascii = v.as_bytes();
-                write_plist_value_ty_and_size(&mut self.writer, 0x50, ascii.len())?;
-                self.writer.write_exact(ascii)?;
+        }
+    }
+
+    fn write_collection(
+        &mut self,
+        writer: &mut PosWriter<impl Write>,
+        value: &'a crate::Value,
+        object_ref: ObjectRef,
+    ) -> Result<(), Error> {
+        // Collections are written depth first using an explicit stack so deeply nested values
+        // cannot overflow the call stack.
+        let mut stack = Vec::new();
+        let mut next_collection = Some((value, object_ref));
+        loop {
+            if let Some((value, object_ref)) = next_collection.take() {
+                let collection_refs_start = self.collection_refs.len();
+                let value_indices_start =
+                    self.write_collection_header(writer, value, object_ref)?;
+                stack.push(CollectionFrame {
+                    children: collection_values(value),
+                    collection_refs_start,
+                    collection_refs: collection_refs_start,
+                    value_indices: value_indices_start,
+                });
             }
-            Value::String(v) => {
-                let utf16_len = v.encode_utf16().count();
-                write_plist_value_ty_and_size(&mut self.writer, 0x60, utf16_len)?;
-                for c in v.encode_utf16() {
-                    self.writer.write_exact(&c.to_be_bytes())?;
+
+            let Some(frame) = stack.last_mut() else {
+                return Ok(());
+            };
+            match frame.children.next() {
+                Some(child) if is_collection(child) => {
+                    let object_ref = self.collection_refs[frame.collection_refs].clone();
+                    frame.collection_refs += 1;
+                    next_collection = Some((child, object_ref));
+                }
+                Some(_) => {
+                    self.write_object(writer, self.value_indices[frame.value_indices])?;
+                    frame.value_indices += 1;
+                }
+                None => {
+                    self.collection_refs.truncate(frame.collection_refs_start);
+                    stack.pop();
                 }
             }
-            Value::Uid(v) => {
-                let v = v.get();
-                if let Ok(v) = u8::try_from(v) {
-                    self.writer.write_exact(&[0x80, v])?;
-                } else if let Ok(v) = u16::try_from(v) {
-                    let mut buf: [_; 3] = [0x81, 0, 0];
-                    buf[1..].copy_from_slice(&v.to_be_bytes());
-                    self.writer.write_exact(&buf)?;
-                } else if let Ok(v) = u32::try_from(v) {
-                    let mut buf: [_; 5] = [0x83, 0, 0, 0, 0];
-                    buf[1..].copy_from_slice(&v.to_be_bytes());
-                    self.writer.write_exact(&buf)?;
-                } else {
-                    let mut buf: [_; 9] = [0x87, 0, 0, 0, 0, 0, 0, 0, 0];
-                    // we want to be explicit about the type here
-                    #[allow(clippy::unnecessary_cast)]
-                    buf[1..].copy_from_slice(&(v as u64).to_be_bytes());
-                    self.writer.write_exact(&buf)?;
+        }
+    }
+
+    /// Writes the object references of a collection followed by its dictionary keys, returning the
+    /// start in `value_indices` of its non-collection values.
+    fn write_collection_header(
+        &mut self,
+        writer: &mut PosWriter<impl Write>,
+        value: &'a crate::Value,
+        object_ref: ObjectRef,
+    ) -> Result<usize, Error> {
+        self.offset_table[object_ref.value()] = writer.pos;
+        let value_indices_start = self.next_value_index;
+        let mut value_indices = value_indices_start;
+
+        let num_keys = match value {
+            crate::Value::Array(array) => {
+                write_plist_value_ty_and_size(writer, 0xa0, array.len())?;
+                0
+            }
+            crate::Value::Dictionary(dict) => {
+                write_plist_value_ty_and_size(writer, 0xd0, dict.len())?;
+                for _ in 0..dict.len() {
+                    let object_ref = self.assign_value_ref(self.value_indices[value_indices]);
+                    value_indices += 1;
+                    write_plist_ref(writer, self.ref_size, object_ref.value())?;
                 }
+                dict.len()
             }
+            _ => unreachable!("value is a collection"),
+        };
+
+        // Assign references to every element of the collection before writing any of them.
+        for child in collection_values(value) {
+            let object_ref = if is_collection(child) {
+                let object_ref = self.next_object_ref.clone_and_increment_self();
+                self.collection_refs.push(object_ref.clone());
+                object_ref
+            } else {
+                let object_ref = self.assign_value_ref(self.value_indices[value_indices]);
+                value_indices += 1;
+                object_ref
+            };
+            write_plist_ref(writer, self.ref_size, object_ref.value())?;
         }
-        Ok(())
+        self.next_value_index = value_indices;
+
+        let mut value_indices = value_indices_start;
+        for _ in 0..num_keys {
+            self.write_object(writer, self.value_indices[value_indices])?;
+            value_indices += 1;
+        }
+        Ok(value_indices)
     }
+
+    fn write_object(
+        &mut self,
+        writer: &mut PosWriter<impl Write>,
+        value_index: usize,
+    ) -> Result<(), Error> {
+        let (value, value_state) = self
+            .values
+            .get_index_mut(value_index)
+            .expect("internal consistency error");
+        let object_ref = match value_state {
+            ValueState::Unassigned => {
+                unreachable!("value object refs are assigned before they are written");
+            }
+            ValueState::Unwritten(object_ref) => object_ref.clone(),
+            ValueState::Written(_) => return Ok(()),
+        };
+
+        self.offset_table[object_ref.value()] = writer.pos;
+        *value_state = ValueState::Written(object_ref);
+        write_plist_object(writer, value)
+    }
+}
+
+/// A collection being written by `TreeEncoder::write_collection`.
+struct CollectionFrame<I> {
+    /// The elements of the collection which have not yet been written.
+    children: I,
+    /// The start in `TreeEncoder::collection_refs` of the collection's child collections.
+    collection_refs_start: usize,
+    /// The position in `TreeEncoder::collection_refs` of the next child collection.
+    collection_refs: usize,
+    /// The position in `TreeEncoder::value_indices` of the next non-collection value.
+    value_indices: usize,
+}
+
+/// Returns the elements of an array or the values of a dictionary.
+fn collection_values(value: &crate::Value) -> impl Iterator<Item = &crate::Value> {
+    let (array, dict) = match value {
+        crate::Value::Array(array) => (Some(array), None),
+        crate::Value::Dictionary(dict) => (None, Some(dict)),
+        _ => (None, None),
+    };
+    array
+        .into_iter()
+        .flatten()
+        .chain(dict.into_iter().flat_map(|dict| dict.values()))
+}
+
+fn is_collection(value: &crate::Value) -> bool {
+    matches!(value, crate::Value::Array(_) | crate::Value::Dictionary(_))
 }
 
 impl<W: Write> Writer for BinaryWriter<W> {
-    fn write_start_array(&mut self, _len: Option<u64>) -> Result<(), Error> {
+    fn write_start_array(&mut self, len: Option<u64>) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(stream::Event::StartArray(len));
+        }
         self.write_start_collection(CollectionType::Array)
     }
-    fn write_start_dictionary(&mut self, _len: Option<u64>) -> Result<(), Error> {
+    fn write_start_dictionary(&mut self, len: Option<u64>) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(stream::Event::StartDictionary(len));
+        }
         self.write_start_collection(CollectionType::Dictionary)
     }
     fn write_end_collection(&mut self) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(stream::Event::EndCollection);
+        }
         self.write_end_collection()
     }
 
     fn write_boolean(&mut self, value: bool) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(stream::Event::Boolean(value));
+        }
         self.write_value(Value::Boolean(value))
     }
     fn write_data(&mut self, value: Cow<[u8]>) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(stream::Event::Data(value));
+        }
         self.write_value(Value::Data(value))
     }
     fn write_date(&mut self, value: Date) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(stream::Event::Date(value));
+        }
         self.write_value(Value::Date(value))
     }
     fn write_integer(&mut self, value: Integer) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(stream::Event::Integer(value));
+        }
         self.write_value(Value::Integer(value))
     }
     fn write_real(&mut self, value: f64) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(stream::Event::Real(value));
+        }
         self.write_value(Value::Real(value.to_bits()))
     }
     fn write_string(&mut self, value: Cow<str>) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(stream::Event::String(value));
+        }
         self.write_value(Value::String(value))
     }
     fn write_uid(&mut self, value: Uid) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(stream::Event::Uid(value));
+        }
         self.write_value(Value::Uid(value))
     }
 }
@@ -609,10 +819,127 @@ fn write_plist_value_ty_and_size(
     Ok(())
 }
 
+/// Writes the object offset table and trailer which end a binary plist.
+fn write_plist_trailer(
+    writer: &mut PosWriter<impl Write>,
+    offset_table: &[usize],
+    ref_size: u8,
+) -> Result<(), Error> {
+    // Write object offset table
+    let offset_table_offset = writer.pos;
+    let offset_size = plist_ref_size(offset_table_offset);
+    for &offset in offset_table {
+        write_plist_ref(writer, offset_size, offset)?;
+    }
+
+    // Write trailer
+    // 6 zero bytes padding
+    // 1 byte offset size
+    // 1 byte object ref size
+    // 8 bytes number of objects
+    // 8 bytes root object ref (always zero)
+    // 8 bytes file offset of the object offset table
+    let mut trailer = [0; 32];
+    trailer[6] = offset_size;
+    trailer[7] = ref_size;
+    trailer[8..16].copy_from_slice(&(offset_table.len() as u64).to_be_bytes());
+    trailer[24..32].copy_from_slice(&(offset_table_offset as u64).to_be_bytes());
+    writer.write_exact(&trailer)?;
+
+    writer.flush().map_err(error::from_io_without_position)
+}
+
+/// Writes a single non-collection object.
+fn write_plist_object(writer: &mut PosWriter<impl Write>, value: &Value) -> Result<(), Error> {
+    match value {
+        Value::Boolean(true) => {
+            writer.write_exact(&[0x09])?;
+        }
+        Value::Boolean(false) => {
+            writer.write_exact(&[0x08])?;
+        }
+        Value::Data(v) => {
+            write_plist_value_ty_and_size(writer, 0x40, v.len())?;
+            writer.write_exact(&v[..])?;
+        }
+        Value::Date(v) => {
+            let secs = v.as_seconds_since_plist_epoch();
+            let mut buf: [_; 9] = [0x33, 0, 0, 0, 0, 0, 0, 0, 0];
+            buf[1..].copy_from_slice(&secs.to_bits().to_be_bytes());
+            writer.write_exact(&buf)?;
+        }
+        Value::Integer(v) => {
+            if let Some(v) = v.as_signed() {
+                if let Ok(v) = u8::try_from(v) {
+                    writer.write_exact(&[0x10, v])?;
+                } else if let Ok(v) = u16::try_from(v) {
+                    let mut buf: [_; 3] = [0x11, 0, 0];
+                    buf[1..].copy_from_slice(&v.to_be_bytes());
+                    writer.write_exact(&buf)?;
+                } else if let Ok(v) = u32::try_from(v) {
+                    let mut buf: [_; 5] = [0x12, 0, 0, 0, 0];
+                    buf[1..].copy_from_slice(&v.to_be_bytes());
+                    writer.write_exact(&buf)?;
+                } else {
+                    let mut buf: [_; 9] = [0x13, 0, 0, 0, 0, 0, 0, 0, 0];
+                    buf[1..].copy_from_slice(&v.to_be_bytes());
+                    writer.write_exact(&buf)?;
+                }
+            } else if let Some(v) = v.as_unsigned() {
+                // `u64`s larger than `i64::MAX` are stored as signed 128 bit
+                // integers.
+                let mut buf: [_; 17] = [0x14, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+                buf[1..].copy_from_slice(&i128::from(v).to_be_bytes());
+                writer.write_exact(&buf)?;
+            } else {
+                unreachable!("an integer can be represented as either an i64 or u64");
+            }
+        }
+        Value::Real(v) => {
+            let mut buf: [_; 9] = [0x23, 0, 0, 0, 0, 0, 0, 0, 0];
+            buf[1..].copy_from_slice(&v.to_be_bytes());
+            writer.write_exact(&buf)?;
+        }
+        Value::String(v) if v.is_ascii() => {
+            let ascii = v.as_bytes();
+            write_plist_value_ty_and_size(writer, 0x50, ascii.len())?;
+            writer.write_exact(ascii)?;
+        }
+        Value::String(v) => {
+            let utf16_len = v.encode_utf16().count();
+            write_plist_value_ty_and_size(writer, 0x60, utf16_len)?;
+            for c in v.encode_utf16() {
+                writer.write_exact(&c.to_be_bytes())?;
+            }
+        }
+        Value::Uid(v) => {
+            let v = v.get();
+            if let Ok(v) = u8::try_from(v) {
+                writer.write_exact(&[0x80, v])?;
+            } else if let Ok(v) = u16::try_from(v) {
+                let mut buf: [_; 3] = [0x81, 0, 0];
+                buf[1..].copy_from_slice(&v.to_be_bytes());
+                writer.write_exact(&buf)?;
+            } else if let Ok(v) = u32::try_from(v) {
+                let mut buf: [_; 5] = [0x83, 0, 0, 0, 0];
+                buf[1..].copy_from_slice(&v.to_be_bytes());
+                writer.write_exact(&buf)?;
+            } else {
+                let mut buf: [_; 9] = [0x87, 0, 0, 0, 0, 0, 0, 0, 0];
+                // we want to be explicit about the type here
+                #[allow(clippy::unnecessary_cast)]
+                buf[1..].copy_from_slice(&(v as u64).to_be_bytes());
+                writer.write_exact(&buf)?;
+            }
+        }
+    }
+    Ok(())
+}
+
 fn plist_ref_size(max_value: usize) -> u8 {
     let significant_bits = 64 - (max_value as u64).leading_zeros() as u8;
     // Convert to number of bytes
-    let significant_bytes = (significant_bits + 7) / 8;
+    let significant_bytes = significant_bits.div_ceil(8);
     // Round up to the next integer byte size which must be power of two.
     significant_bytes.next_power_of_two()
 }
@@ -670,7 +997,21 @@ impl ObjectRef {
     }
 }
 
-impl Value<'_> {
+impl<'a> Value<'a> {
+    /// Borrows a non-collection `crate::Value`, returning `None` for arrays and dictionaries.
+    fn borrowed(value: &'a crate::Value) -> Option<Value<'a>> {
+        Some(match value {
+            crate::Value::Boolean(v) => Value::Boolean(*v),
+            crate::Value::Data(v) => Value::Data(Cow::Borrowed(v)),
+            crate::Value::Date(v) => Value::Date(*v),
+            crate::Value::Integer(v) => Value::Integer(*v),
+            crate::Value::Real(v) => Value::Real(v.to_bits()),
+            crate::Value::String(v) => Value::String(Cow::Borrowed(v)),
+            crate::Value::Uid(v) => Value::Uid(*v),
+            crate::Value::Array(_) | crate::Value::Dictionary(_) => return None,
+        })
+    }
+
     fn into_owned(self) -> Value<'static> {
         match self {
             Value::Boolean(v) => Value::Boolean(v),
@@ -700,7 +1041,10 @@ impl Value<'_> {
 mod tests {
     use std::{fs::File, io::Cursor, path::Path};
 
-    use crate::{stream::BinaryReader, Value};
+    use crate::{
+        stream::{BinaryReader, BinaryWriteOptions, BinaryWriter, Writer},
+        Dictionary, Value,
+    };
 
     fn test_roundtrip<P: AsRef<Path>>(path: P) {
         let reader = File::open(path).unwrap();
@@ -734,4 +1078,58 @@ mod tests {
     fn nskeyedarchiver_roundtrip() {
         test_roundtrip("./tests/data/binary_NSKeyedArchiver.plist")
     }
+
+    #[test]
+    fn value_tree_matches_events() {
+        let mut values = vec![
+            Value::from(1.5),
+            Value::from_file("./tests/data/binary.plist").unwrap(),
+            Value::from_file("./tests/data/utf16_bplist.plist").unwrap(),
+            Value::from_file("./tests/data/binary_NSKeyedArchiver.plist").unwrap(),
+        ];
+        let mut dict = Dictionary::new();
+        dict.insert("a".to_owned(), "b".into());
+        dict.insert(
+            "b".to_owned(),
+            Value::Array(vec!["a".into(), Value::Array(vec![])]),
+        );
+        dict.insert(
+            "c".to_owned(),
+            Value::Array(vec![dict.clone().into(), "b".into()]),
+        );
+        values.push(dict.into());
+
+        for value in values {
+            let mut expected = Vec::new();
+            let mut writer = BinaryWriter::new_with_options(&mut expected, &Default::default());
+            for event in value.events() {
+                writer.write(event).unwrap();
+            }
+
+            let mut actual = Vec::new();
+            BinaryWriter::new_with_options(&mut actual, &BinaryWriteOptions::default())
+                .write_value_tree(&value)
+                .unwrap();
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn deeply_nested_value_tree() {
+        let depth = 200_000;
+        let mut value = Value::Array(Vec::new());
+        for _ in 0..depth {
+            value = Value::Array(vec![value]);
+        }
+
+        let mut buf = Vec::new();
+        value.to_writer_binary(&mut buf).unwrap();
+        let written_len = buf.len();
+
+        // Unnest the value before dropping it to avoid recursing once per level.
+        while let Value::Array(mut array) = value {
+            value = array.pop().unwrap_or(Value::Boolean(false));
+        }
+        assert_eq!(written_len, 1_800_045);
+    }
 }
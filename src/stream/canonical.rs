@@ -0,0 +1,287 @@
+use std::{borrow::Cow, cmp::Ordering, collections::HashSet, fmt, sync::Arc};
+
+use crate::{
+    error::{Error, ErrorKind},
+    stream::{Event, Writer},
+    value::Builder,
+    Dictionary, Value,
+};
+
+type KeyOrder = dyn Fn(&str, &str) -> Ordering + Send + Sync;
+
+/// Options for writing plists in a canonical form which does not depend on the order in which
+/// dictionary entries were inserted.
+///
+/// In canonical form the keys of every dictionary are sorted and dates are truncated to whole
+/// seconds. This gives stable output from sources such as a serialized `HashMap`, and plists
+/// which diff cleanly when checked in to version control.
+///
+/// ```
+/// use plist::{CanonicalOptions, Dictionary, Value, XmlWriteOptions};
+///
+/// let mut dict = Dictionary::new();
+/// dict.insert("b".to_owned(), 2.into());
+/// dict.insert("a".to_owned(), 1.into());
+///
+/// let options = XmlWriteOptions::default()
+///     .root_element(false)
+///     .canonical(CanonicalOptions::new());
+/// let mut xml = Vec::new();
+/// Value::from(dict).to_writer_xml_with_options(&mut xml, &options).unwrap();
+///
+/// assert_eq!(
+///     String::from_utf8(xml).unwrap(),
+///     "<dict>\n\t<key>a</key>\n\t<integer>1</integer>\n\t<key>b</key>\n\t<integer>2</integer>\n</dict>"
+/// );
+/// ```
+#[derive(Clone)]
+pub struct CanonicalOptions {
+    key_order: Option<Arc<KeyOrder>>,
+    whole_second_dates: bool,
+    nfc_keys: bool,
+}
+
+impl CanonicalOptions {
+    /// Returns the default canonicalization options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Specifies the order in which dictionary keys are written.
+    ///
+    /// `compare` should be a total order over all keys. Keys which compare equal are written in
+    /// the order in which they were inserted.
+    ///
+    /// The default is to sort keys by Unicode code point.
+    pub fn key_order<F>(mut self, compare: F) -> Self
+    where
+        F: Fn(&str, &str) -> Ordering + Send + Sync + 'static,
+    {
+        self.key_order = Some(Arc::new(compare));
+        self
+    }
+
+    /// Selects whether dates are truncated to whole seconds.
+    ///
+    /// The default is `true`.
+    pub fn whole_second_dates(mut self, whole_seconds: bool) -> Self {
+        self.whole_second_dates = whole_seconds;
+        self
+    }
+
+    /// Selects whether dictionary keys are converted to Unicode Normalization Form C.
+    ///
+    /// Keys which are equal once normalized cause writing to fail.
+    ///
+    /// The default is `false`.
+    #[cfg(feature = "unicode_normalization")]
+    pub fn nfc_keys(mut self, normalize: bool) -> Self {
+        self.nfc_keys = normalize;
+        self
+    }
+}
+
+impl Default for CanonicalOptions {
+    fn default() -> Self {
+        CanonicalOptions {
+            key_order: None,
+            whole_second_dates: true,
+            nfc_keys: false,
+        }
+    }
+}
+
+impl fmt::Debug for CanonicalOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CanonicalOptions")
+            .field("key_order", &self.key_order.as_ref().map(|_| ".."))
+            .field("whole_second_dates", &self.whole_second_dates)
+            .field("nfc_keys", &self.nfc_keys)
+            .finish()
+    }
+}
+
+/// Buffers the events of a plist and writes them again in canonical form.
+///
+/// Sorting dictionary keys requires the whole dictionary so events are collected into a `Value`
+/// until the root value is complete.
+pub(crate) struct Canonicalizer {
+    options: CanonicalOptions,
+    builder: Builder,
+}
+
+impl Canonicalizer {
+    pub(crate) fn new(options: CanonicalOptions) -> Self {
+        Canonicalizer {
+            options,
+            builder: Builder::default(),
+        }
+    }
+
+    /// Buffers `event`, returning the root value once it is complete.
+    pub(crate) fn push(&mut self, event: Event) -> Result<Option<Value>, Error> {
+        self.builder.write(event)?;
+        if self.builder.is_complete() {
+            self.builder.finish().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Writes `value` to `writer` in canonical form.
+    pub(crate) fn write_value(&self, value: &Value, writer: &mut dyn Writer) -> Result<(), Error> {
+        match value {
+            Value::Array(array) => {
+                writer.write_start_array(Some(array.len() as u64))?;
+                for value in array {
+                    self.write_value(value, writer)?;
+                }
+                writer.write_end_collection()
+            }
+            Value::Dictionary(dict) => {
+                let entries = self.sorted_entries(dict)?;
+                writer.write_start_dictionary(Some(entries.len() as u64))?;
+                for (key, value) in entries {
+                    writer.write_string(key)?;
+                    self.write_value(value, writer)?;
+                }
+                writer.write_end_collection()
+            }
+            Value::Boolean(value) => writer.write_boolean(*value),
+            Value::Data(value) => writer.write_data(Cow::Borrowed(value)),
+            Value::Date(value) if self.options.whole_second_dates => {
+                writer.write_date(value.truncate_to_seconds())
+            }
+            Value::Date(value) => writer.write_date(*value),
+            Value::Real(value) => writer.write_real(*value),
+            Value::Integer(value) => writer.write_integer(*value),
+            Value::String(value) => writer.write_string(Cow::Borrowed(value)),
+            Value::Uid(value) => writer.write_uid(*value),
+        }
+    }
+
+    fn sorted_entries<'a>(
+        &self,
+        dict: &'a Dictionary,
+    ) -> Result<Vec<(Cow<'a, str>, &'a Value)>, Error> {
+        let mut entries: Vec<_> = dict
+            .iter()
+            .map(|(key, value)| (self.normalize_key(key), value))
+            .collect();
+
+        if self.options.nfc_keys {
+            let mut keys = HashSet::with_capacity(entries.len());
+            if !entries.iter().all(|(key, _)| keys.insert(key)) {
+                return Err(ErrorKind::DuplicateDictionaryKey.without_position());
+            }
+        }
+
+        match &self.options.key_order {
+            Some(compare) => entries.sort_by(|(a, _), (b, _)| compare(a, b)),
+            None => entries.sort_by(|(a, _), (b, _)| a.cmp(b)),
+        }
+        Ok(entries)
+    }
+
+    #[cfg(feature = "unicode_normalization")]
+    fn normalize_key<'a>(&self, key: &'a str) -> Cow<'a, str> {
+        use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+        if self.options.nfc_keys && is_nfc_quick(key.chars()) != IsNormalized::Yes {
+            Cow::Owned(key.nfc().collect())
+        } else {
+            Cow::Borrowed(key)
+        }
+    }
+
+    #[cfg(not(feature = "unicode_normalization"))]
+    fn normalize_key<'a>(&self, key: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+    use crate::{stream::XmlWriteOptions, Date};
+
+    fn to_xml(value: &Value, options: CanonicalOptions) -> String {
+        let options = XmlWriteOptions::default()
+            .root_element(false)
+            .indent(b' ', 0)
+            .canonical(options);
+        let mut xml = Vec::new();
+        value
+            .to_writer_xml_with_options(&mut xml, &options)
+            .unwrap();
+        String::from_utf8(xml).unwrap()
+    }
+
+    #[test]
+    fn sorts_keys_recursively() {
+        let value = Value::from_reader_xml(
+            "<plist><array><dict>
+                <key>b</key><dict><key>z</key><true/><key>é</key><true/><key>y</key><true/></dict>
+                <key>a</key><integer>1</integer>
+                <key>B</key><integer>2</integer>
+            </dict></array></plist>"
+                .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            to_xml(&value, CanonicalOptions::new()),
+            "<array><dict><key>B</key><integer>2</integer><key>a</key><integer>1</integer>\
+             <key>b</key><dict><key>y</key><true/><key>z</key><true/><key>é</key><true/></dict>\
+             </dict></array>"
+        );
+
+        let options = CanonicalOptions::new()
+            .key_order(|a, b| a.to_lowercase().cmp(&b.to_lowercase()).then(b.cmp(a)));
+        assert_eq!(
+            to_xml(&value, options),
+            "<array><dict><key>a</key><integer>1</integer><key>b</key>\
+             <dict><key>y</key><true/><key>z</key><true/><key>é</key><true/></dict>\
+             <key>B</key><integer>2</integer></dict></array>"
+        );
+    }
+
+    #[test]
+    fn truncates_dates() {
+        let date = |secs: f64| Date::from(SystemTime::UNIX_EPOCH + Duration::from_secs_f64(secs));
+        let value = Value::from(vec![Value::from(date(1.75)), Value::from(date(2.0))]);
+
+        assert_eq!(
+            to_xml(&value, CanonicalOptions::new()),
+            "<array><date>1970-01-01T00:00:01Z</date><date>1970-01-01T00:00:02Z</date></array>"
+        );
+        assert_eq!(
+            to_xml(&value, CanonicalOptions::new().whole_second_dates(false)),
+            "<array><date>1970-01-01T00:00:01.75Z</date><date>1970-01-01T00:00:02Z</date></array>"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "unicode_normalization")]
+    fn normalizes_keys() {
+        let mut dict = Dictionary::new();
+        dict.insert("e\u{301}".to_owned(), 1.into());
+        dict.insert("d".to_owned(), 2.into());
+        let value = Value::from(dict.clone());
+
+        let options = CanonicalOptions::new().nfc_keys(true);
+        assert_eq!(
+            to_xml(&value, options.clone()),
+            "<dict><key>d</key><integer>2</integer><key>\u{e9}</key><integer>1</integer></dict>"
+        );
+
+        dict.insert("\u{e9}".to_owned(), 3.into());
+        let options = XmlWriteOptions::default().canonical(options);
+        let err = Value::from(dict)
+            .to_writer_xml_with_options(Vec::new(), &options)
+            .unwrap_err();
+        assert_eq!(err.category(), crate::ErrorCategory::UnexpectedStructure);
+    }
+}
@@ -17,15 +17,19 @@ pub(crate) use xml_writer::encode_data_base64 as xml_encode_data_base64;
 mod ascii_reader;
 pub use self::ascii_reader::AsciiReader;
 
+mod canonical;
+pub(crate) use self::canonical::Canonicalizer;
+pub use self::canonical::CanonicalOptions;
+
 use std::{
     borrow::Cow,
-    io::{self, BufReader, Read, Seek},
+    io::{self, BufReader, Cursor, Read, Seek},
     vec,
 };
 
 use crate::{
     dictionary,
-    error::{Error, ErrorKind},
+    error::{Error, ErrorKind, FilePosition},
     Date, Integer, Uid, Value,
 };
 
@@ -94,6 +98,36 @@ pub struct XmlWriteOptions {
     root_element: bool,
     indent_char: u8,
     indent_count: usize,
+    xml_declaration: bool,
+    doctype: Option<Cow<'static, str>>,
+    self_closing_empty_collections: bool,
+    data_line_len: usize,
+    trailing_newline: bool,
+    invalid_chars: InvalidXmlCharPolicy,
+    canonical: Option<CanonicalOptions>,
+}
+
+/// Options for customizing serialization of binary plists.
+#[derive(Clone, Debug, Default)]
+pub struct BinaryWriteOptions {
+    canonical: Option<CanonicalOptions>,
+}
+
+/// How to write strings containing characters which are not allowed in XML 1.0 documents.
+///
+/// These are the control characters other than tab, line feed and carriage return, along with
+/// `U+FFFE` and `U+FFFF`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvalidXmlCharPolicy {
+    /// Fail with an error.
+    Error,
+    /// Replace each invalid character with the given character.
+    Replace(char),
+    /// Write the UTF-8 encoded string as a `<data>` element.
+    ///
+    /// Dictionary keys cannot be written as data so an error is returned for them instead.
+    WriteAsData,
 }
 
 impl XmlWriteOptions {
@@ -155,6 +189,98 @@ impl XmlWriteOptions {
         self.root_element = write_root;
         self
     }
+
+    /// Selects whether to write the `<?xml version="1.0" encoding="UTF-8"?>` declaration.
+    ///
+    /// This has no effect if the root element is not written.
+    ///
+    /// The default is `true`.
+    pub fn xml_declaration(mut self, write_declaration: bool) -> Self {
+        self.xml_declaration = write_declaration;
+        self
+    }
+
+    /// Selects whether to write the plist document type declaration.
+    ///
+    /// This has no effect if the root element is not written.
+    ///
+    /// The default is `true`.
+    pub fn doctype(mut self, write_doctype: bool) -> Self {
+        self.doctype = write_doctype.then_some(Cow::Borrowed(xml_writer::PLIST_DOCTYPE));
+        self
+    }
+
+    /// Specifies the document type declaration to write in place of the default.
+    ///
+    /// The declaration is written as is, for example:
+    /// ```xml
+    /// <!DOCTYPE plist SYSTEM "file://localhost/System/Library/DTDs/PropertyList.dtd">
+    /// ```
+    ///
+    /// This may be either an `&'static str` or an owned `String`.
+    pub fn custom_doctype(mut self, doctype: impl Into<Cow<'static, str>>) -> Self {
+        self.doctype = Some(doctype.into());
+        self
+    }
+
+    /// Selects whether empty arrays and dictionaries are written as `<array/>` and `<dict/>` or
+    /// as `<array></array>` and `<dict></dict>`.
+    ///
+    /// The default is `true`.
+    pub fn self_closing_empty_collections(mut self, self_closing: bool) -> Self {
+        self.self_closing_empty_collections = self_closing;
+        self
+    }
+
+    /// Specifies the maximum number of base64 characters on each line of a `<data>` element.
+    ///
+    /// The length is rounded down to a multiple of four, and lengths of one to three are raised to
+    /// four, the length of the shortest base64 group. A length of zero writes the data on the
+    /// same line as its tags.
+    ///
+    /// The default is `68`, matching Apple's tools.
+    pub fn data_line_len(mut self, line_len: usize) -> Self {
+        self.data_line_len = line_len;
+        self
+    }
+
+    /// Selects whether to write a newline after the end of the plist.
+    ///
+    /// The default is `false`.
+    pub fn trailing_newline(mut self, write_newline: bool) -> Self {
+        self.trailing_newline = write_newline;
+        self
+    }
+
+    /// Specifies how to write strings containing characters which are not allowed in XML.
+    ///
+    /// The default is [`InvalidXmlCharPolicy::Error`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the replacement character of [`InvalidXmlCharPolicy::Replace`] is itself not
+    /// allowed in XML.
+    pub fn invalid_char_policy(mut self, policy: InvalidXmlCharPolicy) -> Self {
+        if let InvalidXmlCharPolicy::Replace(replacement) = policy {
+            assert!(
+                !xml_writer::is_invalid_xml_char(replacement),
+                "replacement char must be allowed in xml"
+            );
+        }
+        self.invalid_chars = policy;
+        self
+    }
+
+    /// Writes the plist in canonical form, with sorted dictionary keys, as described by
+    /// [`CanonicalOptions`].
+    ///
+    /// The whole plist is buffered in memory before being written.
+    ///
+    /// The default is to write dictionary entries in the order they are given.
+    pub fn canonical(mut self, options: CanonicalOptions) -> Self {
+        self.canonical = Some(options);
+        self
+    }
 }
 
 impl Default for XmlWriteOptions {
@@ -163,10 +289,28 @@ impl Default for XmlWriteOptions {
             indent_char: b'\t',
             indent_count: 1,
             root_element: true,
+            xml_declaration: true,
+            doctype: Some(Cow::Borrowed(xml_writer::PLIST_DOCTYPE)),
+            self_closing_empty_collections: true,
+            data_line_len: xml_writer::DATA_MAX_LINE_CHARS,
+            trailing_newline: false,
+            invalid_chars: InvalidXmlCharPolicy::Error,
+            canonical: None,
         }
     }
 }
 
+impl BinaryWriteOptions {
+    /// Writes the plist in canonical form, with sorted dictionary keys, as described by
+    /// [`CanonicalOptions`].
+    ///
+    /// The default is to write dictionary entries in the order they are given.
+    pub fn canonical(mut self, options: CanonicalOptions) -> Self {
+        self.canonical = Some(options);
+        self
+    }
+}
+
 impl<'a> Events<'a> {
     pub(crate) fn new(value: &'a Value) -> Events<'a> {
         Events {
@@ -329,6 +473,63 @@ impl<R: Read + Seek> Iterator for Reader<R> {
     }
 }
 
+impl<R: Read + Seek> EventPosition for Reader<R> {
+    fn event_position(&self) -> FilePosition {
+        match &self.0 {
+            ReaderInner::Uninitialized(_) => FilePosition {
+                offset: 0,
+                line_column: None,
+            },
+            ReaderInner::Binary(parser) => parser.event_position(),
+            ReaderInner::Xml(parser) => parser.event_position(),
+            ReaderInner::Ascii(parser) => parser.event_position(),
+        }
+    }
+}
+
+/// Reads events from a slice containing a plist of any encoding, borrowing strings and data from
+/// the slice where possible.
+pub(crate) enum SliceReader<'a> {
+    Binary(BinaryReader<Cursor<&'a [u8]>>),
+    Xml(XmlReader<Cursor<&'a [u8]>>, &'a [u8]),
+    Other(Reader<Cursor<&'a [u8]>>),
+}
+
+impl<'a> SliceReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> SliceReader<'a> {
+        if bytes.starts_with(b"bplist00") {
+            SliceReader::Binary(BinaryReader::new(Cursor::new(bytes)))
+        } else {
+            SliceReader::Xml(XmlReader::new(Cursor::new(bytes)), bytes)
+        }
+    }
+}
+
+impl<'a> Iterator for SliceReader<'a> {
+    type Item = Result<Event<'a>, Error>;
+
+    fn next(&mut self) -> Option<Result<Event<'a>, Error>> {
+        match self {
+            SliceReader::Binary(reader) => reader.next_borrowed(),
+            SliceReader::Xml(reader, bytes) => match reader.next_borrowed() {
+                // Leave detecting other formats to `Reader` if no XML markup is found.
+                Some(Err(_)) if !reader.xml_doc_started() => {
+                    *self = SliceReader::Other(Reader::new(Cursor::new(*bytes)));
+                    self.next()
+                }
+                res => res,
+            },
+            SliceReader::Other(reader) => reader.next(),
+        }
+    }
+}
+
+/// A source of events which knows where in the input data each event was read from.
+pub(crate) trait EventPosition {
+    /// Returns the position of the most recently read event.
+    fn event_position(&self) -> FilePosition;
+}
+
 fn from_io_offset_0(err: io::Error) -> Error {
     ErrorKind::Io(err).with_byte_offset(0)
 }
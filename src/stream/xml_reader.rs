@@ -1,10 +1,20 @@
 use base64::{engine::general_purpose::STANDARD as base64_standard, Engine};
-use quick_xml::{escape::resolve_xml_entity, events::Event as XmlEvent, Error as XmlReaderError, Reader as EventReader};
-use std::io::{self, BufRead};
+use quick_xml::{
+    errors::IllFormedError,
+    escape::resolve_xml_entity,
+    events::{BytesText, Event as XmlEvent},
+    Error as XmlReaderError, Reader as EventReader,
+};
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    io::{self, BufRead, Cursor, Read},
+    ops::Range,
+};
 
 use crate::{
     error::{Error, ErrorKind, FilePosition},
-    stream::{Event, OwnedEvent},
+    stream::{Event, EventPosition, OwnedEvent},
     Date, Integer,
 };
 
@@ -15,17 +25,64 @@ pub struct XmlReader<R: BufRead> {
     state: ReaderState<R>,
 }
 
-struct ReaderState<R: BufRead>(EventReader<R>);
+struct ReaderState<R: BufRead> {
+    reader: EventReader<LineTracker<R>>,
+    /// The start of the name in `open_element_names` and the position of each open element below
+    /// the root `<plist>` element.
+    open_elements: Vec<(usize, FilePosition)>,
+    open_element_names: Vec<u8>,
+    /// Reused to collect the text content of elements.
+    content: String,
+}
+
+/// Counts the lines consumed from a reader so errors can report a line and column.
+struct LineTracker<R> {
+    reader: R,
+    pos: u64,
+    /// The offsets of the first byte of each line from `first_line` up to the line containing
+    /// `pos`. Lines before the current event are forgotten so this stays short.
+    line_starts: VecDeque<u64>,
+    /// The one-based line starting at `line_starts[0]`.
+    first_line: u64,
+}
 
-enum ReadResult {
+enum ReadResult<'a> {
     XmlDecl,
-    Event(OwnedEvent),
+    Event(Event<'a>),
     Eof,
 }
 
+/// A run of text within an element.
+enum TextRun<'buf> {
+    /// Text and its offsets in the input.
+    Text(BytesText<'buf>, Range<u64>),
+    Char(char),
+    Entity(&'static str),
+    /// Markup such as comments and unknown entities which is ignored.
+    Ignored,
+}
+
+/// The number of base64 characters decoded at once. A multiple of four so only the final chunk
+/// can contain padding.
+const BASE64_CHUNK_LEN: usize = 1024;
+
+/// Decodes base64 text as it is read, ignoring ASCII whitespace.
+struct Base64Decoder {
+    chunk: [u8; BASE64_CHUNK_LEN],
+    chunk_len: usize,
+    data: Vec<u8>,
+    padded: bool,
+    invalid: bool,
+}
+
 impl<R: BufRead> XmlReader<R> {
     pub fn new(reader: R) -> XmlReader<R> {
-        let mut xml_reader = EventReader::from_reader(reader);
+        let mut xml_reader = EventReader::from_reader(LineTracker {
+            reader,
+            pos: 0,
+            line_starts: VecDeque::from([0]),
+            first_line: 1,
+        });
         let config = xml_reader.config_mut();
         config.trim_text(false);
         config.check_end_names = true;
@@ -35,17 +92,62 @@ impl<R: BufRead> XmlReader<R> {
             buffer: Vec::new(),
             started: false,
             finished: false,
-            state: ReaderState(xml_reader),
+            state: ReaderState {
+                reader: xml_reader,
+                open_elements: Vec::new(),
+                open_element_names: Vec::new(),
+                content: String::new(),
+            },
         }
     }
 
     pub fn into_inner(self) -> R {
-        self.state.0.into_inner()
+        self.state.reader.into_inner().reader
     }
 
     pub(crate) fn xml_doc_started(&self) -> bool {
         self.started
     }
+
+    fn next_event<'a>(&mut self, source: Option<&'a [u8]>) -> Option<Result<Event<'a>, Error>> {
+        if self.finished {
+            return None;
+        }
+
+        loop {
+            // Positions are only reported from the current event onwards.
+            let pos = self.state.reader.buffer_position();
+            self.state.reader.get_mut().forget_lines_before(pos);
+
+            match self.state.read_next(&mut self.buffer, source) {
+                Ok(ReadResult::XmlDecl) => {
+                    self.started = true;
+                }
+                Ok(ReadResult::Event(event)) => {
+                    self.started = true;
+                    return Some(Ok(event));
+                }
+                Ok(ReadResult::Eof) => {
+                    self.started = true;
+                    self.finished = true;
+                    return None;
+                }
+                Err(err) => {
+                    self.finished = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
+
+impl<'a> XmlReader<Cursor<&'a [u8]>> {
+    /// Returns the next event, borrowing strings from the underlying slice where they contain no
+    /// entity references.
+    pub(crate) fn next_borrowed(&mut self) -> Option<Result<Event<'a>, Error>> {
+        let source = *self.state.reader.get_ref().reader.get_ref();
+        self.next_event(Some(source))
+    }
 }
 
 impl From<XmlReaderError> for ErrorKind {
@@ -70,41 +172,27 @@ impl From<XmlReaderError> for ErrorKind {
     }
 }
 
+impl<R: BufRead> EventPosition for XmlReader<R> {
+    fn event_position(&self) -> FilePosition {
+        self.state.xml_reader_pos()
+    }
+}
+
 impl<R: BufRead> Iterator for XmlReader<R> {
     type Item = Result<OwnedEvent, Error>;
 
     fn next(&mut self) -> Option<Result<OwnedEvent, Error>> {
-        if self.finished {
-            return None;
-        }
-
-        loop {
-            match self.state.read_next(&mut self.buffer) {
-                Ok(ReadResult::XmlDecl) => {
-                    self.started = true;
-                }
-                Ok(ReadResult::Event(event)) => {
-                    self.started = true;
-                    return Some(Ok(event));
-                }
-                Ok(ReadResult::Eof) => {
-                    self.started = true;
-                    self.finished = true;
-                    return None;
-                }
-                Err(err) => {
-                    self.finished = true;
-                    return Some(Err(err));
-                }
-            }
-        }
+        self.next_event(None)
     }
 }
 
 impl<R: BufRead> ReaderState<R> {
     fn xml_reader_pos(&self) -> FilePosition {
-        let pos = self.0.buffer_position();
-        FilePosition(pos)
+        let offset = self.reader.buffer_position();
+        FilePosition {
+            offset,
+            line_column: self.reader.get_ref().line_column(offset),
+        }
     }
 
     fn with_pos(&self, kind: ErrorKind) -> Error {
@@ -112,55 +200,168 @@ impl<R: BufRead> ReaderState<R> {
     }
 
     fn read_xml_event<'buf>(&mut self, buffer: &'buf mut Vec<u8>) -> Result<XmlEvent<'buf>, Error> {
-        let event = self.0.read_event_into(buffer);
-        let pos = self.xml_reader_pos();
-        event.map_err(|err| ErrorKind::from(err).with_position(pos))
+        // `quick-xml` appends to the buffer so it must be cleared for it to be reused.
+        buffer.clear();
+        let event = self.reader.read_event_into(buffer);
+        match event {
+            Ok(XmlEvent::Start(ref start)) => {
+                let name = start.local_name();
+                // The root `<plist>` element is not tracked.
+                if !(self.open_elements.is_empty() && name.as_ref() == b"plist") {
+                    let name_start = self.open_element_names.len();
+                    self.open_element_names.extend_from_slice(name.as_ref());
+                    self.open_elements.push((name_start, self.xml_reader_pos()));
+                }
+            }
+            Ok(XmlEvent::End(_)) => {
+                if let Some((name_start, _)) = self.open_elements.pop() {
+                    self.open_element_names.truncate(name_start);
+                }
+            }
+            Ok(XmlEvent::Eof) if !self.open_elements.is_empty() => {
+                return Err(self.unclosed_element_error(ErrorKind::UnclosedXmlElement));
+            }
+            Err(XmlReaderError::IllFormed(IllFormedError::MismatchedEndTag { .. })) => {
+                return Err(self.unclosed_element_error(ErrorKind::InvalidXmlSyntax));
+            }
+            _ => (),
+        }
+        event.map_err(|err| self.with_pos(ErrorKind::from(err)))
     }
 
-    fn read_content(&mut self, buffer: &mut Vec<u8>) -> Result<String, Error> {
-        let mut content = String::new();
-        loop {
-            match self.read_xml_event(buffer)? {
-                XmlEvent::Text(text) => {
-                    let decoded = text
+    /// Returns an error noting the innermost open element.
+    fn unclosed_element_error(&self, kind: ErrorKind) -> Error {
+        let err = self.with_pos(kind);
+        let Some(&(name_start, ref pos)) = self.open_elements.last() else {
+            return err;
+        };
+        let name = String::from_utf8_lossy(&self.open_element_names[name_start..]);
+        match pos.line_column {
+            Some((line, _)) => err.with_context(format!(
+                "expected </{name}> to close element opened at line {line}"
+            )),
+            None => err.with_context(format!(
+                "expected </{name}> to close element opened at offset {}",
+                pos.offset
+            )),
+        }
+    }
+
+    /// Reads the next run of text in the current element, or `None` once its end tag is read.
+    fn read_text_run<'buf>(
+        &mut self,
+        buffer: &'buf mut Vec<u8>,
+    ) -> Result<Option<TextRun<'buf>>, Error> {
+        let start = self.reader.buffer_position();
+        let run = match self.read_xml_event(buffer)? {
+            XmlEvent::Text(text) => TextRun::Text(text, start..self.reader.buffer_position()),
+            XmlEvent::GeneralRef(bytes) => {
+                if let Some(ch) = bytes
+                    .resolve_char_ref()
+                    .map_err(|err| self.with_pos(ErrorKind::from(err)))?
+                {
+                    TextRun::Char(ch)
+                } else {
+                    let decoded = bytes
                         .decode()
                         .map_err(|err| self.with_pos(ErrorKind::from(err)))?;
-                    content.push_str(&decoded);
+                    resolve_xml_entity(&decoded).map_or(TextRun::Ignored, TextRun::Entity)
                 }
-                XmlEvent::GeneralRef(bytes) => {
-                    if let Some(ch) = bytes
-                        .resolve_char_ref()
-                        .map_err(|err| self.with_pos(ErrorKind::from(err)))?
-                    {
-                        content.push(ch);
-                    } else {
-                        let decoded = bytes
-                            .decode()
-                            .map_err(|err| self.with_pos(ErrorKind::from(err)))?;
-                        if let Some(entity) = resolve_xml_entity(&decoded) {
-                            content.push_str(entity);
-                        }
-                    }
-                }
-                XmlEvent::End(_) => {
-                    break;
+            }
+            XmlEvent::End(_) => return Ok(None),
+            XmlEvent::Eof => return Err(self.with_pos(ErrorKind::UnclosedXmlElement)),
+            XmlEvent::Start(_) => return Err(self.with_pos(ErrorKind::UnexpectedXmlOpeningTag)),
+            XmlEvent::PI(_)
+            | XmlEvent::Empty(_)
+            | XmlEvent::Comment(_)
+            | XmlEvent::CData(_)
+            | XmlEvent::Decl(_)
+            | XmlEvent::DocType(_) => TextRun::Ignored,
+        };
+        Ok(Some(run))
+    }
+
+    /// Reads the text content of the current element.
+    ///
+    /// Content consisting of a single run of text is borrowed from `source` if it is given. Otherwise
+    /// the content is collected into `self.content` and `None` is returned.
+    fn read_content<'a>(
+        &mut self,
+        buffer: &mut Vec<u8>,
+        source: Option<&'a [u8]>,
+    ) -> Result<Option<Cow<'a, str>>, Error> {
+        self.content.clear();
+        let mut borrowed: Option<Cow<'a, str>> = None;
+        while let Some(run) = self.read_text_run(buffer)? {
+            if let (TextRun::Text(_, range), Some(source)) = (&run, source) {
+                if borrowed.is_none() && self.content.is_empty() {
+                    let text = &source[range.start as usize..range.end as usize];
+                    let decoded = self
+                        .reader
+                        .decoder()
+                        .decode(text)
+                        .map_err(|err| self.with_pos(ErrorKind::from(err)))?;
+                    borrowed = Some(decoded);
+                    continue;
                 }
-                XmlEvent::Eof => return Err(self.with_pos(ErrorKind::UnclosedXmlElement)),
-                XmlEvent::Start(_) => return Err(self.with_pos(ErrorKind::UnexpectedXmlOpeningTag)),
-                XmlEvent::PI(_)
-                | XmlEvent::Empty(_)
-                | XmlEvent::Comment(_)
-                | XmlEvent::CData(_)
-                | XmlEvent::Decl(_)
-                | XmlEvent::DocType(_) => {
-                    // skip
+            }
+            if matches!(run, TextRun::Ignored) {
+                continue;
+            }
+            if let Some(text) = borrowed.take() {
+                self.content.push_str(&text);
+            }
+            match run {
+                TextRun::Text(text, _) => {
+                    let decoded = text
+                        .decode()
+                        .map_err(|err| self.with_pos(ErrorKind::from(err)))?;
+                    self.content.push_str(&decoded);
                 }
+                TextRun::Char(ch) => self.content.push(ch),
+                TextRun::Entity(entity) => self.content.push_str(entity),
+                TextRun::Ignored => (),
+            }
+        }
+        if borrowed.is_none() && self.content.is_empty() {
+            return Ok(Some(Cow::Borrowed("")));
+        }
+        Ok(borrowed)
+    }
+
+    /// Reads the text content of the current element as a string.
+    fn read_string<'a>(
+        &mut self,
+        buffer: &mut Vec<u8>,
+        source: Option<&'a [u8]>,
+    ) -> Result<Cow<'a, str>, Error> {
+        match self.read_content(buffer, source)? {
+            Some(content) => Ok(content),
+            None => Ok(Cow::Owned(std::mem::take(&mut self.content))),
+        }
+    }
+
+    /// Reads and decodes the base64 content of the current `<data>` element.
+    fn read_data(&mut self, buffer: &mut Vec<u8>) -> Result<Vec<u8>, Error> {
+        let mut decoder = Base64Decoder::new();
+        while let Some(run) = self.read_text_run(buffer)? {
+            match run {
+                TextRun::Text(text, _) => decoder.push(&text),
+                TextRun::Char(ch) => decoder.push(ch.encode_utf8(&mut [0; 4]).as_bytes()),
+                TextRun::Entity(entity) => decoder.push(entity.as_bytes()),
+                TextRun::Ignored => (),
             }
         }
-        Ok(content)
+        decoder
+            .finish()
+            .ok_or_else(|| self.with_pos(ErrorKind::InvalidDataString))
     }
 
-    fn read_next(&mut self, buffer: &mut Vec<u8>) -> Result<ReadResult, Error> {
+    fn read_next<'a>(
+        &mut self,
+        buffer: &mut Vec<u8>,
+        source: Option<&'a [u8]>,
+    ) -> Result<ReadResult<'a>, Error> {
         loop {
             match self.read_xml_event(buffer)? {
                 XmlEvent::Decl(_) | XmlEvent::DocType(_) => return Ok(ReadResult::XmlDecl),
@@ -169,29 +370,25 @@ impl<R: BufRead> ReaderState<R> {
                         b"plist" => {}
                         b"array" => return Ok(ReadResult::Event(Event::StartArray(None))),
                         b"dict" => return Ok(ReadResult::Event(Event::StartDictionary(None))),
-                        b"key" => {
-                            return Ok(ReadResult::Event(Event::String(
-                                self.read_content(buffer)?.into(),
-                            )))
+                        b"key" | b"string" => {
+                            let s = self.read_string(buffer, source)?;
+                            return Ok(ReadResult::Event(Event::String(s)));
                         }
                         b"data" => {
-                            let mut encoded = self.read_content(buffer)?;
-                            // Strip whitespace and line endings from input string
-                            encoded.retain(|c| !c.is_ascii_whitespace());
-                            let data = base64_standard
-                                .decode(&encoded)
-                                .map_err(|_| self.with_pos(ErrorKind::InvalidDataString))?;
+                            let data = self.read_data(buffer)?;
                             return Ok(ReadResult::Event(Event::Data(data.into())));
                         }
                         b"date" => {
-                            let s = self.read_content(buffer)?;
-                            let date = Date::from_xml_format(&s)
+                            let content = self.read_content(buffer, source)?;
+                            let s = content.as_deref().unwrap_or(&self.content);
+                            let date = Date::from_xml_format(s)
                                 .map_err(|_| self.with_pos(ErrorKind::InvalidDateString))?;
                             return Ok(ReadResult::Event(Event::Date(date)));
                         }
                         b"integer" => {
-                            let s = self.read_content(buffer)?;
-                            match Integer::from_str(&s) {
+                            let content = self.read_content(buffer, source)?;
+                            let s = content.as_deref().unwrap_or(&self.content);
+                            match Integer::from_str(s) {
                                 Ok(i) => return Ok(ReadResult::Event(Event::Integer(i))),
                                 Err(_) => {
                                     return Err(self.with_pos(ErrorKind::InvalidIntegerString))
@@ -199,17 +396,13 @@ impl<R: BufRead> ReaderState<R> {
                             }
                         }
                         b"real" => {
-                            let s = self.read_content(buffer)?;
+                            let content = self.read_content(buffer, source)?;
+                            let s = content.as_deref().unwrap_or(&self.content);
                             match s.parse() {
                                 Ok(f) => return Ok(ReadResult::Event(Event::Real(f))),
                                 Err(_) => return Err(self.with_pos(ErrorKind::InvalidRealString)),
                             }
                         }
-                        b"string" => {
-                            return Ok(ReadResult::Event(Event::String(
-                                self.read_content(buffer)?.into(),
-                            )))
-                        }
                         b"true" => return Ok(ReadResult::Event(Event::Boolean(true))),
                         b"false" => return Ok(ReadResult::Event(Event::Boolean(false))),
                         _ => return Err(self.with_pos(ErrorKind::UnknownXmlElement)),
@@ -221,6 +414,10 @@ impl<R: BufRead> ReaderState<R> {
                 },
                 XmlEvent::Eof => return Ok(ReadResult::Eof),
                 XmlEvent::Text(text) => {
+                    // Whitespace between elements is almost always ASCII.
+                    if text.iter().all(u8::is_ascii_whitespace) {
+                        continue;
+                    }
                     let decoded = text
                         .decode()
                         .map_err(|err| self.with_pos(ErrorKind::from(err)))?;
@@ -264,6 +461,101 @@ impl<R: BufRead> ReaderState<R> {
     }
 }
 
+impl Base64Decoder {
+    fn new() -> Base64Decoder {
+        Base64Decoder {
+            chunk: [0; BASE64_CHUNK_LEN],
+            chunk_len: 0,
+            data: Vec::new(),
+            padded: false,
+            invalid: false,
+        }
+    }
+
+    fn push(&mut self, text: &[u8]) {
+        for &b in text {
+            if b.is_ascii_whitespace() {
+                continue;
+            }
+            self.chunk[self.chunk_len] = b;
+            self.chunk_len += 1;
+            if self.chunk_len == BASE64_CHUNK_LEN {
+                self.decode_chunk();
+            }
+        }
+    }
+
+    fn decode_chunk(&mut self) {
+        let chunk = &self.chunk[..self.chunk_len];
+        self.chunk_len = 0;
+        if self.invalid || chunk.is_empty() {
+            return;
+        }
+        // Padding is only allowed at the end of the encoded data.
+        self.invalid = self.padded || base64_standard.decode_vec(chunk, &mut self.data).is_err();
+        self.padded = chunk.last() == Some(&b'=');
+    }
+
+    /// Returns the decoded data, or `None` if the text was not valid base64.
+    fn finish(mut self) -> Option<Vec<u8>> {
+        self.decode_chunk();
+        (!self.invalid).then_some(self.data)
+    }
+}
+
+impl<R> LineTracker<R> {
+    /// Returns the one-based line and column of a consumed offset, if its line is still known.
+    fn line_column(&self, offset: u64) -> Option<(u64, u64)> {
+        if offset > self.pos {
+            return None;
+        }
+        let index = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self.line_starts.get(index.checked_sub(1)?)?;
+        Some((self.first_line + index as u64 - 1, offset - line_start + 1))
+    }
+
+    /// Forgets the starts of lines which end before `offset`.
+    fn forget_lines_before(&mut self, offset: u64) {
+        while self
+            .line_starts
+            .get(1)
+            .is_some_and(|&start| start <= offset)
+        {
+            self.line_starts.pop_front();
+            self.first_line += 1;
+        }
+    }
+}
+
+impl<R: BufRead> Read for LineTracker<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead> BufRead for LineTracker<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.reader.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // `fill_buf` returns the already buffered bytes without reading when called again.
+        if let Ok(buf) = self.reader.fill_buf() {
+            let consumed = &buf[..amt.min(buf.len())];
+            let newlines = consumed.iter().enumerate().filter(|&(_, &b)| b == b'\n');
+            for (i, _) in newlines {
+                self.line_starts.push_back(self.pos + i as u64 + 1);
+            }
+        }
+        self.pos += amt as u64;
+        self.reader.consume(amt);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{fs::File, io::BufReader};
@@ -314,6 +606,25 @@ mod tests {
         assert_eq!(events.unwrap(), comparison);
     }
 
+    #[test]
+    fn corefoundation_numeric_forms() {
+        let plist = "<plist><array>
+            <integer>0x1F</integer>
+            <integer>-0x1F</integer>
+            <real>nan</real>
+            <real>+infinity</real>
+            <real>-infinity</real>
+        </array></plist>";
+        let streaming_parser = XmlReader::new(plist.as_bytes());
+        let events: Vec<_> = streaming_parser.map(|e| e.unwrap()).collect();
+
+        assert_eq!(events[1], Integer(31.into()));
+        assert_eq!(events[2], Integer((-31).into()));
+        assert!(matches!(events[3], Real(v) if v.is_nan()));
+        assert_eq!(events[4], Real(f64::INFINITY));
+        assert_eq!(events[5], Real(f64::NEG_INFINITY));
+    }
+
     #[test]
     fn bad_data() {
         let reader = File::open("./tests/data/xml_error.plist").unwrap();
@@ -331,9 +642,146 @@ mod tests {
         let event = events.last().unwrap();
 
         assert!(event.is_err());
+        let err = event.as_ref().unwrap_err();
+        assert_eq!(err.category(), crate::ErrorCategory::Syntax);
+        assert_eq!(err.byte_offset(), Some(174));
+        assert_eq!(err.line(), Some(4));
+        assert_eq!(err.column(), Some(11));
         assert_eq!(
             event.as_ref().unwrap_err().to_string(),
-            "UnexpectedXmlCharactersExpectedElement (offset 174)".to_string()
+            "UnexpectedXmlCharactersExpectedElement (offset 174, line 4, column 11)".to_string()
+        );
+    }
+
+    #[test]
+    fn borrowed_strings() {
+        let plist = "<plist><array>
+            <string>plain</string>
+            <string>a &amp; b</string>
+            <string>split<!-- comment -->text</string>
+            <string></string>
+            <integer>7</integer>
+        </array></plist>";
+        let mut reader = XmlReader::new(Cursor::new(plist.as_bytes()));
+        let events: Vec<_> = std::iter::from_fn(|| reader.next_borrowed())
+            .map(|e| e.unwrap())
+            .collect();
+
+        assert!(matches!(&events[1], String(Cow::Borrowed("plain"))));
+        assert!(matches!(&events[2], String(Cow::Owned(s)) if s == "a & b"));
+        assert!(matches!(&events[3], String(Cow::Owned(s)) if s == "splittext"));
+        assert!(matches!(&events[4], String(Cow::Borrowed(""))));
+        assert_eq!(events[5], Integer(7.into()));
+    }
+
+    #[test]
+    fn buffer_reused() {
+        let plist = format!(
+            "<plist><array>{}</array></plist>",
+            "<string>abc</string>".repeat(1000)
+        );
+        let mut reader = XmlReader::new(plist.as_bytes());
+
+        assert_eq!(reader.by_ref().count(), 1002);
+        assert!(reader.buffer.capacity() < 64);
+    }
+
+    #[test]
+    fn data_decoded_in_chunks() {
+        let data: Vec<u8> = (0..=255).cycle().take(5000).collect();
+        let encoded = base64_standard.encode(&data);
+        let lines: Vec<_> = encoded.as_bytes().chunks(76).collect();
+        let plist = format!(
+            "<plist><data>\n{}\n</data></plist>",
+            std::string::String::from_utf8(lines.join(&b"\n\t"[..])).unwrap()
         );
+        let mut reader = XmlReader::new(plist.as_bytes());
+
+        assert_eq!(reader.next().unwrap().unwrap(), Data(data.into()));
+        assert!(reader.next().is_none());
+
+        // Padding followed by more data is invalid even when it ends a chunk.
+        let mut encoded = "A".repeat(BASE64_CHUNK_LEN - 4);
+        encoded.push_str("AA==AAAA");
+        let plist = format!("<plist><data>{encoded}</data></plist>");
+        let err = XmlReader::new(plist.as_bytes())
+            .next()
+            .unwrap()
+            .unwrap_err();
+        assert!(err.to_string().starts_with("InvalidDataString"));
+    }
+
+    #[test]
+    fn line_column_of_earlier_lines() {
+        let mut tracker = LineTracker {
+            reader: "ab\ncd\n\nef".as_bytes(),
+            pos: 0,
+            line_starts: VecDeque::from([0]),
+            first_line: 1,
+        };
+        tracker.consume(9);
+
+        assert_eq!(tracker.line_column(1), Some((1, 2)));
+        assert_eq!(tracker.line_column(3), Some((2, 1)));
+        assert_eq!(tracker.line_column(6), Some((3, 1)));
+        assert_eq!(tracker.line_column(8), Some((4, 2)));
+        assert_eq!(tracker.line_column(10), None);
+
+        tracker.forget_lines_before(4);
+        assert_eq!(tracker.line_column(1), None);
+        assert_eq!(tracker.line_column(4), Some((2, 2)));
+        assert_eq!(tracker.line_column(9), Some((4, 3)));
+    }
+
+    #[test]
+    fn unclosed_element_context() {
+        let plist = "<plist>\n<dict>\n\t<key>a</key>\n\t<string>b</strin>\n</dict>\n</plist>";
+        let streaming_parser = XmlReader::new(plist.as_bytes());
+        let err = streaming_parser.last().unwrap().unwrap_err();
+
+        assert_eq!(
+            err.display_with_source(plist).to_string(),
+            "error: InvalidXmlSyntax
+ --> line 4, column 19
+  |
+4 | \t<string>b</strin>
+  | \t                 ^
+  = note: expected </string> to close element opened at line 4
+"
+        );
+
+        let plist = "<plist>\n<array>\n  <string>b";
+        let streaming_parser = XmlReader::new(plist.as_bytes());
+        let err = streaming_parser.last().unwrap().unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "UnclosedXmlElement (offset 27, line 3, column 12): expected </string> to close element opened at line 3"
+        );
+
+        let plist = "<plist>\n<array>\n  <string>b</string>\n";
+        let streaming_parser = XmlReader::new(plist.as_bytes());
+        let err = streaming_parser.last().unwrap().unwrap_err();
+
+        assert!(err
+            .to_string()
+            .ends_with("expected </array> to close element opened at line 2"));
+
+        // Element names share one buffer, which must be restored as nested elements close.
+        let plist = "<plist>\n<array>\n<dict><key>a</key><integer>1</integer></dict>\n<string>b";
+        let streaming_parser = XmlReader::new(plist.as_bytes());
+        let err = streaming_parser.last().unwrap().unwrap_err();
+
+        assert!(err
+            .to_string()
+            .ends_with("expected </string> to close element opened at line 4"));
+
+        let plist = "<plist>\n<array>\n<dict><key>a</key><integer>1</integer></dict>\n";
+        let streaming_parser = XmlReader::new(plist.as_bytes());
+        let err = streaming_parser.last().unwrap().unwrap_err();
+
+        assert!(err
+            .to_string()
+            .ends_with("expected </array> to close element opened at line 2"));
     }
 }
@@ -10,17 +10,15 @@ use std::{
 
 use crate::{
     error::{self, from_io_without_position, Error, ErrorKind, EventKind},
-    stream::{Writer, XmlWriteOptions},
+    stream::{Canonicalizer, Event, InvalidXmlCharPolicy, Writer, XmlWriteOptions},
     Date, Integer, Uid,
 };
 
-const DATA_MAX_LINE_CHARS: usize = 68;
-const DATA_MAX_LINE_BYTES: usize = 51;
+pub(crate) const DATA_MAX_LINE_CHARS: usize = 68;
 
-static XML_PROLOGUE: &[u8] = br#"<?xml version="1.0" encoding="UTF-8"?>
-<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
-<plist version="1.0">
-"#;
+static XML_DECLARATION: &[u8] = br#"<?xml version="1.0" encoding="UTF-8"?>"#;
+pub(crate) static PLIST_DOCTYPE: &str = r#"<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">"#;
+static PLIST_START: &[u8] = br#"<plist version="1.0">"#;
 
 #[derive(PartialEq)]
 enum Element {
@@ -33,10 +31,17 @@ pub struct XmlWriter<W: Write> {
     write_root_element: bool,
     indent_char: u8,
     indent_count: usize,
+    xml_declaration: bool,
+    doctype: Option<Cow<'static, str>>,
+    self_closing_empty_collections: bool,
+    data_line_chars: usize,
+    trailing_newline: bool,
+    invalid_chars: InvalidXmlCharPolicy,
     started_plist: bool,
     stack: Vec<Element>,
     expecting_key: bool,
     pending_collection: Option<PendingCollection>,
+    canonical: Option<Canonicalizer>,
 }
 
 enum PendingCollection {
@@ -63,10 +68,21 @@ impl<W: Write> XmlWriter<W> {
             write_root_element: opts.root_element,
             indent_char: opts.indent_char,
             indent_count: opts.indent_count,
+            xml_declaration: opts.xml_declaration,
+            doctype: opts.doctype.clone(),
+            self_closing_empty_collections: opts.self_closing_empty_collections,
+            // Base64 encodes every three bytes as four characters.
+            data_line_chars: match opts.data_line_len {
+                0 => 0,
+                len => (len - len % 4).max(4),
+            },
+            trailing_newline: opts.trailing_newline,
+            invalid_chars: opts.invalid_chars,
             started_plist: false,
             stack: Vec::new(),
             expecting_key: false,
             pending_collection: None,
+            canonical: opts.canonical.clone().map(Canonicalizer::new),
         }
     }
 
@@ -75,6 +91,18 @@ impl<W: Write> XmlWriter<W> {
         self.xml_writer.into_inner()
     }
 
+    /// Buffers `event` when writing in canonical form, writing the plist once it is complete.
+    fn write_canonical(&mut self, event: Event) -> Result<(), Error> {
+        let mut canonical = self.canonical.take().expect("writing in canonical form");
+        let result = match canonical.push(event) {
+            Ok(Some(value)) => canonical.write_value(&value, self),
+            Ok(None) => Ok(()),
+            Err(err) => Err(err),
+        };
+        self.canonical = Some(canonical);
+        result
+    }
+
     fn write_element_and_value(&mut self, name: &str, value: &str) -> Result<(), Error> {
         self.xml_writer
             .create_element(name)
@@ -103,9 +131,7 @@ impl<W: Write> XmlWriter<W> {
     ) -> Result<(), Error> {
         if !self.started_plist {
             if self.write_root_element {
-                self.xml_writer
-                    .get_mut()
-                    .write_all(XML_PROLOGUE)
+                self.write_prologue()
                     .map_err(error::from_io_without_position)?;
             }
 
@@ -125,6 +151,13 @@ impl<W: Write> XmlWriter<W> {
                     .map_err(error::from_io_without_position)?;
             }
 
+            if self.trailing_newline {
+                self.xml_writer
+                    .get_mut()
+                    .write_all(b"\n")
+                    .map_err(error::from_io_without_position)?;
+            }
+
             self.xml_writer
                 .get_mut()
                 .flush()
@@ -134,6 +167,35 @@ impl<W: Write> XmlWriter<W> {
         Ok(())
     }
 
+    fn write_prologue(&mut self) -> io::Result<()> {
+        let writer = self.xml_writer.get_mut();
+        if self.xml_declaration {
+            writer.write_all(XML_DECLARATION)?;
+            writer.write_all(b"\n")?;
+        }
+        if let Some(doctype) = &self.doctype {
+            writer.write_all(doctype.as_bytes())?;
+            writer.write_all(b"\n")?;
+        }
+        writer.write_all(PLIST_START)?;
+        writer.write_all(b"\n")
+    }
+
+    fn write_empty_collection(&mut self, name: &str) -> Result<(), Error> {
+        if self.self_closing_empty_collections {
+            self.xml_writer
+                .write_event(XmlEvent::Empty(BytesStart::new(name)))
+                .map_err(from_io_without_position)?;
+        } else {
+            self.xml_writer
+                .create_element(name)
+                .write_text_content(BytesText::new(""))
+                .map_err(from_io_without_position)?;
+        }
+        self.expecting_key = self.stack.last() == Some(&Element::Dictionary);
+        Ok(())
+    }
+
     fn write_value_event<F: FnOnce(&mut Self) -> Result<(), Error>>(
         &mut self,
         event_kind: EventKind,
@@ -179,35 +241,32 @@ impl<W: Write> XmlWriter<W> {
 }
 
 impl<W: Write> Writer for XmlWriter<W> {
-    fn write_start_array(&mut self, _len: Option<u64>) -> Result<(), Error> {
+    fn write_start_array(&mut self, len: Option<u64>) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(Event::StartArray(len));
+        }
         self.handle_pending_collection()?;
         self.pending_collection = Some(PendingCollection::Array);
         Ok(())
     }
 
-    fn write_start_dictionary(&mut self, _len: Option<u64>) -> Result<(), Error> {
+    fn write_start_dictionary(&mut self, len: Option<u64>) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(Event::StartDictionary(len));
+        }
         self.handle_pending_collection()?;
         self.pending_collection = Some(PendingCollection::Dictionary);
         Ok(())
     }
 
     fn write_end_collection(&mut self) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(Event::EndCollection);
+        }
         self.write_event(|this| {
             match this.pending_collection.take() {
-                Some(PendingCollection::Array) => {
-                    this.xml_writer
-                        .write_event(XmlEvent::Empty(BytesStart::new("array")))
-                        .map_err(from_io_without_position)?;
-                    this.expecting_key = this.stack.last() == Some(&Element::Dictionary);
-                    return Ok(());
-                }
-                Some(PendingCollection::Dictionary) => {
-                    this.xml_writer
-                        .write_event(XmlEvent::Empty(BytesStart::new("dict")))
-                        .map_err(from_io_without_position)?;
-                    this.expecting_key = this.stack.last() == Some(&Element::Dictionary);
-                    return Ok(());
-                }
+                Some(PendingCollection::Array) => return this.write_empty_collection("array"),
+                Some(PendingCollection::Dictionary) => return this.write_empty_collection("dict"),
                 _ => {}
             }
             match (this.stack.pop(), this.expecting_key) {
@@ -231,6 +290,9 @@ impl<W: Write> Writer for XmlWriter<W> {
     }
 
     fn write_boolean(&mut self, value: bool) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(Event::Boolean(value));
+        }
         self.write_value_event(EventKind::Boolean, |this| {
             let value = if value { "true" } else { "false" };
             this.xml_writer
@@ -241,12 +303,19 @@ impl<W: Write> Writer for XmlWriter<W> {
     }
 
     fn write_data(&mut self, value: Cow<[u8]>) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(Event::Data(value));
+        }
         self.write_value_event(EventKind::Data, |this| {
+            if this.data_line_chars == 0 {
+                return this.write_element_and_value("data", &BASE64_STANDARD.encode(&value));
+            }
             this.xml_writer
                 .create_element("data")
                 .write_inner_content(|xml_writer| {
                     write_data_base64(
                         &value,
+                        this.data_line_chars,
                         true,
                         this.indent_char,
                         this.stack.len() * this.indent_count,
@@ -259,25 +328,60 @@ impl<W: Write> Writer for XmlWriter<W> {
     }
 
     fn write_date(&mut self, value: Date) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(Event::Date(value));
+        }
         self.write_value_event(EventKind::Date, |this| {
             this.write_element_and_value("date", &value.to_xml_format())
         })
     }
 
     fn write_integer(&mut self, value: Integer) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(Event::Integer(value));
+        }
         self.write_value_event(EventKind::Integer, |this| {
             this.write_element_and_value("integer", &value.to_string())
         })
     }
 
     fn write_real(&mut self, value: f64) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(Event::Real(value));
+        }
         self.write_value_event(EventKind::Real, |this| {
-            this.write_element_and_value("real", &value.to_string())
+            this.write_element_and_value("real", &real_to_xml_format(value))
         })
     }
 
-    fn write_string(&mut self, value: Cow<str>) -> Result<(), Error> {
+    fn write_string(&mut self, mut value: Cow<str>) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(Event::String(value));
+        }
         self.handle_pending_collection()?;
+        if value.chars().any(is_invalid_xml_char) {
+            match self.invalid_chars {
+                InvalidXmlCharPolicy::Replace(replacement) => {
+                    value = value
+                        .chars()
+                        .map(|c| {
+                            if is_invalid_xml_char(c) {
+                                replacement
+                            } else {
+                                c
+                            }
+                        })
+                        .collect::<String>()
+                        .into();
+                }
+                InvalidXmlCharPolicy::WriteAsData if !self.expecting_key => {
+                    return self.write_data(Cow::Owned(value.into_owned().into_bytes()));
+                }
+                InvalidXmlCharPolicy::Error | InvalidXmlCharPolicy::WriteAsData => {
+                    return Err(ErrorKind::InvalidXmlCharacter.without_position());
+                }
+            }
+        }
         self.write_event(|this| {
             if this.expecting_key {
                 this.write_element_and_value("key", &value)?;
@@ -290,7 +394,10 @@ impl<W: Write> Writer for XmlWriter<W> {
         })
     }
 
-    fn write_uid(&mut self, _value: Uid) -> Result<(), Error> {
+    fn write_uid(&mut self, value: Uid) -> Result<(), Error> {
+        if self.canonical.is_some() {
+            return self.write_canonical(Event::Uid(value));
+        }
         Err(ErrorKind::UidNotSupportedInXmlPlist.without_position())
     }
 }
@@ -308,19 +415,39 @@ impl From<XmlWriterError> for Error {
     }
 }
 
+/// Returns true if `c` is not allowed in an XML 1.0 document.
+pub(crate) fn is_invalid_xml_char(c: char) -> bool {
+    matches!(c, '\u{0}'..='\u{8}' | '\u{b}' | '\u{c}' | '\u{e}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}')
+}
+
+/// Formats a real using the spellings CoreFoundation uses for non-finite values.
+fn real_to_xml_format(value: f64) -> Cow<'static, str> {
+    if value.is_nan() {
+        Cow::Borrowed("nan")
+    } else if value == f64::INFINITY {
+        Cow::Borrowed("+infinity")
+    } else if value == f64::NEG_INFINITY {
+        Cow::Borrowed("-infinity")
+    } else {
+        Cow::Owned(value.to_string())
+    }
+}
+
 #[cfg(feature = "serde")]
 pub(crate) fn encode_data_base64(data: &[u8]) -> String {
     // Pre-allocate space for the base64 encoded data.
-    let num_lines = (data.len() + DATA_MAX_LINE_BYTES - 1) / DATA_MAX_LINE_BYTES;
+    let num_lines = data.len().div_ceil(DATA_MAX_LINE_CHARS / 4 * 3);
     let max_len = num_lines * (DATA_MAX_LINE_CHARS + 1);
 
     let mut base64 = Vec::with_capacity(max_len);
-    write_data_base64(data, false, b'\t', 0, &mut base64).expect("writing to a vec cannot fail");
+    write_data_base64(data, DATA_MAX_LINE_CHARS, false, b'\t', 0, &mut base64)
+        .expect("writing to a vec cannot fail");
     String::from_utf8(base64).expect("encoded base64 is ascii")
 }
 
 fn write_data_base64(
     data: &[u8],
+    line_chars: usize,
     write_initial_newline: bool,
     indent_char: u8,
     indent_repeat: usize,
@@ -330,8 +457,8 @@ fn write_data_base64(
     // <data>
     // AAAA..AA (68 characters per line)
     // </data>
-    let mut encoded = [0; DATA_MAX_LINE_CHARS];
-    for (i, line) in data.chunks(DATA_MAX_LINE_BYTES).enumerate() {
+    let mut encoded = vec![0; line_chars];
+    for (i, line) in data.chunks(line_chars / 4 * 3).enumerate() {
         // Write newline
         if write_initial_newline || i > 0 {
             writer.write_all(b"\n")?;
@@ -477,6 +604,157 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn non_finite_reals() {
+        let plist = [
+            Event::StartArray(None),
+            Event::Real(f64::NAN),
+            Event::Real(f64::INFINITY),
+            Event::Real(f64::NEG_INFINITY),
+            Event::Real(-0.5),
+            Event::EndCollection,
+        ];
+
+        let expected = "<array>
+\t<real>nan</real>
+\t<real>+infinity</real>
+\t<real>-infinity</real>
+\t<real>-0.5</real>
+</array>";
+
+        let actual = events_to_xml(plist, XmlWriteOptions::default().root_element(false));
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn custom_prologue() {
+        let plist = [Event::StartDictionary(None), Event::EndCollection];
+
+        let expected =
+            "<!DOCTYPE plist SYSTEM \"file://localhost/System/Library/DTDs/PropertyList.dtd\">
+<plist version=\"1.0\">
+<dict></dict>
+</plist>
+";
+
+        let options = XmlWriteOptions::default()
+            .xml_declaration(false)
+            .custom_doctype(
+                "<!DOCTYPE plist SYSTEM \"file://localhost/System/Library/DTDs/PropertyList.dtd\">",
+            )
+            .self_closing_empty_collections(false)
+            .trailing_newline(true);
+        let actual = events_to_xml(plist.clone(), options);
+
+        assert_eq!(actual, expected);
+
+        let expected = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<plist version=\"1.0\">
+<dict/>
+</plist>";
+
+        let actual = events_to_xml(plist, XmlWriteOptions::default().doctype(false));
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn data_line_len() {
+        let plist = [
+            Event::StartArray(None),
+            Event::Data((0..16).collect::<Vec<_>>().into()),
+            Event::StartArray(None),
+            Event::EndCollection,
+            Event::EndCollection,
+        ];
+
+        let expected = "<array>
+\t<data>
+\tAAECAwQFBgcI
+\tCQoLDA0ODw==
+\t</data>
+\t<array></array>
+</array>";
+
+        let options = XmlWriteOptions::default()
+            .root_element(false)
+            .data_line_len(15)
+            .self_closing_empty_collections(false);
+        let actual = events_to_xml(plist.clone(), options);
+
+        assert_eq!(actual, expected);
+
+        let expected = "<array>
+\t<data>AAECAwQFBgcICQoLDA0ODw==</data>
+\t<array/>
+</array>";
+
+        let options = XmlWriteOptions::default()
+            .root_element(false)
+            .data_line_len(0);
+        let actual = events_to_xml(plist, options);
+
+        assert_eq!(actual, expected);
+
+        let plist = [Event::Data(vec![0; 6].into())];
+        let expected = "<data>
+AAAA
+AAAA
+</data>";
+
+        let options = XmlWriteOptions::default()
+            .root_element(false)
+            .data_line_len(1);
+        let actual = events_to_xml(plist, options);
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn invalid_chars() {
+        let plist = [
+            Event::StartDictionary(None),
+            Event::String("Name".into()),
+            Event::String("a\u{1}b\tc".into()),
+            Event::EndCollection,
+        ];
+
+        let actual = events_to_xml(
+            plist.clone(),
+            XmlWriteOptions::default()
+                .root_element(false)
+                .invalid_char_policy(InvalidXmlCharPolicy::Replace('?')),
+        );
+        assert_eq!(
+            actual,
+            "<dict>\n\t<key>Name</key>\n\t<string>a?b\tc</string>\n</dict>"
+        );
+
+        let actual = events_to_xml(
+            plist.clone(),
+            XmlWriteOptions::default()
+                .root_element(false)
+                .invalid_char_policy(InvalidXmlCharPolicy::WriteAsData),
+        );
+        assert_eq!(
+            actual,
+            "<dict>\n\t<key>Name</key>\n\t<data>\n\tYQFiCWM=\n\t</data>\n</dict>"
+        );
+
+        let mut writer = XmlWriter::new_with_options(Vec::new(), &XmlWriteOptions::default());
+        let results: Vec<_> = plist.into_iter().map(|e| writer.write(e)).collect();
+        assert!(results[..2].iter().all(Result::is_ok));
+        assert!(results[2].is_err());
+
+        let mut writer = XmlWriter::new_with_options(
+            Vec::new(),
+            &XmlWriteOptions::default().invalid_char_policy(InvalidXmlCharPolicy::WriteAsData),
+        );
+        writer.write(Event::StartDictionary(None)).unwrap();
+        assert!(writer.write(Event::String("\u{ffff}".into())).is_err());
+    }
+
     fn events_to_xml<'event>(
         events: impl IntoIterator<Item = Event<'event>>,
         options: XmlWriteOptions,
@@ -0,0 +1,222 @@
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+
+use crate::{Dictionary, Value};
+
+/// A wrapper which implements `Eq`, `Ord` and `Hash` for a [`Value`] or [`Dictionary`], or a
+/// reference to one.
+///
+/// Values are compared as described by [`Value::total_cmp`]. Unlike the `PartialEq`
+/// implementation of `Value`, reals are compared by their bit patterns so a `NaN` is equal to
+/// itself and `0.0` is not equal to `-0.0`.
+///
+/// ```
+/// use std::collections::BTreeSet;
+/// use plist::{TotalOrd, Value};
+///
+/// let entitlements = [
+///     Value::from("com.apple.developer.icloud-services"),
+///     Value::from(f64::NAN),
+///     Value::from("com.apple.developer.icloud-services"),
+///     Value::from(f64::NAN),
+/// ];
+///
+/// let unique: BTreeSet<_> = entitlements.iter().map(TotalOrd).collect();
+/// assert_eq!(unique.len(), 2);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TotalOrd<T>(pub T);
+
+macro_rules! impl_total_ord {
+    ($ty:ty, $cmp:ident, $hash:ident) => {
+        impl PartialEq for TotalOrd<$ty> {
+            fn eq(&self, other: &Self) -> bool {
+                $cmp(&self.0, &other.0) == Ordering::Equal
+            }
+        }
+
+        impl Eq for TotalOrd<$ty> {}
+
+        impl PartialOrd for TotalOrd<$ty> {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for TotalOrd<$ty> {
+            fn cmp(&self, other: &Self) -> Ordering {
+                $cmp(&self.0, &other.0)
+            }
+        }
+
+        impl Hash for TotalOrd<$ty> {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                $hash(&self.0, state);
+            }
+        }
+    };
+}
+
+impl_total_ord!(Value, cmp_values, hash_value);
+impl_total_ord!(&Value, cmp_values, hash_value);
+impl_total_ord!(Dictionary, cmp_dictionaries, hash_dictionary);
+impl_total_ord!(&Dictionary, cmp_dictionaries, hash_dictionary);
+
+/// The position of each kind of value in the total order.
+fn rank(value: &Value) -> u8 {
+    match value {
+        Value::Array(_) => 0,
+        Value::Dictionary(_) => 1,
+        Value::Boolean(_) => 2,
+        Value::Data(_) => 3,
+        Value::Date(_) => 4,
+        Value::Real(_) => 5,
+        Value::Integer(_) => 6,
+        Value::String(_) => 7,
+        Value::Uid(_) => 8,
+    }
+}
+
+pub(crate) fn cmp_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Array(a), Value::Array(b)) => {
+            for (a, b) in a.iter().zip(b) {
+                match cmp_values(a, b) {
+                    Ordering::Equal => (),
+                    ordering => return ordering,
+                }
+            }
+            a.len().cmp(&b.len())
+        }
+        (Value::Dictionary(a), Value::Dictionary(b)) => cmp_dictionaries(a, b),
+        (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+        (Value::Data(a), Value::Data(b)) => a.cmp(b),
+        (Value::Date(a), Value::Date(b)) => a.cmp(b),
+        (Value::Real(a), Value::Real(b)) => a.total_cmp(b),
+        (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Uid(a), Value::Uid(b)) => a.cmp(b),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// Returns the entries of a dictionary sorted by key.
+///
+/// Dictionary equality does not depend on the order of keys so neither can the total order.
+fn sorted_entries(dict: &Dictionary) -> Vec<(&String, &Value)> {
+    let mut entries: Vec<_> = dict.iter().collect();
+    entries.sort_unstable_by_key(|&(key, _)| key);
+    entries
+}
+
+pub(crate) fn cmp_dictionaries(a: &Dictionary, b: &Dictionary) -> Ordering {
+    let (a, b) = (sorted_entries(a), sorted_entries(b));
+    for ((a_key, a_value), (b_key, b_value)) in a.iter().zip(&b) {
+        match a_key.cmp(b_key).then_with(|| cmp_values(a_value, b_value)) {
+            Ordering::Equal => (),
+            ordering => return ordering,
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+pub(crate) fn hash_value<H: Hasher>(value: &Value, state: &mut H) {
+    rank(value).hash(state);
+    match value {
+        Value::Array(array) => {
+            array.len().hash(state);
+            for value in array {
+                hash_value(value, state);
+            }
+        }
+        Value::Dictionary(dict) => hash_dictionary(dict, state),
+        Value::Boolean(b) => b.hash(state),
+        Value::Data(data) => data.hash(state),
+        Value::Date(date) => date.hash(state),
+        Value::Real(real) => real.to_bits().hash(state),
+        Value::Integer(integer) => integer.hash(state),
+        Value::String(string) => string.hash(state),
+        Value::Uid(uid) => uid.hash(state),
+    }
+}
+
+pub(crate) fn hash_dictionary<H: Hasher>(dict: &Dictionary, state: &mut H) {
+    dict.len().hash(state);
+    for (key, value) in sorted_entries(dict) {
+        key.hash(state);
+        hash_value(value, state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{hash_map::DefaultHasher, HashSet};
+
+    use super::*;
+    use crate::{Date, Uid};
+
+    fn hash<T: Hash>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn total_order() {
+        let mut dict = Dictionary::new();
+        dict.insert("a".to_owned(), 1.into());
+        let values = [
+            Value::Array(vec![]),
+            Value::Array(vec![1.into()]),
+            Value::Array(vec![1.into(), 1.into()]),
+            Value::Array(vec![2.into()]),
+            Value::Dictionary(Dictionary::new()),
+            Value::Dictionary(dict),
+            Value::Boolean(false),
+            Value::Boolean(true),
+            Value::Data(vec![0]),
+            Value::Date(Date::from_xml_format("2000-01-01T00:00:00Z").unwrap()),
+            Value::Real(f64::NEG_INFINITY),
+            Value::Real(-0.0),
+            Value::Real(0.0),
+            Value::Real(f64::NAN),
+            Value::Integer((-1).into()),
+            Value::Integer(u64::MAX.into()),
+            Value::String("a".to_owned()),
+            Value::Uid(Uid::new(1)),
+        ];
+
+        for (i, a) in values.iter().enumerate() {
+            for (j, b) in values.iter().enumerate() {
+                assert_eq!(a.total_cmp(b), i.cmp(&j), "{a:?} {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn dictionary_key_order_is_ignored() {
+        let mut a = Dictionary::new();
+        a.insert("x".to_owned(), f64::NAN.into());
+        a.insert("y".to_owned(), vec![Value::from(1)].into());
+        let mut b = Dictionary::new();
+        b.insert("y".to_owned(), vec![Value::from(1)].into());
+        b.insert("x".to_owned(), f64::NAN.into());
+
+        assert_eq!(TotalOrd(&a), TotalOrd(&b));
+        assert_eq!(hash(&TotalOrd(&a)), hash(&TotalOrd(&b)));
+
+        let a = Value::from(a);
+        let b = Value::from(b);
+        assert_ne!(a, b);
+        assert_eq!(TotalOrd(&a), TotalOrd(&b));
+        assert_eq!(hash(&TotalOrd(&a)), hash(&TotalOrd(&b)));
+
+        let set: HashSet<_> = [a, b, Value::from(0.0), Value::from(-0.0)]
+            .into_iter()
+            .map(TotalOrd)
+            .collect();
+        assert_eq!(set.len(), 3);
+    }
+}
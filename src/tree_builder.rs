@@ -0,0 +1,154 @@
+//! The stack logic shared by everything which builds a tree of values from a stream of events.
+
+use crate::{
+    error::{Error, ErrorKind, EventKind},
+    stream::Event,
+};
+
+/// The nodes and collections of a tree built by a [`TreeBuilder`].
+pub(crate) trait Tree<'event> {
+    /// A complete value.
+    type Node;
+    /// An array which has been started but not yet ended.
+    type Array;
+    /// A dictionary which has been started but not yet ended.
+    type Dictionary;
+    type Key;
+
+    fn start_array(&mut self, len: Option<u64>) -> Self::Array;
+    fn start_dictionary(&mut self, len: Option<u64>) -> Self::Dictionary;
+    fn push(&mut self, array: &mut Self::Array, node: Self::Node);
+    fn insert(&mut self, dict: &mut Self::Dictionary, key: Self::Key, node: Self::Node);
+    fn end_array(&mut self, array: Self::Array) -> Self::Node;
+    fn end_dictionary(&mut self, dict: Self::Dictionary) -> Self::Node;
+
+    /// Converts an event which is not the start or end of a collection into a node.
+    fn leaf(&mut self, event: Event<'event>) -> Self::Node;
+
+    /// Converts a node into a dictionary key, returning the node if it is not a string.
+    fn key(&mut self, node: Self::Node) -> Result<Self::Key, Self::Node>;
+
+    fn kind(&self, node: &Self::Node) -> EventKind;
+}
+
+enum Open<'event, T: Tree<'event>> {
+    Array(T::Array),
+    Dictionary(T::Dictionary, Option<T::Key>),
+}
+
+/// Builds a tree from events, keeping track of the collections which are still open.
+pub(crate) struct TreeBuilder<'event, T: Tree<'event>> {
+    tree: T,
+    stack: Vec<Open<'event, T>>,
+    root: Option<T::Node>,
+}
+
+impl<'event, T: Tree<'event>> TreeBuilder<'event, T> {
+    pub(crate) fn new(tree: T) -> Self {
+        TreeBuilder {
+            tree,
+            stack: Vec::new(),
+            root: None,
+        }
+    }
+
+    /// Builds a tree from an entire event stream, returning the tree and its root node.
+    pub(crate) fn build<I>(tree: T, events: I) -> Result<(T, T::Node), Error>
+    where
+        I: IntoIterator<Item = Result<Event<'event>, Error>>,
+    {
+        let mut builder = TreeBuilder::new(tree);
+        for event in events {
+            builder.write_event(event?)?;
+        }
+        let root = builder.finish()?;
+        Ok((builder.tree, root))
+    }
+
+    pub(crate) fn write_event(&mut self, event: Event<'event>) -> Result<(), Error> {
+        match event {
+            Event::StartArray(len) => self.start_array(len),
+            Event::StartDictionary(len) => self.start_dictionary(len),
+            Event::EndCollection => self.end_collection(),
+            event => {
+                self.check_not_complete(EventKind::of_event(&event))?;
+                let node = self.tree.leaf(event);
+                self.write_node(node)
+            }
+        }
+    }
+
+    pub(crate) fn start_array(&mut self, len: Option<u64>) -> Result<(), Error> {
+        self.check_not_complete(EventKind::StartArray)?;
+        let array = self.tree.start_array(len);
+        self.stack.push(Open::Array(array));
+        Ok(())
+    }
+
+    pub(crate) fn start_dictionary(&mut self, len: Option<u64>) -> Result<(), Error> {
+        self.check_not_complete(EventKind::StartDictionary)?;
+        let dict = self.tree.start_dictionary(len);
+        self.stack.push(Open::Dictionary(dict, None));
+        Ok(())
+    }
+
+    pub(crate) fn end_collection(&mut self) -> Result<(), Error> {
+        self.check_not_complete(EventKind::EndCollection)?;
+        let node = match self.stack.pop() {
+            Some(Open::Array(array)) => self.tree.end_array(array),
+            Some(Open::Dictionary(dict, None)) => self.tree.end_dictionary(dict),
+            Some(Open::Dictionary(_, Some(_))) | None => {
+                return Err(ErrorKind::UnexpectedEventType {
+                    expected: EventKind::ValueOrStartCollection,
+                    found: EventKind::EndCollection,
+                }
+                .without_position())
+            }
+        };
+        self.write_node(node)
+    }
+
+    /// Adds a complete node to the innermost open collection, or makes it the root node.
+    pub(crate) fn write_node(&mut self, node: T::Node) -> Result<(), Error> {
+        match self.stack.last_mut() {
+            None => {
+                self.check_not_complete(self.tree.kind(&node))?;
+                self.root = Some(node);
+            }
+            Some(Open::Array(array)) => self.tree.push(array, node),
+            Some(Open::Dictionary(dict, key)) => match key.take() {
+                Some(key) => self.tree.insert(dict, key, node),
+                None => match self.tree.key(node) {
+                    Ok(new_key) => *key = Some(new_key),
+                    Err(node) => {
+                        return Err(ErrorKind::UnexpectedEventType {
+                            expected: EventKind::DictionaryKeyOrEndCollection,
+                            found: self.tree.kind(&node),
+                        }
+                        .without_position())
+                    }
+                },
+            },
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if a complete root node has been written.
+    pub(crate) fn is_complete(&self) -> bool {
+        self.root.is_some()
+    }
+
+    /// Takes the root node, or returns an error if the root node is not complete.
+    pub(crate) fn finish(&mut self) -> Result<T::Node, Error> {
+        self.root
+            .take()
+            .ok_or_else(|| ErrorKind::UnexpectedEndOfEventStream.without_position())
+    }
+
+    fn check_not_complete(&self, found: EventKind) -> Result<(), Error> {
+        if self.root.is_some() {
+            return Err(ErrorKind::ExpectedEndOfEventStream { found }.without_position());
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use std::fmt;
 
 /// A plist `uid` value. These are found exclusively in plists created by `NSKeyedArchiver`.
-#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Uid {
     value: u64,
 }
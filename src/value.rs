@@ -1,17 +1,27 @@
 use std::{
     borrow::Cow,
+    cmp::Ordering,
+    collections::BTreeMap,
     fs::File,
     io::{BufReader, BufWriter, Read, Seek, Write},
+    ops,
     path::Path,
 };
 
 use crate::{
     error::{self, Error, ErrorKind, EventKind},
+    key_path,
+    merge::{self, DeepMergeOptions},
+    query::PathElement,
     stream::{
-        private, AsciiReader, BinaryWriter, Event, Events, Reader, Writer, XmlReader,
-        XmlWriteOptions, XmlWriter,
+        private, AsciiReader, BinaryWriteOptions, BinaryWriter, Event, Events, Reader, Writer,
+        XmlReader, XmlWriteOptions, XmlWriter,
     },
-    u64_to_usize, Date, Dictionary, Integer, Uid,
+    total_ord,
+    tree_builder::{Tree, TreeBuilder},
+    u64_to_usize,
+    visit::{self, Transform},
+    Date, Dictionary, Integer, Uid,
 };
 
 /// Represents any plist value.
@@ -72,8 +82,18 @@ impl Value {
 
     /// Serializes a `Value` to a byte stream as a binary encoded plist.
     pub fn to_writer_binary<W: Write>(&self, writer: W) -> Result<(), Error> {
-        let mut writer = BinaryWriter::new(writer);
-        self.to_writer_inner(&mut writer)
+        self.to_writer_binary_with_options(writer, &BinaryWriteOptions::default())
+    }
+
+    /// Serializes a `Value` to a byte stream as a binary encoded plist, using custom
+    /// [`BinaryWriteOptions`].
+    pub fn to_writer_binary_with_options<W: Write>(
+        &self,
+        writer: W,
+        options: &BinaryWriteOptions,
+    ) -> Result<(), Error> {
+        let mut writer = BinaryWriter::new_with_options(writer, options);
+        writer.write_value_tree(self)
     }
 
     /// Serializes a `Value` to a byte stream as an XML encoded plist.
@@ -233,6 +253,22 @@ impl Value {
         }
     }
 
+    /// Creates a Data value containing `bytes`.
+    ///
+    /// `Value` does not implement `From<Vec<u8>>`. Alongside `From<Vec<Value>>` it would stop the
+    /// compiler inferring the element type of `Value::from(vec![x.into(), ...])`, and would make
+    /// `Value::from(vec![1u8, 2])` data while collecting the same bytes makes an array.
+    ///
+    /// ```
+    /// use plist::Value;
+    ///
+    /// let value = Value::data(vec![1, 2]);
+    /// assert_eq!(value.as_data(), Some(&[1, 2][..]));
+    /// ```
+    pub fn data(bytes: Vec<u8>) -> Value {
+        Value::Data(bytes)
+    }
+
     /// If the `Value` is a Data, returns the underlying `Vec`.
     ///
     /// Returns `None` otherwise.
@@ -341,6 +377,368 @@ impl Value {
             _ => None,
         }
     }
+
+    /// Compares two values using a total order.
+    ///
+    /// Values of different kinds are ordered as the variants of `Value` are declared. Arrays are
+    /// compared element by element and dictionaries entry by entry in order of their keys, so the
+    /// order of keys in a dictionary does not affect the result. Reals are compared with
+    /// [`f64::total_cmp`], which unlike `PartialEq` considers a `NaN` equal to itself and `0.0`
+    /// unequal to `-0.0`.
+    ///
+    /// Use [`TotalOrd`](crate::TotalOrd) to sort, hash or deduplicate values with this order.
+    pub fn total_cmp(&self, other: &Value) -> Ordering {
+        total_ord::cmp_values(self, other)
+    }
+
+    /// Looks up a value by a JSON Pointer.
+    ///
+    /// A JSON Pointer, as defined in [RFC 6901], is a string of reference tokens each prefixed by
+    /// `/`. Each token is either a dictionary key or an array index. The characters `~` and `/`
+    /// in keys are escaped as `~0` and `~1`. The empty string refers to the value itself.
+    ///
+    /// Returns `None` if the pointer is malformed or does not refer to a value.
+    ///
+    /// [RFC 6901]: https://tools.ietf.org/html/rfc6901
+    ///
+    /// ```
+    /// use plist::{Dictionary, Value};
+    ///
+    /// let mut url_type = Dictionary::new();
+    /// url_type.insert("CFBundleURLSchemes".to_owned(), vec![Value::from("myapp")].into());
+    /// let mut info = Dictionary::new();
+    /// info.insert("CFBundleURLTypes".to_owned(), vec![Value::from(url_type)].into());
+    /// let info = Value::from(info);
+    ///
+    /// assert_eq!(
+    ///     info.pointer("/CFBundleURLTypes/0/CFBundleURLSchemes/0"),
+    ///     Some(&Value::from("myapp"))
+    /// );
+    /// assert_eq!(info.pointer("/CFBundleURLTypes/1"), None);
+    /// ```
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        key_path::get(self, &key_path::split_pointer(pointer)?)
+    }
+
+    /// Looks up a value by a JSON Pointer and returns a mutable reference to it.
+    ///
+    /// See [`Value::pointer`] for the pointer syntax.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        key_path::get_mut(self, &key_path::split_pointer(pointer)?)
+    }
+
+    /// Looks up a value by a key path in the dotted syntax used by `plutil`.
+    ///
+    /// Each component of the key path is separated by a `.` and is either a dictionary key or an
+    /// array index. A literal `.` or `\` in a key is escaped with a preceding `\`. The empty key
+    /// path refers to the value itself.
+    ///
+    /// ```
+    /// use plist::{Dictionary, Value};
+    ///
+    /// let mut entitlements = Dictionary::new();
+    /// entitlements.insert("com.apple.security.app-sandbox".to_owned(), true.into());
+    /// let value = Value::from(entitlements);
+    ///
+    /// assert_eq!(
+    ///     value.get_at_path(r"com\.apple\.security\.app-sandbox"),
+    ///     Some(&Value::Boolean(true))
+    /// );
+    /// ```
+    pub fn get_at_path(&self, key_path: &str) -> Option<&Value> {
+        key_path::get(self, &key_path::split(key_path))
+    }
+
+    /// Looks up a value by a key path and returns a mutable reference to it.
+    ///
+    /// See [`Value::get_at_path`] for the key path syntax.
+    pub fn get_at_path_mut(&mut self, key_path: &str) -> Option<&mut Value> {
+        key_path::get_mut(self, &key_path::split(key_path))
+    }
+
+    /// Inserts a value at a key path, returning the value it replaced if there was one.
+    ///
+    /// If the parent of the key path is a dictionary the value is inserted with the final key.
+    /// If it is an array the value is inserted at the final index, shifting all following
+    /// elements. The index may be equal to the length of the array to append the value. The
+    /// empty key path replaces the value itself.
+    ///
+    /// If `create_intermediate` is `true` any dictionaries missing along the key path are
+    /// created. Otherwise, and for missing array elements, an error is returned.
+    ///
+    /// See [`Value::get_at_path`] for the key path syntax.
+    ///
+    /// ```
+    /// use plist::{Dictionary, Value};
+    ///
+    /// let mut value = Value::from(Dictionary::new());
+    /// value
+    ///     .insert_at_path("NSAppTransportSecurity.NSAllowsArbitraryLoads", true, true)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     value.get_at_path("NSAppTransportSecurity.NSAllowsArbitraryLoads"),
+    ///     Some(&Value::Boolean(true))
+    /// );
+    /// assert!(value.insert_at_path("Missing.Key", true, false).is_err());
+    /// ```
+    pub fn insert_at_path<V: Into<Value>>(
+        &mut self,
+        key_path: &str,
+        value: V,
+        create_intermediate: bool,
+    ) -> Result<Option<Value>, Error> {
+        let value = value.into();
+        let components = key_path::split(key_path);
+        let Some((last, parent_components)) = components.split_last() else {
+            return Ok(Some(std::mem::replace(self, value)));
+        };
+
+        let mut parent = self;
+        for component in parent_components {
+            parent = match parent {
+                Value::Dictionary(dict) => {
+                    if create_intermediate && !dict.contains_key(component) {
+                        dict.insert(component.clone(), Dictionary::new().into());
+                    }
+                    dict.get_mut(component)
+                }
+                Value::Array(array) => key_path::index(component).and_then(|i| array.get_mut(i)),
+                _ => None,
+            }
+            .ok_or_else(|| ErrorKind::KeyPathNotFound.without_position())?;
+        }
+
+        match parent {
+            Value::Dictionary(dict) => Ok(dict.insert(last.clone(), value)),
+            Value::Array(array) => match key_path::index(last) {
+                Some(index) if index <= array.len() => {
+                    array.insert(index, value);
+                    Ok(None)
+                }
+                _ => Err(ErrorKind::KeyPathNotFound.without_position()),
+            },
+            _ => Err(ErrorKind::KeyPathNotFound.without_position()),
+        }
+    }
+
+    /// Removes the value at a key path, returning it if it was present.
+    ///
+    /// Removing an array element shifts all following elements. The empty key path refers to the
+    /// value itself and cannot be removed.
+    ///
+    /// See [`Value::get_at_path`] for the key path syntax.
+    pub fn remove_at_path(&mut self, key_path: &str) -> Option<Value> {
+        let components = key_path::split(key_path);
+        let (last, parent_components) = components.split_last()?;
+        match key_path::get_mut(self, parent_components)? {
+            Value::Dictionary(dict) => dict.shift_remove(last),
+            Value::Array(array) => {
+                let index = key_path::index(last).filter(|&index| index < array.len())?;
+                Some(array.remove(index))
+            }
+            _ => None,
+        }
+    }
+
+    /// Merges `patch` into this value, following the semantics of [RFC 7396] (JSON Merge Patch).
+    ///
+    /// If `patch` is a dictionary it is merged into this value with
+    /// [`Dictionary::merge_patch`], first replacing this value with an empty dictionary if it is
+    /// not already one. Otherwise this value is replaced by `patch`.
+    ///
+    /// [RFC 7396]: https://tools.ietf.org/html/rfc7396
+    pub fn merge_patch(&mut self, patch: &Value) {
+        let Value::Dictionary(patch) = patch else {
+            *self = patch.clone();
+            return;
+        };
+        if !matches!(self, Value::Dictionary(_)) {
+            *self = Dictionary::new().into();
+        }
+        if let Value::Dictionary(dict) = self {
+            dict.merge_patch(patch);
+        }
+    }
+
+    /// Calls `f` with this value and each of its descendants in document order, along with
+    /// their location relative to this value.
+    ///
+    /// Use a [`Visit`](crate::visit::Visit) implementation for more control over the traversal.
+    ///
+    /// ```
+    /// use plist::Value;
+    ///
+    /// let value = Value::from(vec![Value::from(1), Value::from(vec![Value::from(2)])]);
+    ///
+    /// let mut integers = 0;
+    /// value.walk(|_path, value| {
+    ///     if value.as_signed_integer().is_some() {
+    ///         integers += 1;
+    ///     }
+    /// });
+    /// assert_eq!(integers, 2);
+    /// ```
+    pub fn walk<'a, F>(&'a self, mut f: F)
+    where
+        F: FnMut(&[PathElement<'a>], &'a Value),
+    {
+        visit::walk(&[], self, &mut f);
+    }
+
+    /// Calls `f` with this value and each of its descendants in document order, along with
+    /// their location relative to this value, replacing or removing values as directed by the
+    /// [`Transform`] returned by `f`.
+    ///
+    /// Values can also be modified in place by `f`. The locations of array elements are their
+    /// indices before any elements were removed.
+    ///
+    /// ```
+    /// use plist::{query::PathElement, visit::Transform, Value};
+    ///
+    /// let mut value = Value::from_reader_xml(
+    ///     "<plist><dict>
+    ///         <key>Name</key><string>App</string>
+    ///         <key>Signature</key><data>AAAA</data>
+    ///         <key>Nested</key><dict><key>Signature</key><data>AAAA</data></dict>
+    ///     </dict></plist>"
+    ///         .as_bytes(),
+    /// )
+    /// .unwrap();
+    ///
+    /// value.transform(|path, _value| match path.last() {
+    ///     Some(PathElement::Key("Signature")) => Transform::Remove,
+    ///     _ => Transform::Continue,
+    /// });
+    /// assert_eq!(value.pointer("/Signature"), None);
+    /// assert_eq!(value.pointer("/Nested/Signature"), None);
+    /// ```
+    pub fn transform<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&[PathElement], &mut Value) -> Transform,
+    {
+        visit::transform(&[], self, &mut f);
+    }
+
+    /// Recursively merges `overlay` into this value.
+    ///
+    /// Dictionaries are merged key by key, keeping the key order of this value and appending
+    /// keys only present in `overlay`. All other values, including arrays, are replaced by the
+    /// overlay value. Use [`Value::deep_merge_with_options`] to choose how values are merged.
+    ///
+    /// ```
+    /// use plist::Value;
+    ///
+    /// let read = |xml: &str| Value::from_reader_xml(xml.as_bytes()).unwrap();
+    /// let mut prefs = read(
+    ///     "<plist><dict><key>Editor</key><dict>
+    ///         <key>FontSize</key><integer>12</integer>
+    ///         <key>Theme</key><string>Light</string>
+    ///     </dict></dict></plist>",
+    /// );
+    /// prefs.deep_merge(read(
+    ///     "<plist><dict><key>Editor</key><dict>
+    ///         <key>Theme</key><string>Dark</string>
+    ///     </dict></dict></plist>",
+    /// ));
+    ///
+    /// assert_eq!(prefs.get_at_path("Editor.FontSize"), Some(&Value::from(12)));
+    /// assert_eq!(prefs.get_at_path("Editor.Theme"), Some(&Value::from("Dark")));
+    /// ```
+    pub fn deep_merge(&mut self, overlay: Value) {
+        merge::deep_merge(self, overlay, &DeepMergeOptions::default(), &mut Vec::new())
+            .expect("default options never return an error");
+    }
+
+    /// Recursively merges `overlay` into this value using the specified options.
+    ///
+    /// Returns an error, leaving this value unmodified, if two values of different types are
+    /// found and [`MergeTypeConflicts::Error`](crate::MergeTypeConflicts::Error) is selected. The
+    /// error's [`key_path`](Error::key_path) is the location of the conflicting values.
+    pub fn deep_merge_with_options(
+        &mut self,
+        overlay: Value,
+        options: &DeepMergeOptions,
+    ) -> Result<(), Error> {
+        let mut merged = self.clone();
+        merge::deep_merge(&mut merged, overlay, options, &mut Vec::new())?;
+        *self = merged;
+        Ok(())
+    }
+}
+
+/// Accesses a value in a dictionary by key.
+///
+/// # Panics
+///
+/// Panics if the value is not a dictionary or the key is not present in the dictionary.
+///
+/// ```
+/// # let mut dict = plist::Dictionary::new();
+/// # dict.insert("key".to_owned(), "value".into());
+/// # let value = plist::Value::Dictionary(dict);
+/// #
+/// assert_eq!(value["key"].as_string(), Some("value"));
+/// ```
+impl ops::Index<&str> for Value {
+    type Output = Value;
+
+    fn index(&self, index: &str) -> &Value {
+        match self {
+            Value::Dictionary(dict) => &dict[index],
+            _ => panic!("cannot index into a non-dictionary value with a key"),
+        }
+    }
+}
+
+/// Mutably accesses a value in a dictionary by key.
+///
+/// # Panics
+///
+/// Panics if the value is not a dictionary or the key is not present in the dictionary.
+impl ops::IndexMut<&str> for Value {
+    fn index_mut(&mut self, index: &str) -> &mut Value {
+        match self {
+            Value::Dictionary(dict) => &mut dict[index],
+            _ => panic!("cannot index into a non-dictionary value with a key"),
+        }
+    }
+}
+
+/// Accesses an element of an array by index.
+///
+/// # Panics
+///
+/// Panics if the value is not an array or the index is out of bounds.
+///
+/// ```
+/// let value = plist::Value::Array(vec![true.into()]);
+///
+/// assert_eq!(value[0].as_boolean(), Some(true));
+/// ```
+impl ops::Index<usize> for Value {
+    type Output = Value;
+
+    fn index(&self, index: usize) -> &Value {
+        match self {
+            Value::Array(array) => &array[index],
+            _ => panic!("cannot index into a non-array value with an integer"),
+        }
+    }
+}
+
+/// Mutably accesses an element of an array by index.
+///
+/// # Panics
+///
+/// Panics if the value is not an array or the index is out of bounds.
+impl ops::IndexMut<usize> for Value {
+    fn index_mut(&mut self, index: usize) -> &mut Value {
+        match self {
+            Value::Array(array) => &mut array[index],
+            _ => panic!("cannot index into a non-array value with an integer"),
+        }
+    }
 }
 
 #[cfg(feature = "serde")]
@@ -489,6 +887,13 @@ impl From<Dictionary> for Value {
     }
 }
 
+/// Converts a sorted dictionary into a dictionary value whose keys start in sorted order.
+impl From<Dictionary<BTreeMap<String, Value>>> for Value {
+    fn from(from: Dictionary<BTreeMap<String, Value>>) -> Value {
+        Value::Dictionary(from.into())
+    }
+}
+
 impl From<bool> for Value {
     fn from(from: bool) -> Value {
         Value::Boolean(from)
@@ -645,16 +1050,88 @@ impl<'a> From<&'a str> for Value {
     }
 }
 
-enum StackItem {
-    Root(Value),
-    Array(Vec<Value>),
-    Dict(Dictionary),
-    DictAndKey(Dictionary, String),
+impl From<Integer> for Value {
+    fn from(from: Integer) -> Value {
+        Value::Integer(from)
+    }
+}
+
+impl From<Uid> for Value {
+    fn from(from: Uid) -> Value {
+        Value::Uid(from)
+    }
+}
+
+/// Builds `Value`s with a [`TreeBuilder`].
+struct ValueTree;
+
+impl Tree<'_> for ValueTree {
+    type Node = Value;
+    type Array = Vec<Value>;
+    type Dictionary = Dictionary;
+    type Key = String;
+
+    fn start_array(&mut self, len: Option<u64>) -> Vec<Value> {
+        Vec::with_capacity(len.and_then(u64_to_usize).unwrap_or(0))
+    }
+
+    fn start_dictionary(&mut self, _: Option<u64>) -> Dictionary {
+        Dictionary::new()
+    }
+
+    fn push(&mut self, array: &mut Vec<Value>, value: Value) {
+        array.push(value);
+    }
+
+    fn insert(&mut self, dict: &mut Dictionary, key: String, value: Value) {
+        dict.insert(key, value);
+    }
+
+    fn end_array(&mut self, array: Vec<Value>) -> Value {
+        Value::Array(array)
+    }
+
+    fn end_dictionary(&mut self, dict: Dictionary) -> Value {
+        Value::Dictionary(dict)
+    }
+
+    fn leaf(&mut self, event: Event) -> Value {
+        match event {
+            Event::Boolean(value) => Value::Boolean(value),
+            Event::Data(value) => Value::Data(value.into_owned()),
+            Event::Date(value) => Value::Date(value),
+            Event::Integer(value) => Value::Integer(value),
+            Event::Real(value) => Value::Real(value),
+            Event::String(value) => Value::String(value.into_owned()),
+            Event::Uid(value) => Value::Uid(value),
+            Event::StartArray(_) | Event::StartDictionary(_) | Event::EndCollection => {
+                unreachable!("collection events are not leaves")
+            }
+        }
+    }
+
+    fn key(&mut self, value: Value) -> Result<String, Value> {
+        match value {
+            Value::String(key) => Ok(key),
+            value => Err(value),
+        }
+    }
+
+    fn kind(&self, value: &Value) -> EventKind {
+        EventKind::of_value(value)
+    }
 }
 
-#[derive(Default)]
 pub struct Builder {
-    stack: Vec<StackItem>,
+    builder: TreeBuilder<'static, ValueTree>,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Builder {
+            builder: TreeBuilder::new(ValueTree),
+        }
+    }
 }
 
 impl Builder {
@@ -662,111 +1139,58 @@ impl Builder {
     where
         T: Iterator<Item = Result<Event<'event>, Error>>,
     {
-        let mut builder = Self::default();
-        for event in stream {
-            builder.write(event?)?;
-        }
-        builder.finish()
+        TreeBuilder::build(ValueTree, stream).map(|(_, value)| value)
     }
 
-    fn write_value(&mut self, value: Value) -> Result<(), Error> {
-        match (self.stack.pop(), value) {
-            (None, value) => self.stack.push(StackItem::Root(value)),
-            (Some(StackItem::Root(_)), value) => {
-                return Err(ErrorKind::ExpectedEndOfEventStream {
-                    found: EventKind::of_value(&value),
-                }
-                .without_position())
-            }
-            (Some(StackItem::Array(mut array)), value) => {
-                array.push(value);
-                self.stack.push(StackItem::Array(array));
-            }
-            (Some(StackItem::Dict(dict)), Value::String(key)) => {
-                self.stack.push(StackItem::DictAndKey(dict, key));
-            }
-            (Some(StackItem::Dict(_)), value) => {
-                return Err(ErrorKind::UnexpectedEventType {
-                    expected: EventKind::DictionaryKeyOrEndCollection,
-                    found: EventKind::of_value(&value),
-                }
-                .without_position())
-            }
-            (Some(StackItem::DictAndKey(mut dict, key)), value) => {
-                dict.insert(key, value);
-                self.stack.push(StackItem::Dict(dict));
-            }
-        }
-        Ok(())
+    /// Returns `true` if a complete root value has been written.
+    pub(crate) fn is_complete(&self) -> bool {
+        self.builder.is_complete()
     }
 
     pub fn finish(&mut self) -> Result<Value, Error> {
-        match self.stack.pop() {
-            Some(StackItem::Root(value)) => Ok(value),
-            _ => Err(ErrorKind::UnexpectedEndOfEventStream.without_position()),
-        }
+        self.builder.finish()
     }
 }
 
 impl Writer for Builder {
     fn write_start_array(&mut self, len: Option<u64>) -> Result<(), Error> {
-        let len = len.and_then(u64_to_usize).unwrap_or(0);
-        self.stack.push(StackItem::Array(Vec::with_capacity(len)));
-        Ok(())
+        self.builder.start_array(len)
     }
 
-    fn write_start_dictionary(&mut self, _: Option<u64>) -> Result<(), Error> {
-        self.stack.push(StackItem::Dict(Dictionary::new()));
-        Ok(())
+    fn write_start_dictionary(&mut self, len: Option<u64>) -> Result<(), Error> {
+        self.builder.start_dictionary(len)
     }
 
     fn write_end_collection(&mut self) -> Result<(), Error> {
-        let value = match self.stack.pop() {
-            Some(StackItem::Root(_)) => {
-                return Err(ErrorKind::ExpectedEndOfEventStream {
-                    found: EventKind::EndCollection,
-                }
-                .without_position())
-            }
-            Some(StackItem::Array(array)) => Value::Array(array),
-            Some(StackItem::Dict(dict)) => Value::Dictionary(dict),
-            Some(StackItem::DictAndKey(_, _)) | None => {
-                return Err(ErrorKind::UnexpectedEventType {
-                    expected: EventKind::ValueOrStartCollection,
-                    found: EventKind::EndCollection,
-                }
-                .without_position())
-            }
-        };
-        self.write_value(value)
+        self.builder.end_collection()
     }
 
     fn write_boolean(&mut self, value: bool) -> Result<(), Error> {
-        self.write_value(Value::Boolean(value))
+        self.builder.write_node(Value::Boolean(value))
     }
 
     fn write_data(&mut self, value: Cow<[u8]>) -> Result<(), Error> {
-        self.write_value(Value::Data(value.into_owned()))
+        self.builder.write_node(Value::Data(value.into_owned()))
     }
 
     fn write_date(&mut self, value: Date) -> Result<(), Error> {
-        self.write_value(Value::Date(value))
+        self.builder.write_node(Value::Date(value))
     }
 
     fn write_integer(&mut self, value: Integer) -> Result<(), Error> {
-        self.write_value(Value::Integer(value))
+        self.builder.write_node(Value::Integer(value))
     }
 
     fn write_real(&mut self, value: f64) -> Result<(), Error> {
-        self.write_value(Value::Real(value))
+        self.builder.write_node(Value::Real(value))
     }
 
     fn write_string(&mut self, value: Cow<str>) -> Result<(), Error> {
-        self.write_value(Value::String(value.into_owned()))
+        self.builder.write_node(Value::String(value.into_owned()))
     }
 
     fn write_uid(&mut self, value: Uid) -> Result<(), Error> {
-        self.write_value(Value::Uid(value))
+        self.builder.write_node(Value::Uid(value))
     }
 }
 
@@ -866,4 +1290,79 @@ mod tests {
 
         assert!(value.is_err());
     }
+
+    fn nested_value() -> Value {
+        let mut inner = Dictionary::new();
+        inner.insert("a/b".to_owned(), 1.into());
+        inner.insert("c.d".to_owned(), 2.into());
+        let mut outer = Dictionary::new();
+        outer.insert(
+            "array".to_owned(),
+            vec![Value::from(inner), Value::from("x")].into(),
+        );
+        outer.into()
+    }
+
+    #[test]
+    fn pointer_and_index_access() {
+        let mut value = nested_value();
+
+        assert_eq!(value.pointer(""), Some(&value.clone()));
+        assert_eq!(value.pointer("/array/0/a~1b"), Some(&Value::from(1)));
+        assert_eq!(value.pointer("/array/1"), Some(&Value::from("x")));
+        assert_eq!(value.pointer("/array/01"), None);
+        assert_eq!(value.pointer("/array/2"), None);
+        assert_eq!(value.pointer("array"), None);
+        assert_eq!(value["array"][0]["c.d"], Value::from(2));
+
+        *value.pointer_mut("/array/1").unwrap() = "y".into();
+        value["array"][0]["c.d"] = 3.into();
+        assert_eq!(value.get_at_path("array.1"), Some(&Value::from("y")));
+        assert_eq!(value.get_at_path(r"array.0.c\.d"), Some(&Value::from(3)));
+    }
+
+    #[test]
+    fn insert_and_remove_at_path() {
+        let mut value = nested_value();
+
+        assert_eq!(value.insert_at_path("array.1", "w", false).unwrap(), None);
+        assert_eq!(value.insert_at_path("array.3", "z", false).unwrap(), None);
+        assert_eq!(
+            value
+                .pointer("/array")
+                .and_then(Value::as_array)
+                .map(Vec::len),
+            Some(4)
+        );
+        assert!(value.insert_at_path("array.5", "z", false).is_err());
+        assert_eq!(
+            value.insert_at_path("array.0.a/b", 5, false).unwrap(),
+            Some(Value::from(1))
+        );
+
+        let err = value
+            .insert_at_path("new.nested.key", true, false)
+            .unwrap_err();
+        assert_eq!(err.category(), crate::ErrorCategory::KeyPathNotFound);
+        value.insert_at_path("new.nested.key", true, true).unwrap();
+        assert_eq!(
+            value.pointer("/new/nested/key"),
+            Some(&Value::Boolean(true))
+        );
+        assert!(value.insert_at_path("array.1.key", true, true).is_err());
+
+        assert_eq!(value.remove_at_path("array.1"), Some(Value::from("w")));
+        assert_eq!(value.get_at_path("array.1"), Some(&Value::from("x")));
+        assert_eq!(value.remove_at_path("new.nested"), {
+            let mut dict = Dictionary::new();
+            dict.insert("key".to_owned(), true.into());
+            Some(dict.into())
+        });
+        assert_eq!(value.remove_at_path("new.nested"), None);
+        assert_eq!(value.remove_at_path(""), None);
+
+        let old = value.clone();
+        assert_eq!(value.insert_at_path("", "root", false).unwrap(), Some(old));
+        assert_eq!(value, Value::from("root"));
+    }
 }
@@ -0,0 +1,538 @@
+//! A plist value which borrows its strings and data from the input.
+//!
+//! [`ValueRef`] mirrors [`Value`] but holds strings and data as [`Cow`]s. When a plist is read
+//! with [`ValueRef::from_slice`], the ASCII strings and data of a binary plist and the strings
+//! without entity references of an XML plist are borrowed from the slice rather than copied,
+//! which makes read-only inspection of large plists cheaper. Strings and data which must be
+//! decoded, such as UTF-16 strings, XML strings with entity references, XML `<data>` and
+//! everything in ASCII plists, are owned.
+//!
+//! ```
+//! use plist::{Value, ValueRef};
+//!
+//! let bytes = std::fs::read("tests/data/binary.plist").unwrap();
+//! let value = ValueRef::from_slice(&bytes).unwrap();
+//!
+//! let author = value
+//!     .as_dictionary()
+//!     .and_then(|dict| dict.get("Author"))
+//!     .and_then(|author| author.as_string());
+//! assert_eq!(author, Some("William Shakespeare"));
+//!
+//! assert_eq!(value.into_owned(), Value::from_file("tests/data/binary.plist").unwrap());
+//! ```
+
+use indexmap::{map, IndexMap};
+use std::{
+    borrow::Cow,
+    fmt::{self, Debug},
+    ops,
+};
+
+use crate::{
+    error::{Error, EventKind},
+    stream::{Event, SliceReader},
+    tree_builder::{Tree, TreeBuilder},
+    u64_to_usize, Date, Dictionary, Integer, Uid, Value,
+};
+
+/// Represents any plist value, borrowing strings and data where possible.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ValueRef<'a> {
+    Array(Vec<ValueRef<'a>>),
+    Dictionary(DictionaryRef<'a>),
+    Boolean(bool),
+    Data(Cow<'a, [u8]>),
+    Date(Date),
+    Real(f64),
+    Integer(Integer),
+    String(Cow<'a, str>),
+    Uid(Uid),
+}
+
+impl<'a> ValueRef<'a> {
+    /// Reads a `ValueRef` from a slice containing a plist of any encoding.
+    ///
+    /// ASCII strings and data in binary plists, and strings without entity references in XML
+    /// plists, are borrowed from `bytes`.
+    pub fn from_slice(bytes: &'a [u8]) -> Result<ValueRef<'a>, Error> {
+        build(SliceReader::new(bytes))
+    }
+
+    /// Builds a single `ValueRef` from an `Event` iterator.
+    ///
+    /// Borrowed strings and data in the events are borrowed by the returned value.
+    #[cfg(feature = "enable_unstable_features_that_may_break_with_minor_version_bumps")]
+    pub fn from_events<T>(events: T) -> Result<ValueRef<'a>, Error>
+    where
+        T: IntoIterator<Item = Result<Event<'a>, Error>>,
+    {
+        build(events.into_iter())
+    }
+
+    /// Converts this value into a `Value`, copying any borrowed strings and data.
+    pub fn into_owned(self) -> Value {
+        match self {
+            ValueRef::Array(array) => {
+                Value::Array(array.into_iter().map(ValueRef::into_owned).collect())
+            }
+            ValueRef::Dictionary(dict) => Value::Dictionary(dict.into_owned()),
+            ValueRef::Boolean(value) => Value::Boolean(value),
+            ValueRef::Data(value) => Value::Data(value.into_owned()),
+            ValueRef::Date(value) => Value::Date(value),
+            ValueRef::Real(value) => Value::Real(value),
+            ValueRef::Integer(value) => Value::Integer(value),
+            ValueRef::String(value) => Value::String(value.into_owned()),
+            ValueRef::Uid(value) => Value::Uid(value),
+        }
+    }
+
+    /// If the `ValueRef` is an Array, returns the associated `Vec`.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_array(&self) -> Option<&Vec<ValueRef<'a>>> {
+        match *self {
+            ValueRef::Array(ref array) => Some(array),
+            _ => None,
+        }
+    }
+
+    /// If the `ValueRef` is a Dictionary, returns the associated `DictionaryRef`.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_dictionary(&self) -> Option<&DictionaryRef<'a>> {
+        match *self {
+            ValueRef::Dictionary(ref dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// If the `ValueRef` is a Boolean, returns the associated `bool`.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_boolean(&self) -> Option<bool> {
+        match *self {
+            ValueRef::Boolean(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// If the `ValueRef` is a Data, returns the associated bytes.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_data(&self) -> Option<&[u8]> {
+        match *self {
+            ValueRef::Data(ref data) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// If the `ValueRef` is a Date, returns the associated `Date`.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_date(&self) -> Option<Date> {
+        match *self {
+            ValueRef::Date(date) => Some(date),
+            _ => None,
+        }
+    }
+
+    /// If the `ValueRef` is a Real, returns the associated `f64`.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_real(&self) -> Option<f64> {
+        match *self {
+            ValueRef::Real(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// If the `ValueRef` is a signed Integer, returns the associated `i64`.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_signed_integer(&self) -> Option<i64> {
+        match *self {
+            ValueRef::Integer(v) => v.as_signed(),
+            _ => None,
+        }
+    }
+
+    /// If the `ValueRef` is an unsigned Integer, returns the associated `u64`.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_unsigned_integer(&self) -> Option<u64> {
+        match *self {
+            ValueRef::Integer(v) => v.as_unsigned(),
+            _ => None,
+        }
+    }
+
+    /// If the `ValueRef` is a String, returns the associated `str`.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_string(&self) -> Option<&str> {
+        match *self {
+            ValueRef::String(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// If the `ValueRef` is a Uid, returns the associated `Uid`.
+    ///
+    /// Returns `None` otherwise.
+    pub fn as_uid(&self) -> Option<&Uid> {
+        match *self {
+            ValueRef::Uid(ref u) => Some(u),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> From<&'a Value> for ValueRef<'a> {
+    /// Borrows the strings and data of `value`.
+    fn from(value: &'a Value) -> ValueRef<'a> {
+        match value {
+            Value::Array(array) => ValueRef::Array(array.iter().map(ValueRef::from).collect()),
+            Value::Dictionary(dict) => ValueRef::Dictionary(
+                dict.iter()
+                    .map(|(key, value)| (Cow::Borrowed(key.as_str()), ValueRef::from(value)))
+                    .collect(),
+            ),
+            Value::Boolean(value) => ValueRef::Boolean(*value),
+            Value::Data(value) => ValueRef::Data(Cow::Borrowed(value)),
+            Value::Date(value) => ValueRef::Date(*value),
+            Value::Real(value) => ValueRef::Real(*value),
+            Value::Integer(value) => ValueRef::Integer(*value),
+            Value::String(value) => ValueRef::String(Cow::Borrowed(value)),
+            Value::Uid(value) => ValueRef::Uid(*value),
+        }
+    }
+}
+
+impl From<ValueRef<'_>> for Value {
+    fn from(value: ValueRef<'_>) -> Value {
+        value.into_owned()
+    }
+}
+
+/// Represents a plist dictionary whose keys and values may borrow from the input.
+///
+/// Entries are kept in the order in which they were read.
+#[derive(Clone, Default, PartialEq)]
+pub struct DictionaryRef<'a> {
+    map: IndexMap<Cow<'a, str>, ValueRef<'a>>,
+}
+
+impl<'a> DictionaryRef<'a> {
+    /// Makes a new empty `DictionaryRef`.
+    #[inline]
+    pub fn new() -> Self {
+        DictionaryRef {
+            map: IndexMap::new(),
+        }
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    #[inline]
+    pub fn get(&self, key: &str) -> Option<&ValueRef<'a>> {
+        self.map.get(key)
+    }
+
+    /// Returns true if the dictionary contains a value for the specified key.
+    #[inline]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Inserts a key-value pair into the dictionary.
+    ///
+    /// If the dictionary did not have this key present, `None` is returned.
+    ///
+    /// If the dictionary did have this key present, the value is updated, and the old value is
+    /// returned. The key keeps its position in the dictionary.
+    #[inline]
+    pub fn insert(&mut self, k: Cow<'a, str>, v: ValueRef<'a>) -> Option<ValueRef<'a>> {
+        self.map.insert(k, v)
+    }
+
+    /// Returns the number of elements in the dictionary.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if the dictionary contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Gets an iterator over the entries of the dictionary.
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, 'a> {
+        Iter {
+            iter: self.map.iter(),
+        }
+    }
+
+    /// Converts this dictionary into a `Dictionary`, copying any borrowed keys and values.
+    pub fn into_owned(self) -> Dictionary {
+        self.map
+            .into_iter()
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect()
+    }
+}
+
+impl<'a> ops::Index<&str> for DictionaryRef<'a> {
+    type Output = ValueRef<'a>;
+
+    #[inline]
+    fn index(&self, index: &str) -> &Self::Output {
+        self.map.index(index)
+    }
+}
+
+impl Debug for DictionaryRef<'_> {
+    #[inline]
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        self.map.fmt(formatter)
+    }
+}
+
+impl<'a> FromIterator<(Cow<'a, str>, ValueRef<'a>)> for DictionaryRef<'a> {
+    #[inline]
+    fn from_iter<T>(iter: T) -> Self
+    where
+        T: IntoIterator<Item = (Cow<'a, str>, ValueRef<'a>)>,
+    {
+        DictionaryRef {
+            map: FromIterator::from_iter(iter),
+        }
+    }
+}
+
+impl<'b, 'a> IntoIterator for &'b DictionaryRef<'a> {
+    type Item = (&'b Cow<'a, str>, &'b ValueRef<'a>);
+    type IntoIter = Iter<'b, 'a>;
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over a `plist::DictionaryRef`'s entries.
+pub struct Iter<'b, 'a> {
+    iter: map::Iter<'b, Cow<'a, str>, ValueRef<'a>>,
+}
+
+impl<'b, 'a> Iterator for Iter<'b, 'a> {
+    type Item = (&'b Cow<'a, str>, &'b ValueRef<'a>);
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_, '_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+impl ExactSizeIterator for Iter<'_, '_> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
+impl<'a> IntoIterator for DictionaryRef<'a> {
+    type Item = (Cow<'a, str>, ValueRef<'a>);
+    type IntoIter = IntoIter<'a>;
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            iter: self.map.into_iter(),
+        }
+    }
+}
+
+/// An owning iterator over a `plist::DictionaryRef`'s entries.
+pub struct IntoIter<'a> {
+    iter: map::IntoIter<Cow<'a, str>, ValueRef<'a>>,
+}
+
+impl<'a> Iterator for IntoIter<'a> {
+    type Item = (Cow<'a, str>, ValueRef<'a>);
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for IntoIter<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back()
+    }
+}
+
+impl ExactSizeIterator for IntoIter<'_> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
+/// Builds `ValueRef`s with a [`TreeBuilder`], keeping borrowed strings and data borrowed.
+struct ValueRefTree;
+
+impl<'a> Tree<'a> for ValueRefTree {
+    type Node = ValueRef<'a>;
+    type Array = Vec<ValueRef<'a>>;
+    type Dictionary = DictionaryRef<'a>;
+    type Key = Cow<'a, str>;
+
+    fn start_array(&mut self, len: Option<u64>) -> Vec<ValueRef<'a>> {
+        Vec::with_capacity(len.and_then(u64_to_usize).unwrap_or(0))
+    }
+
+    fn start_dictionary(&mut self, _: Option<u64>) -> DictionaryRef<'a> {
+        DictionaryRef::new()
+    }
+
+    fn push(&mut self, array: &mut Vec<ValueRef<'a>>, value: ValueRef<'a>) {
+        array.push(value);
+    }
+
+    fn insert(&mut self, dict: &mut DictionaryRef<'a>, key: Cow<'a, str>, value: ValueRef<'a>) {
+        dict.insert(key, value);
+    }
+
+    fn end_array(&mut self, array: Vec<ValueRef<'a>>) -> ValueRef<'a> {
+        ValueRef::Array(array)
+    }
+
+    fn end_dictionary(&mut self, dict: DictionaryRef<'a>) -> ValueRef<'a> {
+        ValueRef::Dictionary(dict)
+    }
+
+    fn leaf(&mut self, event: Event<'a>) -> ValueRef<'a> {
+        match event {
+            Event::Boolean(value) => ValueRef::Boolean(value),
+            Event::Data(value) => ValueRef::Data(value),
+            Event::Date(value) => ValueRef::Date(value),
+            Event::Integer(value) => ValueRef::Integer(value),
+            Event::Real(value) => ValueRef::Real(value),
+            Event::String(value) => ValueRef::String(value),
+            Event::Uid(value) => ValueRef::Uid(value),
+            Event::StartArray(_) | Event::StartDictionary(_) | Event::EndCollection => {
+                unreachable!("collection events are not leaves")
+            }
+        }
+    }
+
+    fn key(&mut self, value: ValueRef<'a>) -> Result<Cow<'a, str>, ValueRef<'a>> {
+        match value {
+            ValueRef::String(key) => Ok(key),
+            value => Err(value),
+        }
+    }
+
+    fn kind(&self, value: &ValueRef<'a>) -> EventKind {
+        EventKind::of_value_ref(value)
+    }
+}
+
+/// Builds a `ValueRef` from an event stream.
+fn build<'a, T>(events: T) -> Result<ValueRef<'a>, Error>
+where
+    T: Iterator<Item = Result<Event<'a>, Error>>,
+{
+    TreeBuilder::build(ValueRefTree, events).map(|(_, value)| value)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn is_borrowed_from(value: &ValueRef, bytes: &[u8]) -> bool {
+        let range = bytes.as_ptr_range();
+        match value {
+            ValueRef::String(Cow::Borrowed(s)) => range.contains(&s.as_ptr()),
+            ValueRef::Data(Cow::Borrowed(d)) => range.contains(&d.as_ptr()),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn borrows_from_binary_slice() {
+        let bytes = fs::read("./tests/data/binary.plist").unwrap();
+        let value = ValueRef::from_slice(&bytes).unwrap();
+        let dict = value.as_dictionary().unwrap();
+
+        assert!(is_borrowed_from(&dict["Author"], &bytes));
+        assert!(is_borrowed_from(&dict["Data"], &bytes));
+        assert!(dict
+            .iter()
+            .all(|(key, _)| matches!(key, Cow::Borrowed(key) if bytes.as_ptr_range().contains(&key.as_ptr()))));
+        assert_eq!(dict["Height"].as_real(), Some(1.6));
+
+        let owned = Value::from_file("./tests/data/binary.plist").unwrap();
+        assert_eq!(value.clone().into_owned(), owned);
+        assert_eq!(ValueRef::from(&owned), value);
+    }
+
+    #[test]
+    fn reads_xml_slice() {
+        let bytes = fs::read("./tests/data/xml.plist").unwrap();
+        let value = ValueRef::from_slice(&bytes).unwrap();
+        let dict = value.as_dictionary().unwrap();
+
+        assert!(is_borrowed_from(&dict["Author"], &bytes));
+        // Strings containing entity references must be copied.
+        assert_eq!(dict["Pets"].as_string(), Some("A cat & a dog."));
+        assert!(!is_borrowed_from(&dict["Pets"], &bytes));
+        assert_eq!(
+            value.into_owned(),
+            Value::from_file("./tests/data/xml.plist").unwrap()
+        );
+
+        let bytes = fs::read("./tests/data/ascii-sample.plist").unwrap();
+        assert_eq!(
+            ValueRef::from_slice(&bytes).unwrap().into_owned(),
+            Value::from_file("./tests/data/ascii-sample.plist").unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_event_streams() {
+        let events = |events: Vec<Event<'static>>| build(events.into_iter().map(Ok));
+
+        let err = events(vec![Event::StartDictionary(None), Event::Integer(1.into())]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "UnexpectedEventType { expected: DictionaryKeyOrEndCollection, found: Integer }"
+        );
+        let err = events(vec![Event::Boolean(true), Event::Boolean(false)]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ExpectedEndOfEventStream { found: Boolean }"
+        );
+        let err = events(vec![Event::StartArray(None)]).unwrap_err();
+        assert_eq!(err.to_string(), "UnexpectedEndOfEventStream");
+    }
+}
@@ -0,0 +1,347 @@
+//! Traits for recursively traversing a [`Value`].
+//!
+//! The [`Visit`] and [`VisitMut`] traits have a method for each kind of value, all of which
+//! recurse into collections by default. Override the methods for the values of interest and call
+//! the function of the same name in this module to continue the traversal into a collection.
+//! Each method is given the location of the value relative to the root of the traversal.
+//!
+//! ```
+//! use plist::{query::PathElement, visit::Visit, Value};
+//!
+//! /// Collects the key paths of every string.
+//! struct Strings(Vec<String>);
+//!
+//! impl<'a> Visit<'a> for Strings {
+//!     fn visit_scalar(&mut self, path: &[PathElement<'a>], value: &'a Value) {
+//!         if value.as_string().is_some() {
+//!             let path: Vec<_> = path.iter().map(ToString::to_string).collect();
+//!             self.0.push(path.join("."));
+//!         }
+//!     }
+//! }
+//!
+//! let value = Value::from_reader_xml(
+//!     "<plist><dict>
+//!         <key>Name</key><string>App</string>
+//!         <key>Schemes</key><array><string>app</string><integer>1</integer></array>
+//!     </dict></plist>"
+//!         .as_bytes(),
+//! )
+//! .unwrap();
+//!
+//! let mut strings = Strings(Vec::new());
+//! strings.visit_value(&[], &value);
+//! assert_eq!(strings.0, ["Name", "Schemes.0"]);
+//! ```
+//!
+//! For simple traversals [`Value::walk`] and [`Value::transform`] accept a closure instead.
+
+use crate::{query::PathElement, Dictionary, Value};
+
+/// A recursive traversal of a shared [`Value`].
+///
+/// See the [module documentation](self) for details.
+pub trait Visit<'a> {
+    /// Visits any value. By default this calls the method for the kind of value.
+    fn visit_value(&mut self, path: &[PathElement<'a>], value: &'a Value) {
+        visit_value(self, path, value);
+    }
+
+    /// Visits an array. By default this visits each element.
+    fn visit_array(&mut self, path: &[PathElement<'a>], array: &'a [Value]) {
+        visit_array(self, path, array);
+    }
+
+    /// Visits a dictionary. By default this visits each value.
+    fn visit_dictionary(&mut self, path: &[PathElement<'a>], dict: &'a Dictionary) {
+        visit_dictionary(self, path, dict);
+    }
+
+    /// Visits a value which is not a collection. By default this does nothing.
+    fn visit_scalar(&mut self, path: &[PathElement<'a>], value: &'a Value) {
+        let _ = (path, value);
+    }
+}
+
+/// A recursive traversal of a mutable [`Value`].
+///
+/// See the [module documentation](self) for details.
+pub trait VisitMut {
+    /// Visits any value. By default this calls the method for the kind of value.
+    fn visit_value_mut(&mut self, path: &[PathElement], value: &mut Value) {
+        visit_value_mut(self, path, value);
+    }
+
+    /// Visits an array. By default this visits each element.
+    fn visit_array_mut(&mut self, path: &[PathElement], array: &mut Vec<Value>) {
+        visit_array_mut(self, path, array);
+    }
+
+    /// Visits a dictionary. By default this visits each value.
+    fn visit_dictionary_mut(&mut self, path: &[PathElement], dict: &mut Dictionary) {
+        visit_dictionary_mut(self, path, dict);
+    }
+
+    /// Visits a value which is not a collection. By default this does nothing.
+    fn visit_scalar_mut(&mut self, path: &[PathElement], value: &mut Value) {
+        let _ = (path, value);
+    }
+}
+
+/// Calls the [`Visit`] method for the kind of `value`.
+pub fn visit_value<'a, V: Visit<'a> + ?Sized>(
+    visitor: &mut V,
+    path: &[PathElement<'a>],
+    value: &'a Value,
+) {
+    match value {
+        Value::Array(array) => visitor.visit_array(path, array),
+        Value::Dictionary(dict) => visitor.visit_dictionary(path, dict),
+        _ => visitor.visit_scalar(path, value),
+    }
+}
+
+/// Visits each element of `array`.
+pub fn visit_array<'a, V: Visit<'a> + ?Sized>(
+    visitor: &mut V,
+    path: &[PathElement<'a>],
+    array: &'a [Value],
+) {
+    for (i, value) in array.iter().enumerate() {
+        visitor.visit_value(&child_path(path, PathElement::Index(i)), value);
+    }
+}
+
+/// Visits each value of `dict`.
+pub fn visit_dictionary<'a, V: Visit<'a> + ?Sized>(
+    visitor: &mut V,
+    path: &[PathElement<'a>],
+    dict: &'a Dictionary,
+) {
+    for (key, value) in dict {
+        visitor.visit_value(&child_path(path, PathElement::Key(key)), value);
+    }
+}
+
+/// Calls the [`VisitMut`] method for the kind of `value`.
+pub fn visit_value_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    path: &[PathElement],
+    value: &mut Value,
+) {
+    match value {
+        Value::Array(array) => visitor.visit_array_mut(path, array),
+        Value::Dictionary(dict) => visitor.visit_dictionary_mut(path, dict),
+        _ => visitor.visit_scalar_mut(path, value),
+    }
+}
+
+/// Visits each element of `array`.
+// Takes a `Vec` to match `VisitMut::visit_array_mut`, which allows elements to be added or removed.
+#[allow(clippy::ptr_arg)]
+pub fn visit_array_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    path: &[PathElement],
+    array: &mut Vec<Value>,
+) {
+    for (i, value) in array.iter_mut().enumerate() {
+        visitor.visit_value_mut(&child_path(path, PathElement::Index(i)), value);
+    }
+}
+
+/// Visits each value of `dict`.
+pub fn visit_dictionary_mut<V: VisitMut + ?Sized>(
+    visitor: &mut V,
+    path: &[PathElement],
+    dict: &mut Dictionary,
+) {
+    for (key, value) in dict.iter_mut() {
+        visitor.visit_value_mut(&child_path(path, PathElement::Key(key)), value);
+    }
+}
+
+fn child_path<'a>(path: &[PathElement<'a>], element: PathElement<'a>) -> Vec<PathElement<'a>> {
+    let mut child = Vec::with_capacity(path.len() + 1);
+    child.extend_from_slice(path);
+    child.push(element);
+    child
+}
+
+/// What [`Value::transform`] does after calling its closure with a value.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Transform {
+    /// Keep the value and traverse into it if it is a collection.
+    Continue,
+    /// Keep the value without traversing into it.
+    SkipChildren,
+    /// Replace the value without traversing into the replacement.
+    Replace(Value),
+    /// Remove the value from its containing dictionary or array.
+    ///
+    /// The root value cannot be removed and is kept without being traversed.
+    Remove,
+}
+
+/// Applies `f` to `value`, returning `false` if the value should be removed.
+pub(crate) fn transform<F>(path: &[PathElement], value: &mut Value, f: &mut F) -> bool
+where
+    F: FnMut(&[PathElement], &mut Value) -> Transform,
+{
+    match f(path, value) {
+        Transform::Continue => (),
+        Transform::SkipChildren => return true,
+        Transform::Replace(replacement) => {
+            *value = replacement;
+            return true;
+        }
+        Transform::Remove => return false,
+    }
+
+    match value {
+        Value::Array(array) => {
+            let mut i = 0;
+            array.retain_mut(|value| {
+                let keep = transform(&child_path(path, PathElement::Index(i)), value, f);
+                i += 1;
+                keep
+            });
+        }
+        Value::Dictionary(dict) => {
+            dict.retain(|key, value| transform(&child_path(path, PathElement::Key(key)), value, f));
+        }
+        _ => (),
+    }
+    true
+}
+
+/// Applies `f` to `value` and each of its descendants in document order.
+pub(crate) fn walk<'a, F>(path: &[PathElement<'a>], value: &'a Value, f: &mut F)
+where
+    F: FnMut(&[PathElement<'a>], &'a Value),
+{
+    f(path, value);
+    match value {
+        Value::Array(array) => {
+            for (i, value) in array.iter().enumerate() {
+                walk(&child_path(path, PathElement::Index(i)), value, f);
+            }
+        }
+        Value::Dictionary(dict) => {
+            for (key, value) in dict {
+                walk(&child_path(path, PathElement::Key(key)), value, f);
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_path;
+
+    fn value() -> Value {
+        Value::from_reader_xml(
+            "<plist><dict>
+                <key>a</key><array><string> x </string><dict><key>b</key><integer>1</integer></dict></array>
+                <key>c</key><string>y </string>
+                <key>d</key><dict><key>e</key><true/></dict>
+            </dict></plist>"
+                .as_bytes(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn visit() {
+        #[derive(Default)]
+        struct Counts {
+            collections: usize,
+            paths: Vec<String>,
+        }
+
+        impl<'a> Visit<'a> for Counts {
+            fn visit_dictionary(&mut self, path: &[PathElement<'a>], dict: &'a Dictionary) {
+                self.collections += 1;
+                // Do not traverse into `d`.
+                if path != [PathElement::Key("d")] {
+                    visit_dictionary(self, path, dict);
+                }
+            }
+
+            fn visit_array(&mut self, path: &[PathElement<'a>], array: &'a [Value]) {
+                self.collections += 1;
+                visit_array(self, path, array);
+            }
+
+            fn visit_scalar(&mut self, path: &[PathElement<'a>], _value: &'a Value) {
+                self.paths.push(key_path::join(path));
+            }
+        }
+
+        let value = value();
+        let mut counts = Counts::default();
+        counts.visit_value(&[], &value);
+        assert_eq!(counts.collections, 4);
+        assert_eq!(counts.paths, ["a.0", "a.1.b", "c"]);
+    }
+
+    #[test]
+    fn visit_mut() {
+        struct Trim;
+
+        impl VisitMut for Trim {
+            fn visit_scalar_mut(&mut self, _path: &[PathElement], value: &mut Value) {
+                if let Value::String(s) = value {
+                    *s = s.trim().to_owned();
+                }
+            }
+        }
+
+        let mut value = value();
+        Trim.visit_value_mut(&[], &mut value);
+        assert_eq!(value.pointer("/a/0"), Some(&Value::from("x")));
+        assert_eq!(value.pointer("/c"), Some(&Value::from("y")));
+    }
+
+    #[test]
+    fn walk_and_transform() {
+        let mut value = value();
+
+        let mut paths = Vec::new();
+        value.walk(|path, _| paths.push(key_path::join(path)));
+        assert_eq!(paths, ["", "a", "a.0", "a.1", "a.1.b", "c", "d", "d.e"]);
+
+        let mut visited = Vec::new();
+        value.transform(|path, value| {
+            visited.push(key_path::join(path));
+            match path.last() {
+                Some(PathElement::Index(0)) => Transform::Remove,
+                Some(PathElement::Key("c")) => Transform::Replace(Dictionary::new().into()),
+                Some(PathElement::Key("d")) => Transform::SkipChildren,
+                Some(PathElement::Key("b")) => {
+                    *value = 2.into();
+                    Transform::Continue
+                }
+                _ => Transform::Continue,
+            }
+        });
+        assert_eq!(visited, ["", "a", "a.0", "a.1", "a.1.b", "c", "d"]);
+        assert_eq!(
+            value,
+            Value::from_reader_xml(
+                "<plist><dict>
+                    <key>a</key><array><dict><key>b</key><integer>2</integer></dict></array>
+                    <key>c</key><dict/>
+                    <key>d</key><dict><key>e</key><true/></dict>
+                </dict></plist>"
+                    .as_bytes(),
+            )
+            .unwrap()
+        );
+
+        value.transform(|_, _| Transform::Remove);
+        assert!(value.as_dictionary().is_some());
+    }
+}
@@ -0,0 +1,1008 @@
+//! A format-preserving XML plist document.
+
+use quick_xml::{
+    escape::escape,
+    events::{BytesStart, Event as XmlEvent},
+    Reader as EventReader,
+};
+use std::{
+    fmt,
+    fs::File,
+    io::{BufWriter, Read, Write},
+    mem,
+    ops::Range,
+    path::Path,
+    str::FromStr,
+};
+
+use crate::{
+    error::{self, Error, ErrorKind},
+    key_path,
+    stream::XmlReader,
+    Value, XmlWriteOptions,
+};
+
+/// An XML plist which can be edited without disturbing its formatting.
+///
+/// Comments, whitespace, key order and the encoding of untouched values are retained exactly as
+/// they appear in the source. Only the elements affected by an edit are rewritten, using the
+/// indentation of their neighbours.
+///
+/// Values are addressed by key paths in the dotted syntax used by `plutil`, e.g.
+/// `CFBundleURLTypes.0.CFBundleURLSchemes`. A literal `.` in a key is escaped as `\.` and the
+/// empty key path refers to the root value.
+///
+/// # Examples
+///
+/// ```
+/// use plist::{Value, XmlDocument};
+///
+/// let mut doc: XmlDocument = r#"<?xml version="1.0" encoding="UTF-8"?>
+/// <plist version="1.0">
+/// <dict>
+///     <!-- The bundle version -->
+///     <key>CFBundleVersion</key>
+///     <string>1</string>
+/// </dict>
+/// </plist>
+/// "#
+/// .parse()
+/// .unwrap();
+///
+/// doc.set("CFBundleVersion", "2").unwrap();
+/// doc.set("CFBundleShortVersionString", "1.0").unwrap();
+///
+/// assert_eq!(doc.get("CFBundleVersion"), Some(&Value::from("2")));
+/// assert_eq!(
+///     doc.as_str(),
+///     r#"<?xml version="1.0" encoding="UTF-8"?>
+/// <plist version="1.0">
+/// <dict>
+///     <!-- The bundle version -->
+///     <key>CFBundleVersion</key>
+///     <string>2</string>
+///     <key>CFBundleShortVersionString</key>
+///     <string>1.0</string>
+/// </dict>
+/// </plist>
+/// "#
+/// );
+/// ```
+#[derive(Clone)]
+pub struct XmlDocument {
+    source: String,
+    value: Value,
+    root: Node,
+}
+
+/// The location of an element in the document source.
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+struct Node {
+    /// The start of the element's opening tag.
+    start: usize,
+    /// The end of the element's closing tag.
+    end: usize,
+    /// The end of the element's opening tag. Equal to `end` for self-closing elements.
+    content_start: usize,
+    children: Children,
+}
+
+impl Node {
+    fn descendant_mut<S: AsRef<str>>(&mut self, components: &[S]) -> Option<&mut Node> {
+        components
+            .iter()
+            .try_fold(self, |node, component| match &mut node.children {
+                Children::Dictionary(entries) => entries
+                    .iter_mut()
+                    .rfind(|e| e.key == component.as_ref())
+                    .map(|e| &mut e.value),
+                Children::Array(elements) => elements.get_mut(key_path::index(component.as_ref())?),
+                Children::None => None,
+            })
+    }
+
+    /// Moves the offsets after `range` to account for replacing it with `len` bytes.
+    ///
+    /// Nodes which start at the end of the range follow it, while nodes which end there
+    /// precede it.
+    fn shift(&mut self, range: &Range<usize>, len: usize) {
+        if self.end <= range.end {
+            return;
+        }
+        let shift = |offset: usize| offset - range.len() + len;
+        if self.start >= range.end {
+            self.start = shift(self.start);
+        }
+        if self.content_start > range.end {
+            self.content_start = shift(self.content_start);
+        }
+        self.end = shift(self.end);
+        match &mut self.children {
+            Children::Dictionary(entries) => {
+                for entry in entries {
+                    entry.key_node.shift(range, len);
+                    entry.value.shift(range, len);
+                }
+            }
+            Children::Array(elements) => {
+                for element in elements {
+                    element.shift(range, len);
+                }
+            }
+            Children::None => (),
+        }
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+enum Children {
+    Dictionary(Vec<Entry>),
+    Array(Vec<Node>),
+    None,
+}
+
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug, PartialEq))]
+struct Entry {
+    key: String,
+    key_node: Node,
+    value: Node,
+}
+
+/// A replacement of a range of the document source.
+struct Edit {
+    range: Range<usize>,
+    text: String,
+}
+
+/// How an [`Edit`] changes the node it applies to.
+enum Change {
+    /// The node is replaced by the edit's text.
+    Replace,
+    /// A child is inserted into the collection at the index.
+    Insert(usize),
+    /// The child at the index is removed from the collection.
+    Remove(usize),
+}
+
+impl XmlDocument {
+    /// Reads an `XmlDocument` from an XML plist file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<XmlDocument, Error> {
+        let file = File::open(path).map_err(error::from_io_without_position)?;
+        XmlDocument::from_reader(file)
+    }
+
+    /// Reads an `XmlDocument` from a byte stream containing an XML encoded plist.
+    ///
+    /// The document must be encoded as UTF-8.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<XmlDocument, Error> {
+        let mut source = Vec::new();
+        reader
+            .read_to_end(&mut source)
+            .map_err(error::from_io_without_position)?;
+        let source = String::from_utf8(source).map_err(|err| {
+            ErrorKind::InvalidXmlUtf8.with_byte_offset(err.utf8_error().valid_up_to() as u64)
+        })?;
+        XmlDocument::parse(source)
+    }
+
+    fn parse(source: String) -> Result<XmlDocument, Error> {
+        // Parsing with `XmlReader` validates the document and reports errors with their position.
+        let value = Value::from_events(XmlReader::new(source.as_bytes()))?;
+        let root = NodeParser::new(&source, 0).parse_root()?;
+        Ok(XmlDocument {
+            source,
+            value,
+            root,
+        })
+    }
+
+    /// Returns the document source.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Consumes the document and returns its source.
+    pub fn into_string(self) -> String {
+        self.source
+    }
+
+    /// Returns the root value of the document.
+    pub fn as_value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Serializes the document source to a byte stream.
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        writer
+            .write_all(self.source.as_bytes())
+            .and_then(|()| writer.flush())
+            .map_err(error::from_io_without_position)
+    }
+
+    /// Serializes the document source to a file.
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let mut file = File::create(path).map_err(error::from_io_without_position)?;
+        self.to_writer(BufWriter::new(&mut file))?;
+        file.sync_all().map_err(error::from_io_without_position)?;
+        Ok(())
+    }
+
+    /// Returns a reference to the value at the key path.
+    pub fn get(&self, key_path: &str) -> Option<&Value> {
+        key_path::get(&self.value, &key_path::split(key_path))
+    }
+
+    /// Sets the value at the key path, returning the previous value if there was one.
+    ///
+    /// If the parent of the key path is a dictionary without the final key, the key is appended
+    /// to the dictionary. If it is an array the index must refer to an existing element.
+    pub fn set<V: Into<Value>>(
+        &mut self,
+        key_path: &str,
+        value: V,
+    ) -> Result<Option<Value>, Error> {
+        let value = value.into();
+        let components = key_path::split(key_path);
+        let Some((last, parent_components)) = components.split_last() else {
+            let edit = self.replace_node(&self.root, &value)?;
+            self.apply(&components, edit, Change::Replace)?;
+            return Ok(Some(mem::replace(&mut self.value, value)));
+        };
+
+        let parent = self
+            .node(parent_components)
+            .ok_or_else(key_path_not_found)?;
+        let (edit, path, change) = match &parent.children {
+            Children::Dictionary(entries) => match entries.iter().rfind(|e| e.key == *last) {
+                Some(entry) => (
+                    self.replace_node(&entry.value, &value)?,
+                    &components[..],
+                    Change::Replace,
+                ),
+                None => {
+                    let (edit, change) =
+                        self.insert_entry(parent, entries, entries.len(), last, &value)?;
+                    (edit, parent_components, change)
+                }
+            },
+            Children::Array(elements) => {
+                let element = key_path::index(last)
+                    .and_then(|index| elements.get(index))
+                    .ok_or_else(key_path_not_found)?;
+                (
+                    self.replace_node(element, &value)?,
+                    &components[..],
+                    Change::Replace,
+                )
+            }
+            Children::None => return Err(key_path_not_found()),
+        };
+        self.apply(path, edit, change)?;
+
+        let previous = match key_path::get_mut(&mut self.value, parent_components) {
+            Some(Value::Dictionary(dict)) => dict.insert(last.clone(), value),
+            Some(Value::Array(array)) => {
+                let element = key_path::index(last)
+                    .and_then(|index| array.get_mut(index))
+                    .expect("document nodes match its value");
+                Some(mem::replace(element, value))
+            }
+            _ => unreachable!("document nodes match its value"),
+        };
+        Ok(previous)
+    }
+
+    /// Inserts a value at the key path.
+    ///
+    /// If the parent of the key path is an array the value is inserted at the index, shifting
+    /// all following elements. The index may be equal to the length of the array to append the
+    /// value. If it is a dictionary this behaves like [`XmlDocument::set`].
+    pub fn insert<V: Into<Value>>(
+        &mut self,
+        key_path: &str,
+        value: V,
+    ) -> Result<Option<Value>, Error> {
+        let value = value.into();
+        let components = key_path::split(key_path);
+        let Some((last, parent_components)) = components.split_last() else {
+            return self.set(key_path, value);
+        };
+
+        let parent = self
+            .node(parent_components)
+            .ok_or_else(key_path_not_found)?;
+        let Children::Array(elements) = &parent.children else {
+            return self.set(key_path, value);
+        };
+        let index = key_path::index(last)
+            .filter(|&index| index <= elements.len())
+            .ok_or_else(key_path_not_found)?;
+        let spans: Vec<_> = elements.iter().map(|e| e.start..e.end).collect();
+        let (edit, change) = self.insert_child(parent, &spans, index, |_, indent| {
+            self.value_fragment(&value, indent)
+        })?;
+        self.apply(parent_components, edit, change)?;
+
+        let Some(Value::Array(array)) = key_path::get_mut(&mut self.value, parent_components)
+        else {
+            unreachable!("document nodes match its value")
+        };
+        array.insert(index, value);
+        Ok(None)
+    }
+
+    /// Removes the value at the key path, returning it if it was present.
+    ///
+    /// As with [`XmlDocument::set`], the parent of the key path must be an existing dictionary
+    /// or array. The root value cannot be removed.
+    pub fn remove(&mut self, key_path: &str) -> Result<Option<Value>, Error> {
+        let components = key_path::split(key_path);
+        let Some((last, parent_components)) = components.split_last() else {
+            return Err(key_path_not_found());
+        };
+
+        let parent = self
+            .node(parent_components)
+            .ok_or_else(key_path_not_found)?;
+        let (spans, index): (Vec<_>, _) = match &parent.children {
+            Children::Dictionary(entries) => (
+                entries
+                    .iter()
+                    .map(|e| e.key_node.start..e.value.end)
+                    .collect(),
+                entries.iter().rposition(|e| e.key == *last),
+            ),
+            Children::Array(elements) => (
+                elements.iter().map(|e| e.start..e.end).collect(),
+                key_path::index(last).filter(|&index| index < elements.len()),
+            ),
+            Children::None => return Err(key_path_not_found()),
+        };
+        let Some(index) = index else {
+            return Ok(None);
+        };
+
+        let span = &spans[index];
+        let previous_end = self.previous_end(parent, &spans, index);
+        let start = span.start - self.trailing_whitespace(previous_end..span.start).len();
+        let edit = Edit {
+            range: start..span.end,
+            text: String::new(),
+        };
+        self.apply(parent_components, edit, Change::Remove(index))?;
+
+        // An earlier entry with the same key takes effect once the last one is removed.
+        let earlier = match &self.node(parent_components).map(|node| &node.children) {
+            Some(Children::Dictionary(entries)) => match entries.iter().rfind(|e| e.key == *last) {
+                Some(entry) => Some(Value::from_events(XmlReader::new(
+                    &self.source.as_bytes()[entry.value.start..entry.value.end],
+                ))?),
+                None => None,
+            },
+            _ => None,
+        };
+        let previous = match key_path::get_mut(&mut self.value, parent_components) {
+            Some(Value::Dictionary(dict)) => match earlier {
+                Some(earlier) => dict.insert(last.clone(), earlier),
+                None => dict.shift_remove(last),
+            },
+            Some(Value::Array(array)) => Some(array.remove(index)),
+            _ => unreachable!("document nodes match its value"),
+        };
+        Ok(previous)
+    }
+
+    /// Applies an edit to the source and updates the node at `path`, which must contain the
+    /// edited range, without parsing the rest of the document again.
+    fn apply<S: AsRef<str>>(
+        &mut self,
+        path: &[S],
+        edit: Edit,
+        change: Change,
+    ) -> Result<(), Error> {
+        let Edit { range, text } = edit;
+        self.source.replace_range(range.clone(), &text);
+        self.root.shift(&range, text.len());
+
+        let mut parser = NodeParser::new(&self.source, range.start);
+        let node = self.root.descendant_mut(path).expect("edited node exists");
+        match (change, &mut node.children) {
+            (Change::Replace, _) => *node = parser.parse_next_node()?,
+            (Change::Insert(index), Children::Dictionary(entries)) => {
+                let key_node = parser.parse_next_node()?;
+                entries.insert(index, parser.parse_entry(key_node)?);
+            }
+            (Change::Insert(index), Children::Array(elements)) => {
+                elements.insert(index, parser.parse_next_node()?)
+            }
+            (Change::Remove(index), Children::Dictionary(entries)) => {
+                entries.remove(index);
+            }
+            (Change::Remove(index), Children::Array(elements)) => {
+                elements.remove(index);
+            }
+            (Change::Insert(_) | Change::Remove(_), Children::None) => {
+                unreachable!("only collections have children")
+            }
+        }
+        Ok(())
+    }
+
+    fn node<S: AsRef<str>>(&self, components: &[S]) -> Option<&Node> {
+        components
+            .iter()
+            .try_fold(&self.root, |node, component| match &node.children {
+                Children::Dictionary(entries) => entries
+                    .iter()
+                    .rfind(|e| e.key == component.as_ref())
+                    .map(|e| &e.value),
+                Children::Array(elements) => elements.get(key_path::index(component.as_ref())?),
+                Children::None => None,
+            })
+    }
+
+    fn replace_node(&self, node: &Node, value: &Value) -> Result<Edit, Error> {
+        Ok(Edit {
+            range: node.start..node.end,
+            text: self.value_fragment(value, self.line_indent(node.start))?,
+        })
+    }
+
+    fn insert_entry(
+        &self,
+        dict: &Node,
+        entries: &[Entry],
+        index: usize,
+        key: &str,
+        value: &Value,
+    ) -> Result<(Edit, Change), Error> {
+        let spans: Vec<_> = entries
+            .iter()
+            .map(|e| e.key_node.start..e.value.end)
+            .collect();
+        // Separate the new key and value in the same way as an existing entry.
+        let separator = match entries.first() {
+            Some(entry) => self
+                .trailing_whitespace(entry.key_node.end..entry.value.start)
+                .to_owned(),
+            None => String::new(),
+        };
+        self.insert_child(dict, &spans, index, |newline_and_indent, indent| {
+            let separator = if entries.is_empty() {
+                newline_and_indent
+            } else {
+                &separator
+            };
+            Ok(format!(
+                "<key>{}</key>{}{}",
+                escape(key),
+                separator,
+                self.value_fragment(value, indent)?
+            ))
+        })
+    }
+
+    /// Inserts a child into a collection at `index`. `child` is passed a newline followed by the
+    /// indentation of the child and the indentation alone.
+    fn insert_child<F>(
+        &self,
+        collection: &Node,
+        spans: &[Range<usize>],
+        index: usize,
+        child: F,
+    ) -> Result<(Edit, Change), Error>
+    where
+        F: FnOnce(&str, &str) -> Result<String, Error>,
+    {
+        let newline = self.newline();
+
+        if let Some(span) = spans.get(index) {
+            // Insert before the existing child, separated by the same whitespace.
+            let separator =
+                self.trailing_whitespace(self.previous_end(collection, spans, index)..span.start);
+            let indent = self.line_indent(span.start);
+            let text = child(&format!("{newline}{indent}"), indent)? + separator;
+            let edit = Edit {
+                range: span.start..span.start,
+                text,
+            };
+            return Ok((edit, Change::Insert(index)));
+        }
+
+        if let Some(span) = spans.last() {
+            // Append after the last child, separated by the same whitespace.
+            let last_index = spans.len() - 1;
+            let separator = self
+                .trailing_whitespace(self.previous_end(collection, spans, last_index)..span.start);
+            let indent = self.line_indent(span.start);
+            let text = separator.to_owned() + &child(&format!("{newline}{indent}"), indent)?;
+            let edit = Edit {
+                range: span.end..span.end,
+                text,
+            };
+            return Ok((edit, Change::Insert(index)));
+        }
+
+        // The collection is empty so indent the child one level deeper than the collection.
+        let collection_indent = self.line_indent(collection.start);
+        let indent = format!("{collection_indent}{}", self.indent_unit());
+        let newline_and_indent = format!("{newline}{indent}");
+        let child = child(&newline_and_indent, &indent)?;
+
+        if collection.content_start == collection.end {
+            // Expand a self-closing element.
+            let name = match collection.children {
+                Children::Dictionary(_) => "dict",
+                _ => "array",
+            };
+            let edit = Edit {
+                range: collection.start..collection.end,
+                text: format!(
+                    "<{name}>{newline_and_indent}{child}{newline}{collection_indent}</{name}>"
+                ),
+            };
+            return Ok((edit, Change::Replace));
+        }
+
+        let close_start = collection.start
+            + self.source[collection.start..collection.end]
+                .rfind("</")
+                .expect("non-empty element has a closing tag");
+        let whitespace = self.trailing_whitespace(collection.content_start..close_start);
+        let mut text = format!("{newline_and_indent}{child}");
+        if !whitespace.contains('\n') {
+            text.push_str(newline);
+            text.push_str(collection_indent);
+        }
+        let insert_at = close_start - whitespace.len();
+        let edit = Edit {
+            range: insert_at..insert_at,
+            text,
+        };
+        Ok((edit, Change::Insert(index)))
+    }
+
+    /// Serializes `value` as XML with every line after the first prefixed by `indent`.
+    fn value_fragment(&self, value: &Value, indent: &str) -> Result<String, Error> {
+        let unit = self.indent_unit();
+        let mut options = XmlWriteOptions::default().root_element(false);
+        if let Some(&c) = unit.as_bytes().first() {
+            options = options.indent(c, unit.len());
+        }
+
+        let mut xml = Vec::new();
+        value.to_writer_xml_with_options(&mut xml, &options)?;
+        let xml = String::from_utf8(xml).expect("xml writer output is utf-8");
+        Ok(xml.replace('\n', &format!("{}{indent}", self.newline())))
+    }
+
+    /// Returns the end of the child preceding `index` or of the collection's opening tag.
+    fn previous_end(&self, collection: &Node, spans: &[Range<usize>], index: usize) -> usize {
+        match index.checked_sub(1) {
+            Some(previous) => spans[previous].end,
+            None => collection.content_start,
+        }
+    }
+
+    /// Returns the whitespace at the end of the range.
+    fn trailing_whitespace(&self, range: Range<usize>) -> &str {
+        let text = &self.source[range];
+        &text[text.trim_end_matches(is_xml_whitespace).len()..]
+    }
+
+    /// Returns the whitespace at the start of the line containing `pos`.
+    fn line_indent(&self, pos: usize) -> &str {
+        let line_start = self.source[..pos].rfind('\n').map_or(0, |i| i + 1);
+        let line = &self.source[line_start..];
+        &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+    }
+
+    fn newline(&self) -> &'static str {
+        if self.source.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        }
+    }
+
+    /// Infers the indentation used for one level of nesting from the first collection with a
+    /// child on its own line. Defaults to a tab.
+    fn indent_unit(&self) -> &str {
+        let mut stack = vec![&self.root];
+        while let Some(node) = stack.pop() {
+            let first_child = match &node.children {
+                Children::Dictionary(entries) => entries.first().map(|e| &e.key_node),
+                Children::Array(elements) => elements.first(),
+                Children::None => None,
+            };
+            if let Some(child) = first_child {
+                let parent_indent = self.line_indent(node.start);
+                let child_indent = self.line_indent(child.start);
+                if let Some(unit) = child_indent.strip_prefix(parent_indent) {
+                    let single_char = unit.bytes().all(|b| Some(b) == unit.bytes().next());
+                    if !unit.is_empty()
+                        && single_char
+                        && self.source[..child.start].ends_with(child_indent)
+                    {
+                        return unit;
+                    }
+                }
+            }
+            match &node.children {
+                Children::Dictionary(entries) => {
+                    stack.extend(entries.iter().rev().map(|e| &e.value))
+                }
+                Children::Array(elements) => stack.extend(elements.iter().rev()),
+                Children::None => (),
+            }
+        }
+        "\t"
+    }
+}
+
+impl FromStr for XmlDocument {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<XmlDocument, Error> {
+        XmlDocument::parse(s.to_owned())
+    }
+}
+
+impl fmt::Debug for XmlDocument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("XmlDocument")
+            .field("value", &self.value)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for XmlDocument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn key_path_not_found() -> Error {
+    ErrorKind::KeyPathNotFound.without_position()
+}
+
+fn is_xml_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\r' | '\n')
+}
+
+/// Locates the elements of a document which has already been validated by `XmlReader`.
+struct NodeParser<'a> {
+    source: &'a str,
+    reader: EventReader<&'a [u8]>,
+    /// The offset in `source` at which `reader` starts.
+    offset: usize,
+}
+
+impl<'a> NodeParser<'a> {
+    fn new(source: &'a str, offset: usize) -> NodeParser<'a> {
+        let mut reader = EventReader::from_str(&source[offset..]);
+        let config = reader.config_mut();
+        config.trim_text(false);
+        config.check_end_names = true;
+        config.expand_empty_elements = false;
+        NodeParser {
+            source,
+            reader,
+            offset,
+        }
+    }
+
+    fn pos(&self) -> usize {
+        self.offset + self.reader.buffer_position() as usize
+    }
+
+    /// Returns the next start, self-closing or end element along with its start offset.
+    fn next_element(&mut self) -> Result<(usize, XmlEvent<'a>), Error> {
+        loop {
+            let start = self.pos();
+            let event = self
+                .reader
+                .read_event()
+                .map_err(|err| ErrorKind::from(err).with_byte_offset(start as u64))?;
+            match event {
+                XmlEvent::Start(_) | XmlEvent::Empty(_) | XmlEvent::End(_) => {
+                    return Ok((start, event))
+                }
+                XmlEvent::Eof => {
+                    return Err(ErrorKind::UnexpectedEof.with_byte_offset(start as u64))
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn parse_root(&mut self) -> Result<Node, Error> {
+        loop {
+            match self.next_element()? {
+                (_, XmlEvent::Start(tag)) if tag.local_name().as_ref() == b"plist" => (),
+                (start, XmlEvent::Start(tag)) => return self.parse_node(start, &tag, false),
+                (start, XmlEvent::Empty(tag)) => return self.parse_node(start, &tag, true),
+                (start, _) => {
+                    return Err(ErrorKind::InvalidXmlSyntax.with_byte_offset(start as u64))
+                }
+            }
+        }
+    }
+
+    /// Parses the next element along with any children.
+    fn parse_next_node(&mut self) -> Result<Node, Error> {
+        match self.next_element()? {
+            (start, XmlEvent::Start(tag)) => self.parse_node(start, &tag, false),
+            (start, XmlEvent::Empty(tag)) => self.parse_node(start, &tag, true),
+            (start, _) => Err(ErrorKind::InvalidXmlSyntax.with_byte_offset(start as u64)),
+        }
+    }
+
+    /// Parses the value following a dictionary key.
+    fn parse_entry(&mut self, key_node: Node) -> Result<Entry, Error> {
+        let key = match Value::from_events(XmlReader::new(
+            &self.source.as_bytes()[key_node.start..key_node.end],
+        ))? {
+            Value::String(key) => key,
+            _ => return Err(ErrorKind::InvalidXmlSyntax.with_byte_offset(key_node.start as u64)),
+        };
+        Ok(Entry {
+            key,
+            key_node,
+            value: self.parse_next_node()?,
+        })
+    }
+
+    fn parse_node(
+        &mut self,
+        start: usize,
+        tag: &BytesStart,
+        is_empty: bool,
+    ) -> Result<Node, Error> {
+        let content_start = self.pos();
+        let name = tag.local_name();
+        let mut children = match name.as_ref() {
+            b"dict" => Children::Dictionary(Vec::new()),
+            b"array" => Children::Array(Vec::new()),
+            _ => Children::None,
+        };
+
+        if !is_empty {
+            loop {
+                let (child_start, event) = self.next_element()?;
+                let (child_tag, child_is_empty) = match event {
+                    XmlEvent::End(_) => break,
+                    XmlEvent::Start(tag) => (tag, false),
+                    XmlEvent::Empty(tag) => (tag, true),
+                    _ => unreachable!("`next_element` only returns elements"),
+                };
+                let child = self.parse_node(child_start, &child_tag, child_is_empty)?;
+
+                match &mut children {
+                    Children::Dictionary(entries) => entries.push(self.parse_entry(child)?),
+                    Children::Array(elements) => elements.push(child),
+                    // Scalar elements never contain other elements.
+                    Children::None => (),
+                }
+            }
+        }
+
+        Ok(Node {
+            start,
+            end: self.pos(),
+            content_start,
+            children,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::Dictionary;
+
+    const INFO_PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <!-- Identity -->
+  <key>CFBundleIdentifier</key>
+  <string>com.example.app</string>
+  <key>CFBundleURLTypes</key>
+  <array>
+    <dict>
+      <key>CFBundleURLSchemes</key>
+      <array>
+        <string>example</string>
+      </array>
+    </dict>
+  </array>
+  <key>Icon</key>
+  <data>
+  AAAA
+  </data>
+  <key>Empty</key>
+  <dict/>
+</dict>
+</plist>
+"#;
+
+    fn doc() -> XmlDocument {
+        INFO_PLIST.parse().unwrap()
+    }
+
+    /// Checks that the nodes and value of an edited document match those of its parsed source.
+    fn assert_consistent(doc: &XmlDocument) {
+        let parsed: XmlDocument = doc.as_str().parse().unwrap();
+        assert_eq!(doc.root, parsed.root);
+        assert_eq!(doc.value, parsed.value);
+    }
+
+    #[test]
+    fn roundtrip_unmodified() {
+        assert_eq!(doc().as_str(), INFO_PLIST);
+
+        let source = fs::read_to_string("./tests/data/xml.plist").unwrap();
+        let doc: XmlDocument = source.parse().unwrap();
+        assert_eq!(doc.to_string(), source);
+        assert_eq!(
+            doc.as_value(),
+            &Value::from_file("./tests/data/xml.plist").unwrap()
+        );
+    }
+
+    #[test]
+    fn get() {
+        let doc = doc();
+        assert_eq!(
+            doc.get("CFBundleURLTypes.0.CFBundleURLSchemes.0"),
+            Some(&Value::from("example"))
+        );
+        assert_eq!(doc.get("CFBundleURLTypes.1"), None);
+        assert_eq!(doc.get("Icon"), Some(&Value::Data(vec![0, 0, 0])));
+        assert!(doc.get("").unwrap().as_dictionary().is_some());
+    }
+
+    #[test]
+    fn set_existing() {
+        let mut doc = doc();
+        let previous = doc.set("CFBundleIdentifier", "com.example.other").unwrap();
+
+        assert_eq!(previous, Some(Value::from("com.example.app")));
+        assert_eq!(
+            doc.as_str(),
+            INFO_PLIST.replace("com.example.app", "com.example.other")
+        );
+        assert_consistent(&doc);
+
+        let previous = doc.set("", Value::from(1)).unwrap();
+        assert!(previous.unwrap().as_dictionary().is_some());
+        assert_consistent(&doc);
+    }
+
+    #[test]
+    fn set_new_key() {
+        let mut doc = doc();
+        let mut dict = Dictionary::new();
+        dict.insert("Nested".to_owned(), vec![Value::from(1)].into());
+        doc.set("CFBundleURLTypes.0.Extra", dict).unwrap();
+
+        let expected = INFO_PLIST.replace(
+            "        <string>example</string>
+      </array>
+",
+            "        <string>example</string>
+      </array>
+      <key>Extra</key>
+      <dict>
+        <key>Nested</key>
+        <array>
+          <integer>1</integer>
+        </array>
+      </dict>
+",
+        );
+        assert_eq!(doc.as_str(), expected);
+        assert_consistent(&doc);
+    }
+
+    #[test]
+    fn set_in_empty_dictionary() {
+        let mut doc = doc();
+        doc.set("Empty.A", true).unwrap();
+
+        let expected = INFO_PLIST.replace(
+            "  <dict/>\n",
+            "  <dict>
+    <key>A</key>
+    <true/>
+  </dict>
+",
+        );
+        assert_eq!(doc.as_str(), expected);
+        assert_consistent(&doc);
+
+        doc.set("Empty.B", false).unwrap();
+        doc.set("Icon", Value::Array(Vec::new())).unwrap();
+        doc.insert("Icon.0", "a").unwrap();
+        assert_consistent(&doc);
+    }
+
+    #[test]
+    fn insert_into_array() {
+        let mut doc = doc();
+        doc.insert("CFBundleURLTypes.0.CFBundleURLSchemes.0", "first")
+            .unwrap();
+        doc.insert("CFBundleURLTypes.0.CFBundleURLSchemes.2", "last")
+            .unwrap();
+
+        let expected = INFO_PLIST.replace(
+            "        <string>example</string>\n",
+            "        <string>first</string>
+        <string>example</string>
+        <string>last</string>
+",
+        );
+        assert_eq!(doc.as_str(), expected);
+        assert_consistent(&doc);
+        assert!(doc
+            .insert("CFBundleURLTypes.0.CFBundleURLSchemes.4", "x")
+            .is_err());
+    }
+
+    #[test]
+    fn remove() {
+        let mut doc = doc();
+        let removed = doc.remove("CFBundleURLTypes").unwrap();
+
+        assert!(removed.unwrap().as_array().is_some());
+        assert_eq!(doc.remove("CFBundleURLTypes").unwrap(), None);
+        assert_eq!(doc.remove("Empty.Key").unwrap(), None);
+
+        let start = INFO_PLIST.find("  <key>CFBundleURLTypes").unwrap();
+        let end = INFO_PLIST.find("  <key>Icon").unwrap();
+        let expected = format!("{}{}", &INFO_PLIST[..start - 1], &INFO_PLIST[end - 1..]);
+        assert_eq!(doc.as_str(), expected);
+        assert_consistent(&doc);
+    }
+
+    #[test]
+    fn repeated_keys() {
+        let mut doc: XmlDocument = "<plist><dict>
+            <key>a</key><integer>1</integer>
+            <key>b</key><array><integer>2</integer></array>
+            <key>a</key><integer>3</integer>
+        </dict></plist>"
+            .parse()
+            .unwrap();
+
+        assert_eq!(doc.set("a", 4).unwrap(), Some(Value::from(3)));
+        assert_consistent(&doc);
+        assert_eq!(doc.remove("a").unwrap(), Some(Value::from(4)));
+        assert_eq!(doc.get("a"), Some(&Value::from(1)));
+        assert_consistent(&doc);
+        assert_eq!(doc.remove("b.0").unwrap(), Some(Value::from(2)));
+        assert_consistent(&doc);
+        assert_eq!(doc.remove("a").unwrap(), Some(Value::from(1)));
+        assert_consistent(&doc);
+    }
+
+    #[test]
+    fn errors() {
+        let mut doc = doc();
+        assert!(doc.set("CFBundleIdentifier.Child", 1).is_err());
+        assert!(doc.set("Missing.Child", 1).is_err());
+        assert!(doc.set("CFBundleURLTypes.1", 1).is_err());
+        assert!(doc.remove("").is_err());
+        assert!(doc.remove("Missing.Key").is_err());
+        assert!(doc.remove("CFBundleIdentifier.Child").is_err());
+        assert!("<plist><dict><key>a</key></dict></plist>"
+            .parse::<XmlDocument>()
+            .is_err());
+    }
+}